@@ -1,37 +1,93 @@
 use serde::Deserialize;
+use serde_json::Value;
 use std::fs;
 use tauri::{AppHandle, Manager};
 
+use crate::did::password_policy::PasswordPolicy;
 use crate::error::{CommandErrors, CommandResult};
 
 const DEFAULT_SN_API_URL: &str = "https://sn.buckyos.ai/kapi/sn";
 const CONFIG_FILENAME: &str = "config.json";
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 struct AppConfig {
     sn_host: Option<String>,
+    // 保留原始 JSON，在 `password_policy` 中单独解析，写错的策略不影响其他配置项
+    #[serde(default)]
+    password_policy: Option<Value>,
 }
 
-#[tauri::command]
-pub fn get_sn_api_host(app_handle: AppHandle) -> CommandResult<String> {
+fn read_app_config(app_handle: &AppHandle) -> CommandResult<AppConfig> {
     let config_dir = app_handle
         .path()
         .app_config_dir()
         .map_err(|e| CommandErrors::internal(format!("missing app config directory: {e}")))?;
     let path = config_dir.join(CONFIG_FILENAME);
     if !path.exists() {
-        return Ok(DEFAULT_SN_API_URL.to_string());
+        return Ok(AppConfig::default());
     }
 
     let raw = fs::read_to_string(&path)
-        .map_err(|e| CommandErrors::internal(format!("failed to read sn config: {e}")))?;
-    let parsed: AppConfig = serde_json::from_str(&raw)
-        .map_err(|e| CommandErrors::internal(format!("invalid sn config: {e}")))?;
+        .map_err(|e| CommandErrors::internal(format!("failed to read app config: {e}")))?;
+    serde_json::from_str(&raw)
+        .map_err(|e| CommandErrors::internal(format!("invalid app config: {e}")))
+}
 
-    let host = parsed
+#[tauri::command]
+pub fn get_sn_api_host(app_handle: AppHandle) -> CommandResult<String> {
+    let host = read_app_config(&app_handle)?
         .sn_host
         .map(|h| h.trim().to_string())
         .filter(|h| !h.is_empty())
         .unwrap_or_else(|| DEFAULT_SN_API_URL.to_string());
     Ok(host)
 }
+
+/// Policy applied to new DID passwords, defaults unless overridden by the
+/// `password_policy` section of the app config. An unreadable config falls
+/// back to the defaults so it cannot lock the user out of the vault.
+pub fn password_policy(app_handle: &AppHandle) -> PasswordPolicy {
+    match read_app_config(app_handle) {
+        Ok(config) => parse_password_policy(config.password_policy),
+        Err(err) => {
+            log::warn!("{}, using default password policy", err.message());
+            PasswordPolicy::default()
+        }
+    }
+}
+
+fn parse_password_policy(raw: Option<Value>) -> PasswordPolicy {
+    let Some(raw) = raw else {
+        return PasswordPolicy::default();
+    };
+    serde_json::from_value(raw).unwrap_or_else(|err| {
+        log::warn!("invalid password_policy in app config: {err}, using default password policy");
+        PasswordPolicy::default()
+    })
+}
+
+#[tauri::command]
+pub fn get_password_policy(app_handle: AppHandle) -> CommandResult<PasswordPolicy> {
+    Ok(password_policy(&app_handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_password_policy_keeps_other_settings() {
+        let config: AppConfig = serde_json::from_str(
+            r#"{"sn_host": "https://sn.example.com", "password_policy": {"min_length": "twelve"}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.sn_host.as_deref(), Some("https://sn.example.com"));
+        assert_eq!(
+            parse_password_policy(config.password_policy),
+            PasswordPolicy::default()
+        );
+
+        let custom = parse_password_policy(Some(serde_json::json!({"min_length": 16})));
+        assert_eq!(custom.min_length, 16);
+    }
+}
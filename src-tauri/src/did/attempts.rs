@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::crypto::{decrypt_mnemonic, SeedSecret};
+use super::store::StoredDid;
+use crate::error::{CommandErrors, CommandResult};
+
+/// Wrong passwords accepted before backoff starts.
+pub const FREE_ATTEMPTS: u32 = 3;
+const BASE_DELAY_SECS: u64 = 30;
+const MAX_DELAY_SECS: u64 = 60 * 60;
+
+/// Consecutive wrong passwords for one DID, persisted with its record.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PasswordAttempts {
+    pub failed: u32,
+    pub last_failed_at_ms: u64,
+}
+
+impl PasswordAttempts {
+    pub fn is_clean(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// Wait imposed after `failed` wrong passwords: none for the first
+    /// [`FREE_ATTEMPTS`], then 30s doubling with every failure, up to an hour.
+    pub fn backoff_secs(failed: u32) -> u64 {
+        if failed < FREE_ATTEMPTS {
+            return 0;
+        }
+        let doublings = (failed - FREE_ATTEMPTS).min(16);
+        (BASE_DELAY_SECS << doublings).min(MAX_DELAY_SECS)
+    }
+
+    /// Seconds until the next attempt is allowed; 0 when it is allowed now.
+    pub fn retry_after_secs(&self, now_ms: u64) -> u64 {
+        let delay_ms = Self::backoff_secs(self.failed) * 1000;
+        // 时钟回拨时按刚失败处理，不能借此跳过等待
+        let elapsed_ms = now_ms.saturating_sub(self.last_failed_at_ms);
+        delay_ms.saturating_sub(elapsed_ms).div_ceil(1000)
+    }
+
+    pub fn check(&self, now_ms: u64) -> CommandResult<()> {
+        match self.retry_after_secs(now_ms) {
+            0 => Ok(()),
+            retry_after_secs => Err(CommandErrors::TooManyAttempts { retry_after_secs }),
+        }
+    }
+
+    pub fn record_failure(&mut self, now_ms: u64) {
+        self.failed = self.failed.saturating_add(1);
+        self.last_failed_at_ms = now_ms;
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Decrypts the seed of `record` unless it is still backing off. A wrong
+/// password is counted on `record`, a correct one clears the count; either
+/// way `record` may change (the seed is also re-wrapped when its KDF is
+/// outdated) and callers keep it on `InvalidPassword` to persist the count.
+pub fn unlock_with_password(
+    record: &mut StoredDid,
+    password: &str,
+    now_ms: u64,
+) -> CommandResult<SeedSecret> {
+    let seed = record.seed.as_mut().ok_or(CommandErrors::WatchOnly)?;
+    record.password_attempts.check(now_ms)?;
+    match decrypt_mnemonic(password, seed) {
+        Ok((secret, _upgraded)) => {
+            record.password_attempts = PasswordAttempts::default();
+            Ok(secret)
+        }
+        Err(CommandErrors::InvalidPassword) => {
+            record.password_attempts.record_failure(now_ms);
+            Err(CommandErrors::InvalidPassword)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Whether `record` reached its own wipe threshold.
+pub fn wipe_due(record: &StoredDid) -> bool {
+    matches!(record.wipe_after_failures, Some(limit) if record.password_attempts.failed >= limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::crypto::encrypt_mnemonic;
+    use crate::did::domain::{BtcNetwork, MnemonicLanguage, WalletCollection};
+    use bip39::Mnemonic;
+
+    fn record(password: &str) -> StoredDid {
+        let mnemonic = Mnemonic::parse(
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+        )
+        .unwrap();
+        StoredDid {
+            id: "did:bk:1:01J0000000000000000000000".to_string(),
+            nickname: "alice".to_string(),
+            seed: Some(encrypt_mnemonic(password, &mnemonic, "").unwrap()),
+            mnemonic_language: MnemonicLanguage::English,
+            btc_network: BtcNetwork::Mainnet,
+            wallets: WalletCollection::default(),
+            sn_status: None,
+            password_attempts: PasswordAttempts::default(),
+            wipe_after_failures: Some(5),
+            backup_status: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_resets_on_success() {
+        assert_eq!(PasswordAttempts::backoff_secs(2), 0);
+        assert_eq!(PasswordAttempts::backoff_secs(3), 30);
+        assert_eq!(PasswordAttempts::backoff_secs(5), 120);
+        assert_eq!(PasswordAttempts::backoff_secs(40), MAX_DELAY_SECS);
+
+        let mut did = record("right");
+        let mut now = 1_000_000;
+        for _ in 0..FREE_ATTEMPTS {
+            assert!(matches!(
+                unlock_with_password(&mut did, "wrong", now),
+                Err(CommandErrors::InvalidPassword)
+            ));
+        }
+        // 退避期间连正确密码也不尝试
+        assert!(matches!(
+            unlock_with_password(&mut did, "right", now + 1_000),
+            Err(CommandErrors::TooManyAttempts {
+                retry_after_secs: 29
+            })
+        ));
+        assert_eq!(did.password_attempts.failed, FREE_ATTEMPTS);
+        assert!(matches!(
+            unlock_with_password(&mut did, "right", now - 60_000),
+            Err(CommandErrors::TooManyAttempts {
+                retry_after_secs: 30
+            })
+        ));
+
+        now += 30_000;
+        assert!(unlock_with_password(&mut did, "wrong", now).is_err());
+        assert_eq!(did.password_attempts.retry_after_secs(now), 60);
+        assert!(!wipe_due(&did));
+
+        now += 60_000;
+        let secret = unlock_with_password(&mut did, "right", now).unwrap();
+        assert!(secret.phrase.starts_with("legal winner"));
+        assert!(did.password_attempts.is_clean());
+
+        did.password_attempts.failed = 5;
+        assert!(wipe_due(&did));
+    }
+}
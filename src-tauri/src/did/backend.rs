@@ -0,0 +1,217 @@
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::snapshot::{
+    list_snapshots, read_snapshot, remove_snapshot, write_atomic, write_snapshot, VaultSnapshot,
+};
+use crate::error::{CommandErrors, CommandResult};
+
+/// File name of the vault store inside the app data directory.
+pub const STORE_FILE: &str = "wallet.store";
+const SNAPSHOT_DIR: &str = "vault-snapshots";
+
+/// Persistence behind [`super::store::VaultHandle`]: a small JSON key/value
+/// map (the vault and its migration backups) that is always written as a
+/// whole, plus an optional snapshot history.
+pub trait VaultBackend {
+    /// Reads `key` from the persisted map. `VaultCorrupted` when the map
+    /// itself cannot be parsed.
+    fn get(&self, key: &str) -> CommandResult<Option<Value>>;
+
+    /// Stores `entries` next to the untouched keys in one durable write.
+    fn set(&self, entries: Vec<(String, Value)>) -> CommandResult<()>;
+
+    /// Deletes `keys` from the persisted map in one durable write.
+    fn remove(&self, keys: &[String]) -> CommandResult<()>;
+
+    /// Keeps `vault` for later recovery. Backends without history drop it.
+    fn write_snapshot(&self, _vault: &Value) -> CommandResult<()> {
+        Ok(())
+    }
+
+    /// Snapshots, newest first.
+    fn list_snapshots(&self) -> CommandResult<Vec<VaultSnapshot>> {
+        Ok(Vec::new())
+    }
+
+    fn read_snapshot(&self, _id: &str) -> CommandResult<Value> {
+        Err(CommandErrors::not_found("snapshot_not_found"))
+    }
+
+    fn remove_snapshot(&self, _id: &str) -> CommandResult<()> {
+        Ok(())
+    }
+}
+
+/// Plain JSON file in the same layout as the app store, so it can point at
+/// the app data directory: `<dir>/wallet.store` and `<dir>/vault-snapshots`.
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    path: PathBuf,
+    snapshot_dir: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(data_dir: impl AsRef<Path>) -> Self {
+        let data_dir = data_dir.as_ref();
+        Self {
+            path: data_dir.join(STORE_FILE),
+            snapshot_dir: data_dir.join(SNAPSHOT_DIR),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The whole persisted map; `None` when the file does not exist yet.
+    pub fn read_map(&self) -> CommandResult<Option<Map<String, Value>>> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(CommandErrors::store_unavailable(format!(
+                    "read {}: {err}",
+                    self.path.display()
+                )))
+            }
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| CommandErrors::vault_corrupted(e.to_string()))
+    }
+
+    /// Replaces the file with `map` (temp file + fsync + rename).
+    pub fn write_map(&self, map: &Map<String, Value>) -> CommandResult<()> {
+        let bytes = serde_json::to_vec_pretty(map)
+            .map_err(|e| CommandErrors::vault_corrupted(e.to_string()))?;
+        write_atomic(&self.path, &bytes)
+    }
+}
+
+impl VaultBackend for FileBackend {
+    fn get(&self, key: &str) -> CommandResult<Option<Value>> {
+        Ok(self.read_map()?.and_then(|mut map| map.remove(key)))
+    }
+
+    fn set(&self, entries: Vec<(String, Value)>) -> CommandResult<()> {
+        // 文件损坏时只能整体覆盖，其余 key 已无法保留
+        let mut map = self.read_map().ok().flatten().unwrap_or_default();
+        map.extend(entries);
+        self.write_map(&map)
+    }
+
+    fn remove(&self, keys: &[String]) -> CommandResult<()> {
+        let Some(mut map) = self.read_map()? else {
+            return Ok(());
+        };
+        for key in keys {
+            map.remove(key);
+        }
+        self.write_map(&map)
+    }
+
+    fn write_snapshot(&self, vault: &Value) -> CommandResult<()> {
+        write_snapshot(&self.snapshot_dir, vault).map(|_| ())
+    }
+
+    fn list_snapshots(&self) -> CommandResult<Vec<VaultSnapshot>> {
+        list_snapshots(&self.snapshot_dir)
+    }
+
+    fn read_snapshot(&self, id: &str) -> CommandResult<Value> {
+        read_snapshot(&self.snapshot_dir, id)
+    }
+
+    fn remove_snapshot(&self, id: &str) -> CommandResult<()> {
+        remove_snapshot(&self.snapshot_dir, id)
+    }
+}
+
+/// Keeps the map in memory only, without snapshots. Meant for unit tests and
+/// throwaway vaults.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    entries: Mutex<Map<String, Value>>,
+}
+
+impl VaultBackend for MemoryBackend {
+    fn get(&self, key: &str) -> CommandResult<Option<Value>> {
+        Ok(self
+            .entries
+            .lock()
+            .expect("memory backend lock poisoned")
+            .get(key)
+            .cloned())
+    }
+
+    fn set(&self, entries: Vec<(String, Value)>) -> CommandResult<()> {
+        self.entries
+            .lock()
+            .expect("memory backend lock poisoned")
+            .extend(entries);
+        Ok(())
+    }
+
+    fn remove(&self, keys: &[String]) -> CommandResult<()> {
+        let mut entries = self.entries.lock().expect("memory backend lock poisoned");
+        for key in keys {
+            entries.remove(key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use ulid::Ulid;
+
+    #[test]
+    fn test_file_backend_keeps_other_keys() {
+        let dir = std::env::temp_dir().join(format!("vault-backend-{}", Ulid::new()));
+        let backend = FileBackend::new(&dir);
+        assert_eq!(backend.get("vault").unwrap(), None);
+
+        backend
+            .set(vec![(
+                "vault.v1.backup".to_string(),
+                json!({ "version": 1 }),
+            )])
+            .unwrap();
+        backend
+            .set(vec![("vault".to_string(), json!({ "version": 3 }))])
+            .unwrap();
+        let reopened = FileBackend::new(&dir);
+        assert_eq!(
+            reopened.get("vault").unwrap(),
+            Some(json!({ "version": 3 }))
+        );
+        assert_eq!(
+            reopened.get("vault.v1.backup").unwrap(),
+            Some(json!({ "version": 1 }))
+        );
+        reopened.remove(&["vault.v1.backup".to_string()]).unwrap();
+        assert_eq!(reopened.get("vault.v1.backup").unwrap(), None);
+        assert!(reopened.get("vault").unwrap().is_some());
+
+        reopened.write_snapshot(&json!({ "version": 3 })).unwrap();
+        let snapshots = reopened.list_snapshots().unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(
+            reopened.read_snapshot(&snapshots[0].id).unwrap(),
+            json!({ "version": 3 })
+        );
+
+        fs::write(reopened.path(), b"{ not json").unwrap();
+        assert!(matches!(
+            reopened.get("vault"),
+            Err(CommandErrors::VaultCorrupted(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::crypto::{seal, unseal};
+use super::store::{parse_vault, EncryptedSeed, KdfParams, StoredDid, VaultStore};
+use crate::error::{CommandErrors, CommandResult};
+
+pub const BACKUP_FORMAT: &str = "buckyos-vault-backup";
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Portable, password-encrypted copy of selected DIDs. The payload is a
+/// [`VaultStore`] so imports go through the same migrations as the vault;
+/// seeds inside stay encrypted with their own vault passwords.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupFile {
+    pub format: String,
+    pub version: u32,
+    pub created_at: u64,
+    #[serde(flatten)]
+    pub payload: EncryptedSeed,
+}
+
+fn backup_aad(format: &str, version: u32, created_at: u64) -> Vec<u8> {
+    format!("{format}:{version}:{created_at}").into_bytes()
+}
+
+pub fn seal_backup(password: &str, dids: &[StoredDid]) -> CommandResult<String> {
+    let mut vault = VaultStore::default();
+    vault.dids = dids.to_vec();
+    let plaintext =
+        serde_json::to_vec(&vault).map_err(|e| CommandErrors::internal(e.to_string()))?;
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| CommandErrors::internal(e.to_string()))?
+        .as_secs();
+    let aad = backup_aad(BACKUP_FORMAT, BACKUP_VERSION, created_at);
+    let payload = seal(password, &plaintext, &aad, KdfParams::recommended())?;
+
+    let file = BackupFile {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        created_at,
+        payload,
+    };
+    serde_json::to_string_pretty(&file).map_err(|e| CommandErrors::internal(e.to_string()))
+}
+
+// 备份文件不可信：KDF 参数超过生成备份时使用的 `KdfParams::recommended()` 就拒绝，
+// 避免打开一个构造过的文件就耗尽内存或 CPU
+fn check_backup_kdf(kdf: KdfParams) -> CommandResult<()> {
+    let within_bounds = match (kdf, KdfParams::recommended()) {
+        (
+            KdfParams::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            },
+            KdfParams::Argon2id {
+                memory_kib: max_memory_kib,
+                iterations: max_iterations,
+                parallelism: max_parallelism,
+            },
+        ) => {
+            memory_kib <= max_memory_kib
+                && iterations <= max_iterations
+                && parallelism <= max_parallelism
+        }
+        _ => false,
+    };
+    if within_bounds {
+        Ok(())
+    } else {
+        Err(CommandErrors::invalid_backup("unsupported kdf parameters"))
+    }
+}
+
+pub fn open_backup(password: &str, content: &str) -> CommandResult<Vec<StoredDid>> {
+    let file: BackupFile =
+        serde_json::from_str(content).map_err(|e| CommandErrors::invalid_backup(e.to_string()))?;
+    if file.format != BACKUP_FORMAT {
+        return Err(CommandErrors::invalid_backup("unknown backup format"));
+    }
+    if file.version != BACKUP_VERSION {
+        return Err(CommandErrors::invalid_backup(format!(
+            "unsupported backup version {}",
+            file.version
+        )));
+    }
+
+    check_backup_kdf(file.payload.kdf)?;
+
+    let aad = backup_aad(&file.format, file.version, file.created_at);
+    let plaintext = unseal(password, &file.payload, &aad).map_err(|err| match err {
+        CommandErrors::VaultCorrupted(reason) => CommandErrors::invalid_backup(reason),
+        other => other,
+    })?;
+    let raw = serde_json::from_slice(&plaintext)
+        .map_err(|e| CommandErrors::invalid_backup(e.to_string()))?;
+    let (vault, _) = parse_vault(raw)?;
+    Ok(vault.dids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::crypto::encrypt_mnemonic;
+    use crate::did::domain::{BtcNetwork, MnemonicLanguage, SnStatusInfo, WalletCollection};
+    use bip39::Mnemonic;
+
+    fn sample_did() -> StoredDid {
+        let mnemonic = Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        StoredDid {
+            id: "did:bk:1:01J0000000000000000000000".to_string(),
+            nickname: "alice".to_string(),
+            seed: Some(encrypt_mnemonic("vault_pw", &mnemonic, "").unwrap()),
+            mnemonic_language: MnemonicLanguage::English,
+            btc_network: BtcNetwork::Mainnet,
+            wallets: WalletCollection::default(),
+            sn_status: Some(SnStatusInfo {
+                username: Some("alice".to_string()),
+                zone_config: None,
+            }),
+            password_attempts: Default::default(),
+            wipe_after_failures: None,
+            backup_status: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_backup_round_trip() {
+        let did = sample_did();
+        let content = seal_backup("backup_pw", std::slice::from_ref(&did)).unwrap();
+
+        let restored = open_backup("backup_pw", &content).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].id, did.id);
+        assert_eq!(restored[0].nickname, "alice");
+        assert_eq!(
+            restored[0].sn_status.as_ref().unwrap().username.as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            restored[0].seed.as_ref().unwrap().cipher_hex,
+            did.seed.as_ref().unwrap().cipher_hex
+        );
+
+        assert!(matches!(
+            open_backup("wrong_pw", &content),
+            Err(CommandErrors::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_backup_is_rejected() {
+        let content = seal_backup("backup_pw", &[sample_did()]).unwrap();
+        let mut file: BackupFile = serde_json::from_str(&content).unwrap();
+        file.created_at += 1;
+        let tampered = serde_json::to_string(&file).unwrap();
+        assert!(open_backup("backup_pw", &tampered).is_err());
+
+        assert!(matches!(
+            open_backup("backup_pw", "{}"),
+            Err(CommandErrors::InvalidBackup(_))
+        ));
+    }
+
+    #[test]
+    fn test_oversized_kdf_params_are_rejected_before_unsealing() {
+        let content = seal_backup("backup_pw", &[sample_did()]).unwrap();
+        let mut file: BackupFile = serde_json::from_str(&content).unwrap();
+        file.payload.kdf = KdfParams::Argon2id {
+            memory_kib: 4 * 1024 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        };
+        let oversized = serde_json::to_string(&file).unwrap();
+        assert!(matches!(
+            open_backup("backup_pw", &oversized),
+            Err(CommandErrors::InvalidBackup(reason)) if reason == "unsupported kdf parameters"
+        ));
+
+        file.payload.kdf = KdfParams::Pbkdf2Sha256 {
+            iterations: u32::MAX,
+        };
+        let legacy = serde_json::to_string(&file).unwrap();
+        assert!(matches!(
+            open_backup("backup_pw", &legacy),
+            Err(CommandErrors::InvalidBackup(_))
+        ));
+    }
+}
@@ -1,32 +1,62 @@
-use bip39::{Language, Mnemonic};
+use bip39::Mnemonic;
+use bitcoin::Address;
 use rand::{rngs::OsRng, RngCore};
 use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
-use tauri::AppHandle;
+use std::str::FromStr;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
 
 use crate::error::{CommandErrors, CommandResult};
 
-use super::crypto::{decrypt_mnemonic, encrypt_mnemonic};
-use super::domain::{BtcAddressType, DidInfo, SnStatusInfo};
+use super::attempts::{now_ms, unlock_with_password, wipe_due, PasswordAttempts};
+use super::backend::VaultBackend;
+use super::backup::{open_backup, seal_backup};
+use super::crypto::{encrypt_mnemonic, SeedSecret};
+use super::derive::{
+    derive_btc_address, derive_btc_address_from_xpub, derive_eth_address, normalize_eth_address,
+    parse_account_xpub, SeedCtx,
+};
+use super::descriptor::export_account_xpub;
+use super::discovery::{discover_btc_wallets, DiscoveryLimits, ElectrumClient};
+use super::domain::{
+    AccountXpub, AddressChain, BtcAddress, BtcAddressType, BtcNetwork, BuckyIdentity, ChainAddress,
+    DidInfo, InvalidMnemonicWord, MnemonicLanguage, SignedPsbt, SnStatusInfo, WalletCollection,
+};
 use super::identity::{derive_wallets_with_requests, DidDerivationPlan, WalletRequest};
-use super::store::{load_vault, new_did_id, open_store, save_vault, StoredDid};
+use super::message::{sign_message, verify_message};
+use super::mnemonic::{complete_word, detect_language, mnemonic_entropy, parse_mnemonic};
+use super::psbt::sign as sign_psbt_with_seed;
+use super::repair::{repair_candidates, MnemonicRepair};
+use super::session::{
+    emit_locked, emit_unlocked, SessionInfo, SessionManager, DEFAULT_IDLE_TIMEOUT_SECS,
+};
+use super::slip39::{combine_mnemonics, split_mnemonics};
+use super::store::{new_did_id, parse_vault, StoredDid, VaultHandle, VaultService, VaultStore};
+use super::verification::BackupStatus;
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use secrecy::{ExposeSecret, SecretString};
 use std::time::{SystemTime, UNIX_EPOCH};
-
-#[cfg(test)]
-use super::derive::{derive_eth_address, SeedCtx};
+use zeroize::Zeroizing;
 
 #[derive(Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum WalletExtensionKind {
     Btc {
         address_type: BtcAddressType,
+        #[serde(default)]
+        account: u32,
+        #[serde(default)]
+        chain: AddressChain,
         #[serde(default = "default_count")]
         count: u32,
     },
     Eth {
+        #[serde(default)]
+        account: u32,
+        #[serde(default)]
+        chain: AddressChain,
         #[serde(default = "default_count")]
         count: u32,
     },
@@ -40,11 +70,127 @@ fn default_count() -> u32 {
     1
 }
 
+/// Account-level extended public key of a watch-only DID and how many receive
+/// addresses to derive from it.
+#[derive(Deserialize)]
+pub struct WatchOnlyXpub {
+    pub address_type: BtcAddressType,
+    /// BIP44 account the key was exported for.
+    #[serde(default)]
+    pub account: u32,
+    pub xpub: String,
+    #[serde(default = "default_count")]
+    pub count: u32,
+}
+
+// 读写 vault 的命令都拆成两层：`xxx_in` 对任意 VaultBackend 工作，`did_cli` 与单元
+// 测试直接调用；`#[tauri::command]` 只负责取出托管的 VaultService 并发送 session 事件。
+// 所有读写都经过 VaultHandle 的事务，并发调用按顺序执行。
+
+/// Returns the decrypted seed of DID `did_id`: from `password` when given,
+/// otherwise from its unlocked session. Passwords go through the attempt
+/// limits; a counted failure is kept even though the transaction fails, and a
+/// DID that reached its wipe threshold is deleted. Watch-only DIDs fail with
+/// `WatchOnly`.
+fn unlock_seed(
+    vault: &mut VaultStore,
+    sessions: &SessionManager,
+    did_id: &str,
+    password: Option<&str>,
+) -> CommandResult<SeedSecret> {
+    let position = vault
+        .dids
+        .iter()
+        .position(|did| did.id == did_id)
+        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+    if vault.dids[position].is_watch_only() {
+        return Err(CommandErrors::WatchOnly);
+    }
+    let Some(password) = password else {
+        return sessions.secret(did_id).ok_or(CommandErrors::SessionLocked);
+    };
+
+    match unlock_with_password(&mut vault.dids[position], password, now_ms()) {
+        Ok(secret) => Ok(secret),
+        Err(CommandErrors::InvalidPassword) => {
+            let wiped = wipe_due(&vault.dids[position]);
+            if wiped {
+                wipe_did(vault, sessions, position);
+            }
+            vault.keep_on_error();
+            Err(if wiped {
+                CommandErrors::not_found("wallet_wiped")
+            } else {
+                CommandErrors::InvalidPassword
+            })
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn wipe_did(vault: &mut VaultStore, sessions: &SessionManager, position: usize) {
+    let record = vault.dids.remove(position);
+    if vault.active_did.as_deref() == Some(record.id.as_str()) {
+        vault.active_did = None;
+    }
+    sessions.lock(Some(&record.id));
+    vault.forget_did(&record.id);
+    log::warn!(
+        "{} wiped after {} failed password attempts",
+        record.id,
+        record.password_attempts.failed
+    );
+}
+
+/// Parses user-entered words in `language`, or in the wordlist they belong
+/// to when no language is given.
+fn parse_mnemonic_words(
+    words: &[String],
+    language: Option<MnemonicLanguage>,
+) -> CommandResult<Mnemonic> {
+    let phrase = SecretString::new(words.join(" "));
+    let mnemonic = match language {
+        Some(language) => Mnemonic::parse_in(language.into(), phrase.expose_secret().as_str())?,
+        None => parse_mnemonic(phrase.expose_secret())?,
+    };
+    Ok(mnemonic)
+}
+
+/// Entropy size in bits for `word_count` words or `strength` bits; 128 bits
+/// (12 words) when neither is given.
+fn mnemonic_strength(word_count: Option<usize>, strength: Option<usize>) -> CommandResult<usize> {
+    let bits = match (word_count, strength) {
+        (Some(words), Some(bits)) if words * 32 / 3 != bits => {
+            return Err(CommandErrors::invalid_mnemonic(format!(
+                "word count {words} does not match strength {bits}"
+            )));
+        }
+        (Some(words), _) => match words {
+            12 | 15 | 18 | 21 | 24 => words * 32 / 3,
+            _ => return Err(bip39::Error::BadWordCount(words).into()),
+        },
+        (None, Some(bits)) => match bits {
+            128 | 160 | 192 | 224 | 256 => bits,
+            _ => return Err(bip39::Error::BadEntropyBitCount(bits).into()),
+        },
+        (None, None) => 128,
+    };
+    Ok(bits)
+}
+
+/// Generates a mnemonic of `word_count` words (12/15/18/21/24) or `strength`
+/// bits of entropy (128–256); both may be given if they agree.
 #[tauri::command]
-pub fn generate_mnemonic() -> CommandResult<Vec<String>> {
-    let mut entropy = [0u8; 16]; // 128 bits for 12 words
-    OsRng.fill_bytes(&mut entropy);
-    let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy)?;
+pub fn generate_mnemonic(
+    language: Option<MnemonicLanguage>,
+    word_count: Option<usize>,
+    strength: Option<usize>,
+) -> CommandResult<Vec<String>> {
+    let bits = mnemonic_strength(word_count, strength)?;
+    let mut buffer = Zeroizing::new([0u8; 32]);
+    let entropy = &mut buffer[..bits / 8];
+    OsRng.fill_bytes(entropy);
+    let mnemonic = Mnemonic::from_entropy_in(language.unwrap_or_default().into(), entropy)?;
     Ok(mnemonic
         .to_string()
         .split_whitespace()
@@ -52,32 +198,97 @@ pub fn generate_mnemonic() -> CommandResult<Vec<String>> {
         .collect())
 }
 
+/// Suggestions returned per mistyped or partially typed word.
+const MAX_WORD_SUGGESTIONS: usize = 8;
+
+/// Every non-empty word of `words` missing from the wordlist, with the words
+/// it may have been meant as.
 #[tauri::command]
-pub fn validate_mnemonic_words(words: Vec<String>) -> CommandResult<Option<String>> {
-    for word in words {
-        let trimmed = word.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if Language::English.find_word(trimmed).is_none() {
-            return Ok(Some(trimmed.to_string()));
+pub fn validate_mnemonic_words(words: Vec<String>) -> CommandResult<Vec<InvalidMnemonicWord>> {
+    // 以匹配单词最多的词表为准，其余词表的单词视为无效
+    let language = detect_language(&words);
+    let invalid = words
+        .iter()
+        .enumerate()
+        .map(|(index, word)| (index, word.trim()))
+        .filter(|(_, word)| !word.is_empty() && language.find_word(word).is_none())
+        .map(|(index, word)| InvalidMnemonicWord {
+            position: index + 1,
+            word: word.to_string(),
+            suggestions: complete_word(language, word, MAX_WORD_SUGGESTIONS)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        })
+        .collect();
+    Ok(invalid)
+}
+
+/// Wordlist words `prefix` may be completed to, for word-by-word entry.
+#[tauri::command]
+pub fn complete_mnemonic_word(
+    prefix: String,
+    language: Option<MnemonicLanguage>,
+) -> CommandResult<Vec<String>> {
+    let language = language.unwrap_or_default().into();
+    Ok(complete_word(language, &prefix, MAX_WORD_SUGGESTIONS)
+        .into_iter()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Checksum-passing corrections of `words`, see [`repair_candidates`]. With
+/// `expected_did`, candidates deriving that Bucky DID (with `passphrase`)
+/// are listed first.
+#[tauri::command]
+pub fn suggest_mnemonic_repairs(
+    words: Vec<String>,
+    language: Option<MnemonicLanguage>,
+    expected_did: Option<String>,
+    passphrase: Option<String>,
+) -> CommandResult<Vec<MnemonicRepair>> {
+    let language = language
+        .map(Into::into)
+        .unwrap_or_else(|| detect_language(&words));
+    let mut candidates = repair_candidates(&words, language)?;
+
+    if let Some(expected_did) = expected_did.map(|did| did.trim().to_string()) {
+        let passphrase = SecretString::new(passphrase.unwrap_or_default());
+        let requests = vec![WalletRequest::bucky(1)];
+        for candidate in candidates.iter_mut() {
+            let mnemonic = Mnemonic::parse_in(language, candidate.words.join(" "))?;
+            let wallets = derive_wallets_with_requests(
+                &mnemonic,
+                passphrase.expose_secret(),
+                &requests,
+                None,
+            )?;
+            let did = wallets
+                .bucky
+                .entries
+                .first()
+                .map(|entry| entry.did.as_str());
+            candidate.matches_expected_did = Some(did == Some(expected_did.as_str()));
         }
+        candidates.sort_by_key(|candidate| candidate.matches_expected_did != Some(true));
     }
-    Ok(None)
+    Ok(candidates)
 }
 
 #[tauri::command]
-pub fn derive_bucky_public_key(mnemonic_words: Vec<String>) -> CommandResult<Value> {
+pub fn derive_bucky_public_key(
+    mnemonic_words: Vec<String>,
+    passphrase: Option<String>,
+) -> CommandResult<Value> {
     if mnemonic_words.is_empty() {
         return Err(CommandErrors::MnemonicRequired);
     }
 
-    let decrypted = mnemonic_words.join(" ");
-    let secret_phrase = SecretString::new(decrypted);
-    let mnemonic = Mnemonic::parse_in(Language::English, secret_phrase.expose_secret())?;
-    drop(secret_phrase);
+    let mnemonic = parse_mnemonic_words(&mnemonic_words, None)?;
+    let passphrase = SecretString::new(passphrase.unwrap_or_default());
     let requests = vec![WalletRequest::bucky(1)];
-    let wallets = derive_wallets_with_requests(&mnemonic, "", &requests, None)?;
+    let wallets =
+        derive_wallets_with_requests(&mnemonic, passphrase.expose_secret(), &requests, None)?;
 
     wallets
         .bucky
@@ -89,79 +300,316 @@ pub fn derive_bucky_public_key(mnemonic_words: Vec<String>) -> CommandResult<Val
 
 #[tauri::command]
 pub fn create_did(
-    app_handle: AppHandle,
+    vault: State<'_, VaultService>,
+    nickname: String,
+    password: String,
+    mnemonic_words: Vec<String>,
+    passphrase: Option<String>,
+    language: Option<MnemonicLanguage>,
+    btc_network: Option<BtcNetwork>,
+) -> CommandResult<DidInfo> {
+    create_did_in(
+        vault.handle(),
+        nickname,
+        password,
+        mnemonic_words,
+        passphrase,
+        language,
+        btc_network,
+    )
+}
+
+pub fn create_did_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
     nickname: String,
     password: String,
     mnemonic_words: Vec<String>,
+    passphrase: Option<String>,
+    language: Option<MnemonicLanguage>,
+    btc_network: Option<BtcNetwork>,
 ) -> CommandResult<DidInfo> {
-    let mnemonic_phrase = mnemonic_words.join(" ");
-    let mnemonic = Mnemonic::parse_in(Language::English, &mnemonic_phrase)?;
+    store.password_policy().check(&password)?;
+    let mnemonic = parse_mnemonic_words(&mnemonic_words, language)?;
+    let passphrase = SecretString::new(passphrase.unwrap_or_default());
 
     let requests = DidDerivationPlan::default_requests();
-    let wallets = derive_wallets_with_requests(&mnemonic, "", &requests, None)?;
+    let wallets =
+        derive_wallets_with_requests(&mnemonic, passphrase.expose_secret(), &requests, None)?;
 
-    let encrypted_seed = encrypt_mnemonic(&password, &mnemonic)?;
+    let encrypted_seed = encrypt_mnemonic(&password, &mnemonic, passphrase.expose_secret())?;
 
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
+    store.transaction(|vault| {
+        if vault
+            .dids
+            .iter()
+            .any(|did| did.nickname.eq_ignore_ascii_case(&nickname))
+        {
+            return Err(CommandErrors::NicknameExists);
+        }
 
-    if vault
-        .dids
-        .iter()
-        .any(|did| did.nickname.eq_ignore_ascii_case(&nickname))
-    {
-        return Err(CommandErrors::NicknameExists);
-    }
+        let record = StoredDid {
+            id: new_did_id(),
+            nickname,
+            seed: Some(encrypted_seed),
+            mnemonic_language: mnemonic.language().into(),
+            btc_network: btc_network.unwrap_or_default(),
+            wallets,
+            sn_status: None,
+            password_attempts: PasswordAttempts::default(),
+            wipe_after_failures: None,
+            backup_status: BackupStatus::Unverified,
+        };
 
-    let record = StoredDid {
-        id: new_did_id(),
-        nickname,
-        seed: encrypted_seed,
-        wallets,
-        sn_status: None,
-    };
+        vault.active_did = Some(record.id.clone());
+        vault.dids.push(record.clone());
+        Ok(record.to_info())
+    })
+}
+
+/// Runs on a blocking worker: key derivation and the optional address
+/// discovery would otherwise stall the UI thread.
+#[tauri::command]
+pub async fn import_did(
+    app: AppHandle,
+    nickname: String,
+    password: String,
+    mnemonic_words: Vec<String>,
+    passphrase: Option<String>,
+    btc_network: Option<BtcNetwork>,
+    electrum_server: Option<String>,
+) -> CommandResult<DidInfo> {
+    tauri::async_runtime::spawn_blocking(move || {
+        import_did_in(
+            app.state::<VaultService>().handle(),
+            nickname,
+            password,
+            mnemonic_words,
+            passphrase,
+            btc_network,
+            electrum_server,
+        )
+    })
+    .await
+    .map_err(|e| CommandErrors::internal(e.to_string()))?
+}
 
-    vault.active_did = Some(record.id.clone());
-    vault.dids.push(record.clone());
+pub fn import_did_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    nickname: String,
+    password: String,
+    mnemonic_words: Vec<String>,
+    passphrase: Option<String>,
+    btc_network: Option<BtcNetwork>,
+    electrum_server: Option<String>,
+) -> CommandResult<DidInfo> {
+    if mnemonic_words.is_empty() {
+        return Err(CommandErrors::MnemonicRequired);
+    }
 
-    save_vault(&store, &vault)?;
+    let mnemonic = parse_mnemonic_words(&mnemonic_words, None)?;
 
-    Ok(record.to_info())
+    let passphrase = SecretString::new(passphrase.unwrap_or_default());
+    store_imported_did(
+        store,
+        nickname,
+        &password,
+        &mnemonic,
+        passphrase.expose_secret(),
+        btc_network.unwrap_or_default(),
+        electrum_server.as_deref(),
+    )
 }
 
+/// Restores a DID from SLIP-39 share mnemonics produced by
+/// `split_mnemonic_shares`, then imports it like `import_did`. The BIP39
+/// `passphrase` and the mnemonic `language` are not part of the shares and
+/// must be given again. Runs on a blocking worker like `import_did`.
 #[tauri::command]
-pub fn import_did(
-    app_handle: AppHandle,
+#[allow(clippy::too_many_arguments)]
+pub async fn import_did_from_shares(
+    app: AppHandle,
     nickname: String,
     password: String,
-    mnemonic_words: Vec<String>,
+    shares: Vec<Vec<String>>,
+    passphrase: Option<String>,
+    language: Option<MnemonicLanguage>,
+    btc_network: Option<BtcNetwork>,
+    electrum_server: Option<String>,
 ) -> CommandResult<DidInfo> {
-    if mnemonic_words.is_empty() {
+    tauri::async_runtime::spawn_blocking(move || {
+        import_did_from_shares_in(
+            app.state::<VaultService>().handle(),
+            nickname,
+            password,
+            shares,
+            passphrase,
+            language,
+            btc_network,
+            electrum_server,
+        )
+    })
+    .await
+    .map_err(|e| CommandErrors::internal(e.to_string()))?
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn import_did_from_shares_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    nickname: String,
+    password: String,
+    shares: Vec<Vec<String>>,
+    passphrase: Option<String>,
+    language: Option<MnemonicLanguage>,
+    btc_network: Option<BtcNetwork>,
+    electrum_server: Option<String>,
+) -> CommandResult<DidInfo> {
+    if shares.is_empty() {
         return Err(CommandErrors::MnemonicRequired);
     }
 
-    let decrypted = mnemonic_words.join(" ");
-    let secret_phrase = SecretString::new(decrypted);
-    let mnemonic = Mnemonic::parse_in(Language::English, secret_phrase.expose_secret())?;
-    drop(secret_phrase);
+    let share_phrases: Vec<SecretString> = shares
+        .into_iter()
+        .map(|words| SecretString::new(words.join(" ")))
+        .collect();
+    let share_refs: Vec<&str> = share_phrases
+        .iter()
+        .map(|phrase| phrase.expose_secret().as_str())
+        .collect();
+    let entropy = combine_mnemonics(&share_refs, b"")?;
+    let mnemonic = Mnemonic::from_entropy_in(language.unwrap_or_default().into(), &entropy)?;
+
+    let passphrase = SecretString::new(passphrase.unwrap_or_default());
+    store_imported_did(
+        store,
+        nickname,
+        &password,
+        &mnemonic,
+        passphrase.expose_secret(),
+        btc_network.unwrap_or_default(),
+        electrum_server.as_deref(),
+    )
+}
+
+fn store_imported_did<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    nickname: String,
+    password: &str,
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    btc_network: BtcNetwork,
+    electrum_server: Option<&str>,
+) -> CommandResult<DidInfo> {
+    store.password_policy().check(password)?;
 
     let requests = DidDerivationPlan::default_requests();
-    let wallets = derive_wallets_with_requests(&mnemonic, "", &requests, None)?;
+    let mut wallets = derive_wallets_with_requests(mnemonic, passphrase, &requests, None)?;
+    let ctx = SeedCtx::new(mnemonic, passphrase)?;
+    // 网络扫描放在事务之外，避免长时间占用保险库；扫描失败不影响导入
+    if let Some(server) = electrum_server {
+        match discover_with_server(&ctx, btc_network, server) {
+            Ok(discovered) => wallets.merge(discovered),
+            Err(err) => log::warn!("address discovery via {server} failed: {err}"),
+        }
+    }
+
+    let encrypted_seed = encrypt_mnemonic(password, mnemonic, passphrase)?;
+
+    store.transaction(|vault| {
+        // 同一身份的观察钱包：公开数据与种子一致时补上种子，保留其 id、昵称与 SN 状态
+        let primary_did = wallets
+            .bucky
+            .entries
+            .first()
+            .map(|entry| entry.did.as_str());
+        let watch_only = vault.dids.iter().position(|did| {
+            did.is_watch_only() && primary_did.is_some_and(|primary| did.owns_bucky_did(primary))
+        });
+        if let Some(position) = watch_only {
+            let record = &mut vault.dids[position];
+            if record.btc_network != btc_network || !seed_matches_watch_only(&ctx, record)? {
+                return Err(CommandErrors::IdentityExists);
+            }
+            record.seed = Some(encrypted_seed);
+            record.mnemonic_language = mnemonic.language().into();
+            record.wallets.merge_missing(wallets);
+            record.password_attempts = PasswordAttempts::default();
+            record.backup_status = BackupStatus::Verified {
+                verified_at_ms: now_ms(),
+            };
+            let info = record.to_info();
+            vault.active_did = Some(info.id.clone());
+            return Ok(info);
+        }
+
+        ensure_new_did(vault, &nickname, &wallets)?;
+
+        let record = StoredDid {
+            id: new_did_id(),
+            nickname,
+            seed: Some(encrypted_seed),
+            mnemonic_language: mnemonic.language().into(),
+            btc_network,
+            wallets,
+            sn_status: None,
+            password_attempts: PasswordAttempts::default(),
+            wipe_after_failures: None,
+            // 导入时用户刚输入过助记词，视为已备份
+            backup_status: BackupStatus::Verified {
+                verified_at_ms: now_ms(),
+            },
+        };
+
+        vault.active_did = Some(record.id.clone());
+        vault.dids.push(record.clone());
+        Ok(record.to_info())
+    })
+}
 
-    let encrypted_seed = encrypt_mnemonic(&password, &mnemonic)?;
+fn discover_with_server(
+    ctx: &SeedCtx,
+    btc_network: BtcNetwork,
+    server: &str,
+) -> CommandResult<WalletCollection> {
+    let mut client = ElectrumClient::connect(server)?;
+    discover_btc_wallets(ctx, btc_network, &mut client, DiscoveryLimits::default())
+}
 
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
+/// Whether `ctx` derives every address stored on the watch-only `record`,
+/// i.e. its account xpubs and ETH addresses came from this seed.
+fn seed_matches_watch_only(ctx: &SeedCtx, record: &StoredDid) -> CommandResult<bool> {
+    for entry in record.wallets.btc_addresses() {
+        let derived = derive_btc_address(
+            ctx,
+            record.btc_network,
+            entry.address_type,
+            entry.account,
+            entry.chain,
+            entry.index,
+        )?;
+        if derived.to_string() != entry.address {
+            return Ok(false);
+        }
+    }
+    for entry in record.wallets.eth_addresses() {
+        if derive_eth_address(ctx, entry.account, entry.chain, entry.index)? != entry.address {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
 
+/// Rejects a DID whose primary identity or nickname is already in `vault`.
+fn ensure_new_did(
+    vault: &VaultStore,
+    nickname: &str,
+    wallets: &WalletCollection,
+) -> CommandResult<()> {
     if let Some(new_identity) = wallets.bucky.entries.first() {
-        if vault.dids.iter().any(|existing| {
-            existing
-                .wallets
-                .bucky
-                .entries
-                .iter()
-                .any(|entry| entry.did == new_identity.did)
-        }) {
+        if vault
+            .dids
+            .iter()
+            .any(|existing| existing.owns_bucky_did(&new_identity.did))
+        {
             return Err(CommandErrors::IdentityExists);
         }
     }
@@ -169,247 +617,908 @@ pub fn import_did(
     if vault
         .dids
         .iter()
-        .any(|did| did.nickname.eq_ignore_ascii_case(&nickname))
+        .any(|did| did.nickname.eq_ignore_ascii_case(nickname))
     {
         return Err(CommandErrors::NicknameExists);
     }
+    Ok(())
+}
 
-    let record = StoredDid {
-        id: new_did_id(),
+/// Stores a DID known only by public data: the public JWK of its Bucky
+/// identity 0, optional account xpubs (their receive addresses are derived
+/// here) and optional ETH addresses. It lists and binds to an SN like any
+/// other DID, but commands that need the seed fail with `WatchOnly`.
+/// Importing a seed that derives all of this data later attaches the seed
+/// to the same record.
+#[tauri::command]
+pub fn import_watch_only_did(
+    vault: State<'_, VaultService>,
+    nickname: String,
+    public_key: Value,
+    btc_xpubs: Option<Vec<WatchOnlyXpub>>,
+    eth_addresses: Option<Vec<String>>,
+    btc_network: Option<BtcNetwork>,
+) -> CommandResult<DidInfo> {
+    import_watch_only_did_in(
+        vault.handle(),
         nickname,
-        seed: encrypted_seed,
-        wallets,
-        sn_status: None,
-    };
+        public_key,
+        btc_xpubs,
+        eth_addresses,
+        btc_network,
+    )
+}
+
+pub fn import_watch_only_did_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    nickname: String,
+    public_key: Value,
+    btc_xpubs: Option<Vec<WatchOnlyXpub>>,
+    eth_addresses: Option<Vec<String>>,
+    btc_network: Option<BtcNetwork>,
+) -> CommandResult<DidInfo> {
+    let btc_network = btc_network.unwrap_or_default();
+    let did = name_lib::get_device_did_from_ed25519_jwk(&public_key)
+        .map_err(|e| CommandErrors::invalid_public_key(e.to_string()))?;
+
+    let mut wallets = WalletCollection::default();
+    wallets.bucky.push_with_index(
+        0,
+        BuckyIdentity {
+            index: 0,
+            did,
+            public_key,
+        },
+    );
+
+    for request in btc_xpubs.unwrap_or_default() {
+        if request.count == 0 {
+            return Err(CommandErrors::CountMustBePositive);
+        }
+        let chain = AddressChain::Receive;
+        if wallets
+            .btc_series(request.address_type, request.account, chain)
+            .is_some()
+        {
+            return Err(CommandErrors::invalid_public_key(format!(
+                "duplicate xpub for {:?} account {}",
+                request.address_type, request.account
+            )));
+        }
+        let xpub = parse_account_xpub(&request.xpub, btc_network, request.address_type)?;
+        let series = wallets.btc_series_mut(request.address_type, request.account, chain);
+        for index in 0..request.count {
+            let address = derive_btc_address_from_xpub(
+                &xpub,
+                btc_network,
+                request.address_type,
+                chain,
+                index,
+            )?;
+            series.push_with_index(
+                index,
+                BtcAddress {
+                    address_type: request.address_type,
+                    account: request.account,
+                    chain,
+                    index,
+                    address: address.to_string(),
+                },
+            );
+        }
+    }
 
-    vault.active_did = Some(record.id.clone());
-    vault.dids.push(record.clone());
+    let eth_series = wallets.eth_series_mut(0, AddressChain::Receive);
+    for (index, address) in (0u32..).zip(eth_addresses.unwrap_or_default()) {
+        eth_series.push_with_index(
+            index,
+            ChainAddress {
+                account: 0,
+                chain: AddressChain::Receive,
+                index,
+                address: normalize_eth_address(&address)?,
+            },
+        );
+    }
 
-    save_vault(&store, &vault)?;
+    store.transaction(|vault| {
+        ensure_new_did(vault, &nickname, &wallets)?;
+
+        let record = StoredDid {
+            id: new_did_id(),
+            nickname,
+            seed: None,
+            mnemonic_language: MnemonicLanguage::default(),
+            btc_network,
+            wallets,
+            sn_status: None,
+            password_attempts: PasswordAttempts::default(),
+            wipe_after_failures: None,
+            backup_status: BackupStatus::default(),
+        };
 
-    Ok(record.to_info())
+        vault.active_did = Some(record.id.clone());
+        vault.dids.push(record.clone());
+        Ok(record.to_info())
+    })
 }
 
 #[tauri::command]
 pub fn extend_wallets(
-    app_handle: AppHandle,
-    password: String,
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: String,
+    request: WalletExtensionKind,
+) -> CommandResult<DidInfo> {
+    extend_wallets_in(vault.handle(), &sessions, password, did_id, request)
+}
+
+pub fn extend_wallets_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
     did_id: String,
     request: WalletExtensionKind,
 ) -> CommandResult<DidInfo> {
     let count = match &request {
         WalletExtensionKind::Btc { count, .. }
-        | WalletExtensionKind::Eth { count }
+        | WalletExtensionKind::Eth { count, .. }
         | WalletExtensionKind::Bucky { count } => *count,
     };
     if count == 0 {
         return Err(CommandErrors::CountMustBePositive);
     }
 
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
+    store.transaction(|vault| {
+        let secret = unlock_seed(vault, sessions, &did_id, password.as_deref())?;
+        let mnemonic = secret.mnemonic()?;
 
-    let info = {
         let record = vault
             .dids
             .iter_mut()
             .find(|did| did.id == did_id)
             .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
 
-        let decrypted = decrypt_mnemonic(&password, &record.seed)?;
-        let secret_phrase = SecretString::new(decrypted);
-        let mnemonic = Mnemonic::parse_in(Language::English, secret_phrase.expose_secret())?;
-        drop(secret_phrase);
-
         let requests = match request {
             WalletExtensionKind::Btc {
                 address_type,
+                account,
+                chain,
+                count,
+            } => vec![WalletRequest::btc(
+                record.btc_network,
+                address_type,
+                account,
+                chain,
+                count,
+            )],
+            WalletExtensionKind::Eth {
+                account,
+                chain,
                 count,
-            } => vec![WalletRequest::btc(address_type, count)],
-            WalletExtensionKind::Eth { count } => vec![WalletRequest::eth(count)],
+            } => vec![WalletRequest::eth(account, chain, count)],
             WalletExtensionKind::Bucky { count } => vec![WalletRequest::bucky(count)],
         };
 
-        if requests.is_empty() {
-            record.to_info()
-        } else {
-            let new_wallets =
-                derive_wallets_with_requests(&mnemonic, "", &requests, Some(&record.wallets))?;
-            record.wallets.merge(new_wallets);
-            record.to_info()
-        }
-    };
-
-    save_vault(&store, &vault)?;
-    Ok(info)
+        let new_wallets = derive_wallets_with_requests(
+            &mnemonic,
+            &secret.passphrase,
+            &requests,
+            Some(&record.wallets),
+        )?;
+        record.wallets.merge(new_wallets);
+        Ok(record.to_info())
+    })
 }
 
+/// Account-level extended public keys of `did_id` (the active DID when
+/// omitted) for every BTC address type, with master fingerprint and
+/// descriptors, for loading `account` (0 when omitted) into watch-only tools.
 #[tauri::command]
-pub fn wallet_exists(app_handle: AppHandle) -> CommandResult<bool> {
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
-    Ok(!vault.dids.is_empty())
+pub fn export_account_xpubs(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+    account: Option<u32>,
+) -> CommandResult<Vec<AccountXpub>> {
+    export_account_xpubs_in(vault.handle(), &sessions, password, did_id, account)
+}
+
+pub fn export_account_xpubs_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+    account: Option<u32>,
+) -> CommandResult<Vec<AccountXpub>> {
+    let (ctx, network) = unlock_btc_seed(store, sessions, password, did_id)?;
+
+    BtcAddressType::ALL
+        .into_iter()
+        .map(|address_type| {
+            export_account_xpub(&ctx, network, address_type, account.unwrap_or_default())
+        })
+        .collect()
 }
 
+/// Signs the inputs of a base64 PSBT that derive from the DID's seed and
+/// returns the updated PSBT with a summary of what it spends.
 #[tauri::command]
-pub fn list_dids(app_handle: AppHandle) -> CommandResult<Vec<DidInfo>> {
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
-    Ok(vault.dids.iter().map(StoredDid::to_info).collect())
+pub fn sign_psbt(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+    psbt: String,
+) -> CommandResult<SignedPsbt> {
+    sign_psbt_in(vault.handle(), &sessions, password, did_id, psbt)
+}
+
+pub fn sign_psbt_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+    psbt: String,
+) -> CommandResult<SignedPsbt> {
+    let (ctx, network) = unlock_btc_seed(store, sessions, password, did_id)?;
+    sign_psbt_with_seed(&ctx, network, &psbt)
 }
 
+/// Signs `message` with the key of `address`, one of the DID's stored BTC
+/// addresses, to prove ownership of it. Returns the base64 signature.
 #[tauri::command]
-pub fn active_did(app_handle: AppHandle) -> CommandResult<Option<DidInfo>> {
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
+pub fn sign_btc_message(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+    address: String,
+    message: String,
+) -> CommandResult<String> {
+    sign_btc_message_in(
+        vault.handle(),
+        &sessions,
+        password,
+        did_id,
+        address,
+        message,
+    )
+}
 
-    Ok(vault.active_did.and_then(|id| {
-        vault
+pub fn sign_btc_message_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+    address: String,
+    message: String,
+) -> CommandResult<String> {
+    let (target_id, entry) = store.read(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        let record = vault
             .dids
             .iter()
-            .find(|did| did.id == id)
-            .map(StoredDid::to_info)
-    }))
+            .find(|did| did.id == target_id)
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        let entry = record
+            .wallets
+            .btc_addresses()
+            .find(|entry| entry.address == address.trim())
+            .cloned()
+            .ok_or_else(|| CommandErrors::not_found("address_not_found"))?;
+        Ok((target_id, entry))
+    })?;
+    let (ctx, network) = unlock_btc_seed(store, sessions, password, Some(target_id))?;
+    sign_message(&ctx, network, &entry, &message)
 }
 
+/// Checks a signature made by `sign_btc_message` or any wallet following the
+/// same formats. Needs no vault access; `address` must belong to
+/// `btc_network` (mainnet when omitted).
 #[tauri::command]
-pub fn set_active_did(app_handle: AppHandle, did_id: String) -> CommandResult<DidInfo> {
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
-
-    let record = vault
-        .dids
-        .iter()
-        .find(|did| did.id == did_id)
-        .cloned()
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
-
-    vault.active_did = Some(record.id.clone());
-    save_vault(&store, &vault)?;
-
-    Ok(record.to_info())
+pub fn verify_btc_message(
+    address: String,
+    message: String,
+    signature: String,
+    btc_network: Option<BtcNetwork>,
+) -> CommandResult<bool> {
+    let network = bitcoin::Network::from(btc_network.unwrap_or_default());
+    let address = Address::from_str(address.trim())
+        .map_err(|e| CommandErrors::invalid_address(e.to_string()))?
+        .require_network(network)
+        .map_err(|e| CommandErrors::invalid_address(e.to_string()))?;
+    verify_message(&address, &message, &signature)
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
-pub struct SnStatusPayload {
-    pub username: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub zone_config: Option<String>,
+/// Unlocks the seed of `did_id` (the active DID when omitted) and returns it
+/// with the DID's Bitcoin network.
+fn unlock_btc_seed<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<(SeedCtx, BtcNetwork)> {
+    let (secret, network) = store.transaction(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        let secret = unlock_seed(vault, sessions, &target_id, password.as_deref())?;
+        let network = vault
+            .dids
+            .iter()
+            .find(|did| did.id == target_id)
+            .map(|did| did.btc_network)
+            .unwrap_or_default();
+        Ok((secret, network))
+    })?;
+    let mnemonic = secret.mnemonic()?;
+    Ok((SeedCtx::new(&mnemonic, &secret.passphrase)?, network))
 }
 
+/// Serializes `did_ids` (every DID when omitted) into an encrypted backup
+/// file protected by `backup_password`. Returns the file content. Every
+/// selected DID with a seed must accept `password` or have an unlocked
+/// session.
 #[tauri::command]
-pub fn list_sn_statuses(app_handle: AppHandle) -> CommandResult<HashMap<String, SnStatusInfo>> {
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
-    let mut map = HashMap::new();
-    for did in &vault.dids {
-        if let Some(status) = &did.sn_status {
-            map.insert(did.id.clone(), status.clone());
+pub fn export_vault_backup(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    backup_password: String,
+    did_ids: Option<Vec<String>>,
+) -> CommandResult<String> {
+    export_vault_backup_in(
+        vault.handle(),
+        &sessions,
+        password,
+        backup_password,
+        did_ids,
+    )
+}
+
+pub fn export_vault_backup_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    backup_password: String,
+    did_ids: Option<Vec<String>>,
+) -> CommandResult<String> {
+    let selected: Vec<StoredDid> = store.transaction(|vault| {
+        let ids = did_ids.unwrap_or_else(|| vault.dids.iter().map(|did| did.id.clone()).collect());
+        let mut selected = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let watch_only = vault
+                .dids
+                .iter()
+                .find(|did| &did.id == id)
+                .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?
+                .is_watch_only();
+            // 备份文件只受备份密码保护，导出前要求每个 DID 的密码或会话
+            if !watch_only {
+                unlock_seed(vault, sessions, id, password.as_deref())?;
+            }
+            selected.extend(vault.dids.iter().find(|did| &did.id == id).cloned());
         }
+        Ok(selected)
+    })?;
+    if selected.is_empty() {
+        return Err(CommandErrors::not_found("wallet_not_found"));
     }
-    Ok(map)
+
+    seal_backup(&backup_password, &selected)
 }
 
+/// Merges the DIDs of a backup file into the vault, skipping any whose Bucky
+/// DID is already present. Returns the DIDs that were added.
 #[tauri::command]
-pub fn set_sn_status(
-    app_handle: AppHandle,
-    did_id: String,
-    status: SnStatusPayload,
-) -> CommandResult<()> {
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
+pub fn import_vault_backup(
+    vault: State<'_, VaultService>,
+    backup: String,
+    backup_password: String,
+) -> CommandResult<Vec<DidInfo>> {
+    import_vault_backup_in(vault.handle(), backup, backup_password)
+}
 
-    let username = status
-        .username
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty());
+pub fn import_vault_backup_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    backup: String,
+    backup_password: String,
+) -> CommandResult<Vec<DidInfo>> {
+    let incoming = open_backup(&backup_password, &backup)?;
+
+    store.transaction(|vault| {
+        let mut imported = Vec::new();
+        for mut record in incoming {
+            if let Some(did) = record.primary_bucky_did() {
+                if vault
+                    .dids
+                    .iter()
+                    .any(|existing| existing.owns_bucky_did(did))
+                {
+                    continue;
+                }
+            }
+            if vault.dids.iter().any(|existing| existing.id == record.id) {
+                record.id = new_did_id();
+            }
+            let base = record.nickname.clone();
+            let mut suffix = 2;
+            while vault
+                .dids
+                .iter()
+                .any(|did| did.nickname.eq_ignore_ascii_case(&record.nickname))
+            {
+                record.nickname = format!("{base} ({suffix})");
+                suffix += 1;
+            }
 
-    let record = vault
-        .dids
-        .iter_mut()
-        .find(|did| did.id == did_id)
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+            imported.push(record.to_info());
+            vault.dids.push(record);
+        }
 
-    record.sn_status = Some(SnStatusInfo {
-        username,
-        zone_config: status
-            .zone_config
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty()),
-    });
+        if vault.active_did.is_none() {
+            vault.active_did = imported.first().map(|info| info.id.clone());
+        }
+        Ok(imported)
+    })
+}
+
+#[tauri::command]
+pub fn wallet_exists(vault: State<'_, VaultService>) -> CommandResult<bool> {
+    wallet_exists_in(vault.handle())
+}
+
+pub fn wallet_exists_in<B: VaultBackend>(store: &VaultHandle<B>) -> CommandResult<bool> {
+    store.read_index(|vault| Ok(vault.did_count() > 0))
+}
 
-    save_vault(&store, &vault)
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct VaultSnapshotInfo {
+    pub id: String,
+    pub created_at_ms: u64,
+    /// `None` when the snapshot can no longer be parsed.
+    pub did_count: Option<usize>,
 }
 
 #[tauri::command]
-pub fn clear_sn_status(app_handle: AppHandle, did_id: String) -> CommandResult<()> {
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
-    if let Some(record) = vault.dids.iter_mut().find(|did| did.id == did_id) {
-        record.sn_status = None;
-    }
-    save_vault(&store, &vault)
+pub fn list_vault_snapshots(
+    vault: State<'_, VaultService>,
+) -> CommandResult<Vec<VaultSnapshotInfo>> {
+    list_vault_snapshots_in(vault.handle())
 }
 
+pub fn list_vault_snapshots_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+) -> CommandResult<Vec<VaultSnapshotInfo>> {
+    let backend = store.backend();
+    Ok(backend
+        .list_snapshots()?
+        .into_iter()
+        .map(|snapshot| VaultSnapshotInfo {
+            did_count: backend
+                .read_snapshot(&snapshot.id)
+                .and_then(parse_vault)
+                .map(|(vault, _)| vault.did_count())
+                .ok(),
+            id: snapshot.id,
+            created_at_ms: snapshot.created_at_ms,
+        })
+        .collect())
+}
+
+/// Replaces the current vault with the snapshot `snapshot_id`. Needs the
+/// password or unlocked session of `did_id` (the active DID by default) when
+/// the vault holds any DID with a seed. Failed password counts of the current
+/// DIDs carry over to the recovered records. A plaintext snapshot is refused
+/// while metadata encryption is on.
 #[tauri::command]
-pub fn delete_wallet(
-    app_handle: AppHandle,
-    password: String,
+pub fn recover_vault(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    snapshot_id: String,
+    password: Option<String>,
     did_id: Option<String>,
-) -> CommandResult<()> {
-    let store = open_store(&app_handle)?;
-    let mut vault = load_vault(&store)?;
-
-    let target_id = match did_id {
-        Some(id) => id,
-        None => vault
-            .active_did
-            .clone()
-            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?,
-    };
+) -> CommandResult<Vec<DidInfo>> {
+    recover_vault_in(vault.handle(), &sessions, snapshot_id, password, did_id)
+}
 
-    let position = vault
-        .dids
-        .iter()
-        .position(|did| did.id == target_id)
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+pub fn recover_vault_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    snapshot_id: String,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<Vec<DidInfo>> {
+    let raw = store.backend().read_snapshot(&snapshot_id)?;
+    let (mut recovered, _) = store.parse(raw)?;
+    let infos = store.transaction(|vault| {
+        // 元数据加密开启后不再接受明文快照
+        if vault.is_sealed() && !recovered.is_sealed() {
+            return Err(CommandErrors::invalid_backup("snapshot_not_sealed"));
+        }
+        let auth_id = did_id.or_else(|| {
+            let mut seeded = vault.dids.iter().filter(|did| !did.is_watch_only());
+            let first = seeded.clone().next();
+            seeded
+                .find(|did| vault.active_did.as_deref() == Some(did.id.as_str()))
+                .or(first)
+                .map(|did| did.id.clone())
+        });
+        if let Some(id) = auth_id {
+            unlock_seed(vault, sessions, &id, password.as_deref())?;
+        }
 
-    let record = vault.dids.get(position).expect("did exists");
-    decrypt_mnemonic(&password, &record.seed)?;
+        // 恢复旧快照不能清零输错计数
+        for record in recovered.dids.iter_mut() {
+            if let Some(current) = vault.dids.iter().find(|did| did.id == record.id) {
+                record.password_attempts = current.password_attempts;
+            }
+        }
+        *vault = recovered;
+        Ok(vault.did_infos())
+    })?;
+    log::warn!("vault recovered from snapshot {snapshot_id}");
+    Ok(infos)
+}
 
-    vault.dids.remove(position);
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct VaultStatus {
+    /// Metadata encryption is on.
+    pub sealed: bool,
+    /// Sealed and the vault key is not in memory.
+    pub locked: bool,
+}
 
-    if matches!(vault.active_did.as_deref(), Some(active) if active == target_id) {
-        vault.active_did = None;
-    }
+#[tauri::command]
+pub fn vault_status(vault: State<'_, VaultService>) -> CommandResult<VaultStatus> {
+    vault_status_in(vault.handle())
+}
 
-    save_vault(&store, &vault)?;
-    Ok(())
+pub fn vault_status_in<B: VaultBackend>(store: &VaultHandle<B>) -> CommandResult<VaultStatus> {
+    store.read_index(|vault| {
+        Ok(VaultStatus {
+            sealed: vault.is_sealed(),
+            locked: vault.is_locked(),
+        })
+    })
 }
 
+/// Seals every DID record (nicknames, addresses, public keys, SN status)
+/// under a random vault key wrapped with `vault_password`. When encryption is
+/// already on, re-wraps the key with the new password instead.
 #[tauri::command]
-pub fn reveal_mnemonic(
-    app_handle: AppHandle,
-    password: String,
+pub fn enable_vault_encryption(
+    vault: State<'_, VaultService>,
+    vault_password: String,
+) -> CommandResult<()> {
+    enable_vault_encryption_in(vault.handle(), vault_password)
+}
+
+pub fn enable_vault_encryption_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    vault_password: String,
+) -> CommandResult<()> {
+    let key = store.transaction(|vault| vault.seal_with(&vault_password))?;
+    store.set_vault_key(Some(key));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn disable_vault_encryption(
+    vault: State<'_, VaultService>,
+    vault_password: String,
+) -> CommandResult<()> {
+    disable_vault_encryption_in(vault.handle(), vault_password)
+}
+
+pub fn disable_vault_encryption_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    vault_password: String,
+) -> CommandResult<()> {
+    store.index_transaction(|vault| {
+        // 关闭加密前总是重新校验 vault 密码
+        vault.unlock_sealed(&vault_password)?;
+        vault.unseal()
+    })?;
+    store.set_vault_key(None);
+    Ok(())
+}
+
+/// Opens a vault with sealed metadata and keeps its key in memory until
+/// `lock_vault`. Returns the full DID list.
+#[tauri::command]
+pub fn unlock_vault(
+    vault: State<'_, VaultService>,
+    vault_password: String,
+) -> CommandResult<Vec<DidInfo>> {
+    unlock_vault_in(vault.handle(), vault_password)
+}
+
+pub fn unlock_vault_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    vault_password: String,
+) -> CommandResult<Vec<DidInfo>> {
+    let (key, infos) = store.index_transaction(|vault| {
+        let key = vault.unlock_sealed(&vault_password)?;
+        Ok((key, vault.did_infos()))
+    })?;
+    if key.is_some() {
+        store.set_vault_key(key);
+    }
+    Ok(infos)
+}
+
+/// Forgets the vault key and locks every DID session with it.
+#[tauri::command]
+pub fn lock_vault(
+    app_handle: AppHandle,
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+) -> CommandResult<()> {
+    vault.handle().set_vault_key(None);
+    emit_locked(&app_handle, sessions.lock(None));
+    Ok(())
+}
+
+/// Lists every DID. While the vault is locked, only redacted entries from its
+/// plaintext index are returned.
+#[tauri::command]
+pub fn list_dids(vault: State<'_, VaultService>) -> CommandResult<Vec<DidInfo>> {
+    list_dids_in(vault.handle())
+}
+
+pub fn list_dids_in<B: VaultBackend>(store: &VaultHandle<B>) -> CommandResult<Vec<DidInfo>> {
+    store.read_index(|vault| Ok(vault.did_infos()))
+}
+
+#[tauri::command]
+pub fn active_did(vault: State<'_, VaultService>) -> CommandResult<Option<DidInfo>> {
+    active_did_in(vault.handle())
+}
+
+pub fn active_did_in<B: VaultBackend>(store: &VaultHandle<B>) -> CommandResult<Option<DidInfo>> {
+    store.read_index(|vault| {
+        Ok(vault
+            .active_did
+            .as_deref()
+            .and_then(|id| vault.did_info(id)))
+    })
+}
+
+#[tauri::command]
+pub fn set_active_did(vault: State<'_, VaultService>, did_id: String) -> CommandResult<DidInfo> {
+    set_active_did_in(vault.handle(), did_id)
+}
+
+pub fn set_active_did_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    did_id: String,
+) -> CommandResult<DidInfo> {
+    store.transaction(|vault| {
+        let info = vault
+            .dids
+            .iter()
+            .find(|did| did.id == did_id)
+            .map(StoredDid::to_info)
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+
+        vault.active_did = Some(info.id.clone());
+        Ok(info)
+    })
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+pub struct SnStatusPayload {
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_config: Option<String>,
+}
+
+#[tauri::command]
+pub fn list_sn_statuses(
+    vault: State<'_, VaultService>,
+) -> CommandResult<HashMap<String, SnStatusInfo>> {
+    list_sn_statuses_in(vault.handle())
+}
+
+pub fn list_sn_statuses_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+) -> CommandResult<HashMap<String, SnStatusInfo>> {
+    store.read(|vault| {
+        let mut map = HashMap::new();
+        for did in &vault.dids {
+            if let Some(status) = &did.sn_status {
+                map.insert(did.id.clone(), status.clone());
+            }
+        }
+        Ok(map)
+    })
+}
+
+/// Stores the SN binding of `did_id`. Binding a username is refused while
+/// the DID's mnemonic backup is unverified, like
+/// `generate_zone_boot_config_jwt`.
+#[tauri::command]
+pub fn set_sn_status(
+    vault: State<'_, VaultService>,
+    did_id: String,
+    status: SnStatusPayload,
+) -> CommandResult<()> {
+    set_sn_status_in(vault.handle(), did_id, status)
+}
+
+pub fn set_sn_status_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    did_id: String,
+    status: SnStatusPayload,
+) -> CommandResult<()> {
+    let username = status
+        .username
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    store.transaction(|vault| {
+        let record = vault
+            .dids
+            .iter_mut()
+            .find(|did| did.id == did_id)
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+
+        // 与生成 zone 配置相同：备份未验证前不能绑定 SN 用户名
+        if username.is_some() {
+            record.backup_status.require_verified()?;
+        }
+        record.sn_status = Some(SnStatusInfo {
+            username,
+            zone_config: status
+                .zone_config
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+        });
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn clear_sn_status(vault: State<'_, VaultService>, did_id: String) -> CommandResult<()> {
+    clear_sn_status_in(vault.handle(), did_id)
+}
+
+pub fn clear_sn_status_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    did_id: String,
+) -> CommandResult<()> {
+    store.transaction(|vault| {
+        if let Some(record) = vault.dids.iter_mut().find(|did| did.id == did_id) {
+            record.sn_status = None;
+        }
+        Ok(())
+    })
+}
+
+/// Deletes `did_id` once `max_failures` wrong passwords in a row are entered
+/// for it, or turns that off with `None`; backoff applies either way. Needs
+/// the DID's password.
+#[tauri::command]
+pub fn set_wipe_after_failures(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: String,
+    did_id: String,
+    max_failures: Option<u32>,
+) -> CommandResult<DidInfo> {
+    set_wipe_after_failures_in(vault.handle(), &sessions, password, did_id, max_failures)
+}
+
+pub fn set_wipe_after_failures_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: String,
+    did_id: String,
+    max_failures: Option<u32>,
+) -> CommandResult<DidInfo> {
+    if max_failures == Some(0) {
+        return Err(CommandErrors::CountMustBePositive);
+    }
+
+    store.transaction(|vault| {
+        unlock_seed(vault, sessions, &did_id, Some(&password))?;
+        let record = vault
+            .dids
+            .iter_mut()
+            .find(|did| did.id == did_id)
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        record.wipe_after_failures = max_failures;
+        Ok(record.to_info())
+    })
+}
+
+/// Deletes `did_id` (the active DID when omitted). DIDs with a seed need
+/// their password or an unlocked session; watch-only DIDs hold no secret and
+/// are deleted directly.
+#[tauri::command]
+pub fn delete_wallet(
+    app_handle: AppHandle,
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<()> {
+    let locked = delete_wallet_in(vault.handle(), &sessions, password, did_id)?;
+    emit_locked(&app_handle, locked);
+    Ok(())
+}
+
+/// Returns the session ids locked along with the deleted DID.
+pub fn delete_wallet_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
     did_id: Option<String>,
 ) -> CommandResult<Vec<String>> {
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
+    let target_id = store.transaction(|vault| {
+        let target_id = match did_id {
+            Some(id) => id,
+            None => vault
+                .active_did
+                .clone()
+                .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?,
+        };
 
-    let target_id = did_id
-        .or_else(|| vault.active_did.clone())
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        // 观察钱包不含私钥，也没有可验证的密码，直接删除
+        let watch_only = vault
+            .dids
+            .iter()
+            .any(|did| did.id == target_id && did.is_watch_only());
+        if !watch_only {
+            unlock_seed(vault, sessions, &target_id, password.as_deref())?;
+        }
+        vault.dids.retain(|did| did.id != target_id);
 
-    let record = vault
-        .dids
-        .iter()
-        .find(|did| did.id == target_id)
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        if matches!(vault.active_did.as_deref(), Some(active) if active == target_id) {
+            vault.active_did = None;
+        }
+        Ok(target_id)
+    })?;
+    Ok(sessions.lock(Some(&target_id)))
+}
+
+#[tauri::command]
+pub fn reveal_mnemonic(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<Vec<String>> {
+    reveal_mnemonic_in(vault.handle(), &sessions, password, did_id)
+}
+
+pub fn reveal_mnemonic_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<Vec<String>> {
+    let (secret, language) = store.transaction(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
 
-    let decrypted = decrypt_mnemonic(&password, &record.seed)?;
-    let secret_phrase = SecretString::new(decrypted);
-    let mnemonic = Mnemonic::parse_in(Language::English, secret_phrase.expose_secret())?;
-    drop(secret_phrase);
+        let secret = unlock_seed(vault, sessions, &target_id, password.as_deref())?;
+        let language = vault
+            .dids
+            .iter()
+            .find(|did| did.id == target_id)
+            .map(|did| did.mnemonic_language)
+            .unwrap_or_default();
+        Ok((secret, language))
+    })?;
+    let mnemonic = secret.mnemonic_in(language.into())?;
 
     Ok(mnemonic
         .to_string()
@@ -418,19 +1527,308 @@ pub fn reveal_mnemonic(
         .collect())
 }
 
+/// Splits the mnemonic of `did_id` (the active DID when omitted) into
+/// `share_count` SLIP-39 share mnemonics, any `threshold` of which restore it.
+#[tauri::command]
+pub fn split_mnemonic_shares(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+    threshold: u8,
+    share_count: u8,
+) -> CommandResult<Vec<Vec<String>>> {
+    split_mnemonic_shares_in(
+        vault.handle(),
+        &sessions,
+        password,
+        did_id,
+        threshold,
+        share_count,
+    )
+}
+
+pub fn split_mnemonic_shares_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+    threshold: u8,
+    share_count: u8,
+) -> CommandResult<Vec<Vec<String>>> {
+    let secret = store.transaction(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        unlock_seed(vault, sessions, &target_id, password.as_deref())
+    })?;
+    let mnemonic = secret.mnemonic()?;
+
+    let entropy = Zeroizing::new(mnemonic_entropy(&mnemonic));
+    let shares = split_mnemonics(&entropy, b"", threshold, share_count)?;
+    Ok(shares
+        .iter()
+        .map(|share| share.split_whitespace().map(|w| w.to_string()).collect())
+        .collect())
+}
+
+/// Starts a backup check for `did_id` (the active DID when omitted): returns
+/// the 1-based positions of the mnemonic words the user must enter next.
+#[tauri::command]
+pub fn issue_backup_challenge(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<Vec<usize>> {
+    issue_backup_challenge_in(vault.handle(), &sessions, password, did_id)
+}
+
+pub fn issue_backup_challenge_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+) -> CommandResult<Vec<usize>> {
+    store.transaction(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+
+        let secret = unlock_seed(vault, sessions, &target_id, password.as_deref())?;
+        let record = vault
+            .dids
+            .iter_mut()
+            .find(|did| did.id == target_id)
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        let word_count = secret
+            .mnemonic_in(record.mnemonic_language.into())?
+            .word_count();
+
+        Ok(record.backup_status.issue_challenge(word_count, &mut OsRng))
+    })
+}
+
+/// Answers the pending backup challenge of `did_id` with the words at the
+/// challenged positions, in order. Marks the DID as backed up on success; a
+/// wrong answer fails with `BackupMismatch` and needs a new challenge.
+#[tauri::command]
+pub fn verify_backup_challenge(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    did_id: Option<String>,
+    answers: Vec<String>,
+) -> CommandResult<DidInfo> {
+    verify_backup_challenge_in(vault.handle(), &sessions, password, did_id, answers)
+}
+
+pub fn verify_backup_challenge_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+    answers: Vec<String>,
+) -> CommandResult<DidInfo> {
+    store.transaction(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+
+        let secret = unlock_seed(vault, sessions, &target_id, password.as_deref())?;
+        let record = vault
+            .dids
+            .iter_mut()
+            .find(|did| did.id == target_id)
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        let mnemonic = secret.mnemonic_in(record.mnemonic_language.into())?;
+        let words: Vec<&str> = mnemonic.words().collect();
+
+        let result = record.backup_status.answer(&words, &answers, now_ms());
+        let info = record.to_info();
+        if result.is_err() {
+            // 答错也要落盘：本次挑战作废
+            vault.keep_on_error();
+        }
+        result.map(|_| info)
+    })
+}
+
+/// Re-encrypts the seed of `did_id` with `new_password`. Without `did_id`,
+/// every DID that unlocks with `old_password` is re-encrypted, and a password
+/// that unlocks none counts as one failed attempt of the active DID only. All
+/// records are written in a single transaction; returns the ids that were
+/// updated.
+/// `new_password` must meet the password policy.
+#[tauri::command]
+pub fn change_password(
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    old_password: String,
+    new_password: String,
+    did_id: Option<String>,
+) -> CommandResult<Vec<String>> {
+    change_password_in(
+        vault.handle(),
+        &sessions,
+        old_password,
+        new_password,
+        did_id,
+    )
+}
+
+pub fn change_password_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    old_password: String,
+    new_password: String,
+    did_id: Option<String>,
+) -> CommandResult<Vec<String>> {
+    store.password_policy().check(&new_password)?;
+
+    store.transaction(|vault| {
+        if let Some(id) = &did_id {
+            let secret = unlock_seed(vault, sessions, id, Some(&old_password))?;
+            let record = vault
+                .dids
+                .iter_mut()
+                .find(|did| &did.id == id)
+                .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+            record.seed = Some(encrypt_mnemonic(
+                &new_password,
+                &secret.mnemonic()?,
+                &secret.passphrase,
+            )?);
+            return Ok(vec![id.clone()]);
+        }
+
+        // 未指定 DID 时逐个尝试：退避中的 DID 跳过；只有没有任何 DID 匹配时才计数，
+        // 且只计在当前激活的 DID 上
+        let now = now_ms();
+        let attempts: Vec<PasswordAttempts> =
+            vault.dids.iter().map(|did| did.password_attempts).collect();
+        let mut updated = Vec::new();
+        for record in vault.dids.iter_mut() {
+            let secret = match unlock_with_password(record, &old_password, now) {
+                Ok(secret) => secret,
+                Err(
+                    CommandErrors::InvalidPassword
+                    | CommandErrors::TooManyAttempts { .. }
+                    | CommandErrors::WatchOnly,
+                ) => continue,
+                Err(err) => return Err(err),
+            };
+
+            record.seed = Some(encrypt_mnemonic(
+                &new_password,
+                &secret.mnemonic()?,
+                &secret.passphrase,
+            )?);
+            updated.push(record.id.clone());
+        }
+
+        let active = vault.active_did.clone();
+        for (record, attempts) in vault.dids.iter_mut().zip(attempts) {
+            let counted = updated.is_empty() && active.as_deref() == Some(record.id.as_str());
+            if !counted && !updated.contains(&record.id) {
+                record.password_attempts = attempts;
+            }
+        }
+        if !updated.is_empty() {
+            return Ok(updated);
+        }
+
+        vault.keep_on_error();
+        let wiped = vault
+            .dids
+            .iter()
+            .position(|did| active.as_deref() == Some(did.id.as_str()) && wipe_due(did));
+        if let Some(position) = wiped {
+            wipe_did(vault, sessions, position);
+            return Err(CommandErrors::not_found("wallet_wiped"));
+        }
+        Err(CommandErrors::invalid_password())
+    })
+}
+
+/// Unlocks `did_id` (the active DID by default) so that sensitive commands can
+/// run without a password until the session sits idle for `idle_timeout_secs`.
+#[tauri::command]
+pub fn unlock_session(
+    app_handle: AppHandle,
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: String,
+    did_id: Option<String>,
+    idle_timeout_secs: Option<u64>,
+) -> CommandResult<SessionInfo> {
+    let info = unlock_session_in(
+        vault.handle(),
+        &sessions,
+        password,
+        did_id,
+        idle_timeout_secs,
+    )?;
+    emit_unlocked(&app_handle, &info);
+    Ok(info)
+}
+
+pub fn unlock_session_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: String,
+    did_id: Option<String>,
+    idle_timeout_secs: Option<u64>,
+) -> CommandResult<SessionInfo> {
+    // 先校验参数，避免无效请求也解密种子或计入失败次数
+    let timeout_secs = idle_timeout_secs.unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS);
+    if timeout_secs == 0 {
+        return Err(CommandErrors::InvalidSessionTimeout);
+    }
+    let (target_id, secret) = store.transaction(|vault| {
+        let target_id = did_id
+            .or_else(|| vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        let secret = unlock_seed(vault, sessions, &target_id, Some(&password))?;
+        Ok((target_id, secret))
+    })?;
+
+    Ok(sessions.unlock(&target_id, secret, Duration::from_secs(timeout_secs)))
+}
+
+/// Locks `did_id`, or every unlocked DID when omitted.
+#[tauri::command]
+pub fn lock_session(
+    app_handle: AppHandle,
+    sessions: State<'_, SessionManager>,
+    did_id: Option<String>,
+) -> CommandResult<()> {
+    emit_locked(&app_handle, sessions.lock(did_id.as_deref()));
+    Ok(())
+}
+
 #[tauri::command]
-pub fn current_wallet_nickname(app_handle: AppHandle) -> CommandResult<Option<String>> {
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
+pub fn session_status(sessions: State<'_, SessionManager>) -> CommandResult<Vec<SessionInfo>> {
+    Ok(sessions.status())
+}
+
+#[tauri::command]
+pub fn current_wallet_nickname(vault: State<'_, VaultService>) -> CommandResult<Option<String>> {
+    current_wallet_nickname_in(vault.handle())
+}
 
-    match &vault.active_did {
+pub fn current_wallet_nickname_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+) -> CommandResult<Option<String>> {
+    // 锁定时 `dids` 为空，昵称不可见
+    store.read_index(|vault| match &vault.active_did {
         Some(active_id) => Ok(vault
             .dids
             .iter()
             .find(|did| &did.id == active_id)
             .map(|did| did.nickname.clone())),
         None => Ok(None),
-    }
+    })
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -442,52 +1840,54 @@ struct ZoneBootClaims {
     iat: usize,
 }
 
-fn load_active_signing_key(
-    app_handle: &AppHandle,
-    password: &str,
+fn load_active_signing_key<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<&str>,
 ) -> CommandResult<(EncodingKey, Option<String>)> {
-    let store = open_store(app_handle)?;
-    let vault = load_vault(&store)?;
-    let target_id = vault
-        .active_did
-        .clone()
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
-
-    let record = vault
-        .dids
-        .iter()
-        .find(|d| d.id == target_id)
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+    let (secret, did_label) = store.transaction(|vault| {
+        let target_id = vault
+            .active_did
+            .clone()
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
 
-    let decrypted = decrypt_mnemonic(password, &record.seed)?;
-    let secret_phrase = SecretString::new(decrypted);
-    let mnemonic = Mnemonic::parse_in(Language::English, secret_phrase.expose_secret())?;
-    drop(secret_phrase);
+        let secret = unlock_seed(vault, sessions, &target_id, password)?;
+        let did_label = vault
+            .dids
+            .iter()
+            .find(|d| d.id == target_id)
+            .and_then(|record| record.primary_bucky_did())
+            .map(str::to_string);
+        Ok((secret, did_label))
+    })?;
+    let mnemonic = secret.mnemonic()?;
 
     let phrase = mnemonic.to_string();
-    let passphrase_opt: Option<&str> = None;
     let index = 0u32;
     let (private_pem, _public_jwk) =
-        name_lib::generate_ed25519_key_pair_from_mnemonic(&phrase, passphrase_opt, index)
+        name_lib::generate_ed25519_key_pair_from_mnemonic(&phrase, secret.passphrase_opt(), index)
             .map_err(|e| CommandErrors::crypto_failed(e.to_string()))?;
 
     let pem_key = EncodingKey::from_ed_pem(private_pem.as_bytes())
         .map_err(|e| CommandErrors::crypto_failed(format!("invalid ed25519 private key: {e}")))?;
 
-    let did_label = record
-        .wallets
-        .bucky
-        .entries
-        .first()
-        .map(|entry| entry.did.clone());
-
     Ok((pem_key, did_label))
 }
 
 #[tauri::command]
 pub fn sign_json_with_active_did(
-    app_handle: AppHandle,
-    password: String,
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
+    payloads: Vec<Value>,
+) -> CommandResult<Vec<Option<String>>> {
+    sign_json_with_active_did_in(vault.handle(), &sessions, password, payloads)
+}
+
+pub fn sign_json_with_active_did_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
     payloads: Vec<Value>,
 ) -> CommandResult<Vec<Option<String>>> {
     let mut sanitized = Vec::with_capacity(payloads.len());
@@ -506,7 +1906,7 @@ pub fn sign_json_with_active_did(
         return Err(CommandErrors::SignMessageRequired);
     }
 
-    let (pem_key, _did_label) = load_active_signing_key(&app_handle, &password)?;
+    let (pem_key, _did_label) = load_active_signing_key(store, sessions, password.as_deref())?;
 
     let mut signatures = Vec::with_capacity(sanitized.len());
     for payload in sanitized {
@@ -526,38 +1926,51 @@ pub fn sign_json_with_active_did(
     Ok(signatures)
 }
 
+/// Signs the zone boot config with the owner key of `did_id`. Refused while
+/// the DID's mnemonic backup is unverified.
 #[tauri::command]
 pub fn generate_zone_boot_config_jwt(
-    app_handle: AppHandle,
-    password: String,
+    vault: State<'_, VaultService>,
+    sessions: State<'_, SessionManager>,
+    password: Option<String>,
     did_id: Option<String>,
     sn: Option<String>,
     #[allow(unused_variables)] ood_name: Option<String>,
 ) -> CommandResult<String> {
-    // resolve target DID (active by default)
-    let store = open_store(&app_handle)?;
-    let vault = load_vault(&store)?;
-    let target_id = did_id
-        .or(vault.active_did.clone())
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
-    let record = vault
-        .dids
-        .iter()
-        .find(|d| d.id == target_id)
-        .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+    generate_zone_boot_config_jwt_in(vault.handle(), &sessions, password, did_id, sn, ood_name)
+}
+
+pub fn generate_zone_boot_config_jwt_in<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    sessions: &SessionManager,
+    password: Option<String>,
+    did_id: Option<String>,
+    sn: Option<String>,
+    ood_name: Option<String>,
+) -> CommandResult<String> {
+    let secret = store.transaction(|vault| {
+        // resolve target DID (active by default)
+        let target_id = did_id
+            .or(vault.active_did.clone())
+            .ok_or_else(|| CommandErrors::not_found("wallet_not_found"))?;
+        if let Some(record) = vault.dids.iter().find(|d| d.id == target_id) {
+            // 观察钱包没有种子，备份状态对它没有意义
+            if record.is_watch_only() {
+                return Err(CommandErrors::WatchOnly);
+            }
+            record.backup_status.require_verified()?;
+        }
 
-    // unlock mnemonic to validate password and derive private key
-    let decrypted = decrypt_mnemonic(&password, &record.seed)?;
-    let secret_phrase = SecretString::new(decrypted);
-    let mnemonic = Mnemonic::parse_in(Language::English, secret_phrase.expose_secret())?;
-    drop(secret_phrase);
+        // unlock mnemonic to validate password and derive private key
+        unlock_seed(vault, sessions, &target_id, password.as_deref())
+    })?;
+    let mnemonic = secret.mnemonic()?;
 
     // derive ed25519 owner private key from mnemonic index 0 (Bucky identity)
     let phrase = mnemonic.to_string();
-    let passphrase_opt: Option<&str> = None;
     let index = 0u32;
     let (private_pem, _public_jwk) =
-        name_lib::generate_ed25519_key_pair_from_mnemonic(&phrase, passphrase_opt, index)
+        name_lib::generate_ed25519_key_pair_from_mnemonic(&phrase, secret.passphrase_opt(), index)
             .map_err(|e| CommandErrors::crypto_failed(e.to_string()))?;
 
     let pem_key = EncodingKey::from_ed_pem(private_pem.as_bytes())
@@ -587,14 +2000,39 @@ pub fn generate_zone_boot_config_jwt(
 
 #[cfg(test)]
 mod tests {
-    use super::domain::DEFAULT_BTC_ADDRESS_TYPE;
     use super::*;
-    use tauri::test::mock_app;
+    use crate::did::backend::{FileBackend, MemoryBackend};
+    use crate::did::domain::DEFAULT_BTC_ADDRESS_TYPE;
+    use crate::did::password_policy::{PasswordPolicy, PasswordRule};
+    use crate::did::store::{backup_key, STORE_KEY};
+    use bip39::Language;
+
+    fn memory_vault() -> (VaultHandle<MemoryBackend>, SessionManager) {
+        (
+            VaultHandle::new(MemoryBackend::default()),
+            SessionManager::default(),
+        )
+    }
+
+    fn words(phrase: &str) -> Vec<String> {
+        phrase.split(' ').map(String::from).collect()
+    }
 
     #[test]
     fn test_generate_mnemonic() {
-        let words = generate_mnemonic().unwrap();
-        assert_eq!(words.len(), 12);
+        let mnemonic_words = generate_mnemonic(None, None, None).unwrap();
+        assert_eq!(mnemonic_words.len(), 12);
+        assert_eq!(generate_mnemonic(None, Some(24), None).unwrap().len(), 24);
+        assert_eq!(generate_mnemonic(None, None, Some(160)).unwrap().len(), 15);
+        assert_eq!(
+            generate_mnemonic(None, Some(18), Some(192)).unwrap().len(),
+            18
+        );
+
+        for (word_count, strength) in [(Some(13), None), (None, Some(100)), (Some(12), Some(256))] {
+            let err = generate_mnemonic(None, word_count, strength).unwrap_err();
+            assert!(matches!(err, CommandErrors::InvalidMnemonic(_)));
+        }
     }
 
     #[test]
@@ -605,32 +2043,26 @@ mod tests {
         )
         .unwrap();
         let ctx = SeedCtx::new(&mnemonic, "").unwrap();
-        let address = derive_eth_address(&ctx, 0).unwrap();
+        let address = derive_eth_address(&ctx, 0, AddressChain::Receive, 0).unwrap();
         assert_eq!(address, "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
     }
 
     #[test]
     fn test_create_did_flow() {
-        let app = mock_app()
-            .plugin(tauri_plugin_store::Builder::default().build())
-            .build();
-        let app_handle = app.handle();
+        let (store, sessions) = memory_vault();
 
         let nickname = "test_user".to_string();
-        let password = "password123".to_string();
-        let mnemonic_words = vec![
-            "abandon", "abandon", "abandon", "abandon", "abandon", "abandon", "abandon", "abandon",
-            "abandon", "abandon", "abandon", "about",
-        ]
-        .into_iter()
-        .map(String::from)
-        .collect();
+        let password = "correct-horse-battery".to_string();
+        let mnemonic_words = words("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about");
 
-        let did_info = create_did(
-            app_handle.clone(),
+        let did_info = create_did_in(
+            &store,
             nickname.clone(),
             password.clone(),
             mnemonic_words,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -646,90 +2078,1666 @@ mod tests {
             identity.did
         );
 
-        let dids = list_dids(app_handle.clone()).unwrap();
+        let dids = list_dids_in(&store).unwrap();
         assert_eq!(dids.len(), 1);
         assert_eq!(dids[0].id, did_info.id);
         assert_eq!(dids[0].bucky_wallets.len(), 1);
 
-        let active = active_did(app_handle.clone()).unwrap().unwrap();
+        let active = active_did_in(&store).unwrap().unwrap();
         assert_eq!(active.id, did_info.id);
 
-        let mnemonic = reveal_mnemonic(
-            app_handle.clone(),
-            password.clone(),
+        let mnemonic = reveal_mnemonic_in(
+            &store,
+            &sessions,
+            Some(password.clone()),
             Some(did_info.id.clone()),
         )
         .unwrap();
         assert_eq!(mnemonic.len(), 12);
 
-        delete_wallet(app_handle.clone(), password, Some(did_info.id)).unwrap();
-        let dids_after = list_dids(app_handle).unwrap();
+        delete_wallet_in(&store, &sessions, Some(password), Some(did_info.id)).unwrap();
+        let dids_after = list_dids_in(&store).unwrap();
         assert!(dids_after.is_empty());
     }
 
     #[test]
     fn test_extend_wallets() {
-        let app = mock_app()
-            .plugin(tauri_plugin_store::Builder::default().build())
-            .build();
-        let app_handle = app.handle();
+        let (store, sessions) = memory_vault();
 
         let nickname = "extend_user".to_string();
-        let password = "password123".to_string();
-        let mnemonic_words = vec![
-            "abandon", "abandon", "abandon", "abandon", "abandon", "abandon", "abandon", "abandon",
-            "abandon", "abandon", "abandon", "about",
-        ]
-        .into_iter()
-        .map(String::from)
-        .collect();
+        let password = "correct-horse-battery".to_string();
+        let mnemonic_words = words("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about");
 
-        let did_info = create_did(
-            app_handle.clone(),
+        let did_info = create_did_in(
+            &store,
             nickname.clone(),
             password.clone(),
             mnemonic_words,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
-        let extended_btc = extend_wallets(
-            app_handle.clone(),
-            password.clone(),
+        let extended_btc = extend_wallets_in(
+            &store,
+            &sessions,
+            Some(password.clone()),
             did_info.id.clone(),
             WalletExtensionKind::Btc {
                 address_type: DEFAULT_BTC_ADDRESS_TYPE,
+                account: 0,
+                chain: AddressChain::Receive,
                 count: 2,
             },
         )
         .unwrap();
         assert_eq!(extended_btc.btc_addresses.len(), 2);
 
-        let extended_eth = extend_wallets(
-            app_handle.clone(),
-            password.clone(),
+        let extended_eth = extend_wallets_in(
+            &store,
+            &sessions,
+            Some(password.clone()),
             did_info.id.clone(),
-            WalletExtensionKind::Eth { count: 1 },
+            WalletExtensionKind::Eth {
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 1,
+            },
         )
         .unwrap();
         assert_eq!(extended_eth.eth_addresses.len(), 1);
 
-        let extended_bucky = extend_wallets(
-            app_handle.clone(),
-            password.clone(),
+        let extended_bucky = extend_wallets_in(
+            &store,
+            &sessions,
+            Some(password.clone()),
             did_info.id.clone(),
             WalletExtensionKind::Bucky { count: 1 },
         )
         .unwrap();
         assert_eq!(extended_bucky.bucky_wallets.len(), 2);
 
-        let listed = list_dids(app_handle.clone()).unwrap();
+        let listed = list_dids_in(&store).unwrap();
         assert_eq!(listed.len(), 1);
         assert_eq!(listed[0].btc_addresses.len(), 2);
         assert_eq!(listed[0].eth_addresses.len(), 1);
         assert_eq!(listed[0].bucky_wallets.len(), 2);
 
-        delete_wallet(app_handle.clone(), password, Some(did_info.id)).unwrap();
-        let after_delete = list_dids(app_handle).unwrap();
+        delete_wallet_in(&store, &sessions, Some(password), Some(did_info.id)).unwrap();
+        let after_delete = list_dids_in(&store).unwrap();
         assert!(after_delete.is_empty());
     }
+
+    #[test]
+    fn test_change_password() {
+        let (store, sessions) = memory_vault();
+
+        let first = create_did_in(
+            &store,
+            "first".to_string(),
+            "shared_pw".to_string(),
+            words("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let second = import_did_in(
+            &store,
+            "second".to_string(),
+            "shared_pw".to_string(),
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let third = import_did_in(
+            &store,
+            "third".to_string(),
+            "other_pw".to_string(),
+            words(
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            ),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let err = change_password_in(
+            &store,
+            &sessions,
+            "wrong_pw".to_string(),
+            "renewed_pw".to_string(),
+            Some(first.id.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPassword));
+
+        let mut updated = change_password_in(
+            &store,
+            &sessions,
+            "shared_pw".to_string(),
+            "renewed_pw".to_string(),
+            None,
+        )
+        .unwrap();
+        updated.sort();
+        let mut expected = vec![first.id.clone(), second.id.clone()];
+        expected.sort();
+        assert_eq!(updated, expected);
+        // 已有 DID 匹配时，其余 DID 不记输错
+        let third_info = list_dids_in(&store)
+            .unwrap()
+            .into_iter()
+            .find(|did| did.id == third.id)
+            .unwrap();
+        assert_eq!(third_info.failed_password_attempts, 0);
+
+        for id in [&first.id, &second.id] {
+            assert!(reveal_mnemonic_in(
+                &store,
+                &sessions,
+                Some("shared_pw".to_string()),
+                Some(id.clone())
+            )
+            .is_err());
+            assert_eq!(
+                reveal_mnemonic_in(
+                    &store,
+                    &sessions,
+                    Some("renewed_pw".to_string()),
+                    Some(id.clone())
+                )
+                .unwrap()
+                .len(),
+                12
+            );
+        }
+        assert!(reveal_mnemonic_in(
+            &store,
+            &sessions,
+            Some("other_pw".to_string()),
+            Some(third.id.clone())
+        )
+        .is_ok());
+
+        let updated = change_password_in(
+            &store,
+            &sessions,
+            "other_pw".to_string(),
+            "third_pw".to_string(),
+            Some(third.id.clone()),
+        )
+        .unwrap();
+        assert_eq!(updated, vec![third.id.clone()]);
+        assert!(reveal_mnemonic_in(
+            &store,
+            &sessions,
+            Some("third_pw".to_string()),
+            Some(third.id)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_unlocked_session_replaces_password() {
+        let (store, sessions) = memory_vault();
+
+        let password = "correct-horse-battery".to_string();
+        let mnemonic_words = words("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about");
+        let did_info = create_did_in(
+            &store,
+            "session_user".to_string(),
+            password.clone(),
+            mnemonic_words,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let err = reveal_mnemonic_in(&store, &sessions, None, None).unwrap_err();
+        assert!(matches!(err, CommandErrors::SessionLocked));
+
+        // 超时参数在解密前校验，错误密码不会被计入
+        let err =
+            unlock_session_in(&store, &sessions, "wrong".to_string(), None, Some(0)).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidSessionTimeout));
+        let stored = store
+            .read(|vault| Ok(vault.dids[0].password_attempts))
+            .unwrap();
+        assert_eq!(stored, PasswordAttempts::default());
+
+        assert!(unlock_session_in(&store, &sessions, "wrong".to_string(), None, None).is_err());
+        let info = unlock_session_in(&store, &sessions, password.clone(), None, Some(60)).unwrap();
+        assert_eq!(info.did_id, did_info.id);
+        assert_eq!(sessions.status().len(), 1);
+
+        let mnemonic = reveal_mnemonic_in(&store, &sessions, None, None).unwrap();
+        assert_eq!(mnemonic.len(), 12);
+        let extended = extend_wallets_in(
+            &store,
+            &sessions,
+            None,
+            did_info.id.clone(),
+            WalletExtensionKind::Eth {
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 1,
+            },
+        )
+        .unwrap();
+        assert_eq!(extended.eth_addresses.len(), 1);
+
+        sessions.lock(None);
+        assert!(sessions.status().is_empty());
+        let err = extend_wallets_in(
+            &store,
+            &sessions,
+            None,
+            did_info.id,
+            WalletExtensionKind::Eth {
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 1,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::SessionLocked));
+    }
+
+    #[test]
+    fn test_did_restored_from_shares_keeps_identity() {
+        let (store, sessions) = memory_vault();
+
+        // 2-of-3 SLIP-39 shares of the entropy behind PHRASE.
+        const PHRASE: &str =
+            "army van defense carry jealous true garbage claim echo media make crunch";
+        const SHARES: [&str; 3] = [
+            "recall penalty academic acid dryer center mayor academic health goat picture brave smear cowboy true eyebrow idle taxi duckling square",
+            "recall penalty academic agency ceramic thank dramatic duration loyalty dilemma founder river bulge walnut emission superior husband speak repair prospect",
+            "recall penalty academic always agency reunion level swimming desktop silent density fiscal grant method prune tolerate purple example finger exact",
+        ];
+
+        let expected = derive_wallets_with_requests(
+            &Mnemonic::parse_in(Language::English, PHRASE).unwrap(),
+            "",
+            &DidDerivationPlan::default_requests(),
+            None,
+        )
+        .unwrap();
+
+        let err = import_did_from_shares_in(
+            &store,
+            "shamir_user".to_string(),
+            "correct-horse-battery".to_string(),
+            vec![words(SHARES[1])],
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidShare(_)));
+
+        let restored = import_did_from_shares_in(
+            &store,
+            "shamir_user".to_string(),
+            "correct-horse-battery".to_string(),
+            vec![words(SHARES[2]), words(SHARES[0])],
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(restored.bucky_wallets.len(), 1);
+        assert_eq!(restored.bucky_wallets[0].did, expected.bucky.entries[0].did);
+        assert_eq!(
+            restored.bucky_wallets[0].public_key,
+            expected.bucky.entries[0].public_key
+        );
+
+        let shares = split_mnemonic_shares_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(restored.id.clone()),
+            3,
+            5,
+        )
+        .unwrap();
+        assert_eq!(shares.len(), 5);
+        let picked: Vec<String> = [4, 1, 3].iter().map(|&i| shares[i].join(" ")).collect();
+        let entropy = combine_mnemonics(&picked, b"").unwrap();
+        let mnemonic = Mnemonic::from_entropy_in(Language::English, &entropy).unwrap();
+        assert_eq!(mnemonic.to_string(), PHRASE);
+
+        delete_wallet_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(restored.id),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_bip39_passphrase_is_used_for_every_derivation() {
+        let (store, sessions) = memory_vault();
+
+        const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::parse_in(Language::English, PHRASE).unwrap();
+        let derive = |passphrase: &str| {
+            derive_wallets_with_requests(
+                &mnemonic,
+                passphrase,
+                &DidDerivationPlan::default_requests(),
+                None,
+            )
+            .unwrap()
+        };
+
+        let did_info = import_did_in(
+            &store,
+            "passphrase_user".to_string(),
+            "correct-horse-battery".to_string(),
+            words(PHRASE),
+            Some("TREZOR".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        let identity = &did_info.bucky_wallets[0];
+        assert_eq!(identity.did, derive("TREZOR").bucky.entries[0].did);
+        assert_ne!(identity.did, derive("").bucky.entries[0].did);
+        assert_eq!(
+            derive_bucky_public_key(words(PHRASE), Some("TREZOR".to_string())).unwrap(),
+            identity.public_key
+        );
+
+        let ctx = SeedCtx::new(&mnemonic, "TREZOR").unwrap();
+        let extended = extend_wallets_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            did_info.id.clone(),
+            WalletExtensionKind::Eth {
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 1,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            extended.eth_addresses[0].address,
+            derive_eth_address(&ctx, 0, AddressChain::Receive, 0).unwrap()
+        );
+
+        // 修改密码后口令仍随 seed 一起保存。
+        change_password_in(
+            &store,
+            &sessions,
+            "correct-horse-battery".to_string(),
+            "staple-orbit-lantern".to_string(),
+            Some(did_info.id.clone()),
+        )
+        .unwrap();
+        unlock_session_in(
+            &store,
+            &sessions,
+            "staple-orbit-lantern".to_string(),
+            Some(did_info.id.clone()),
+            None,
+        )
+        .unwrap();
+        let extended = extend_wallets_in(
+            &store,
+            &sessions,
+            None,
+            did_info.id.clone(),
+            WalletExtensionKind::Eth {
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 1,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            extended.eth_addresses[1].address,
+            derive_eth_address(&ctx, 0, AddressChain::Receive, 1).unwrap()
+        );
+
+        delete_wallet_in(&store, &sessions, None, Some(did_info.id)).unwrap();
+    }
+
+    #[test]
+    fn test_chinese_mnemonic_keeps_its_language() {
+        let (store, sessions) = memory_vault();
+
+        let mnemonic_words =
+            generate_mnemonic(Some(MnemonicLanguage::ChineseTraditional), None, None).unwrap();
+        assert_eq!(mnemonic_words.len(), 12);
+        assert!(validate_mnemonic_words(mnemonic_words.clone())
+            .unwrap()
+            .is_empty());
+
+        let mut typo = mnemonic_words.clone();
+        typo[4] = "abandon".to_string();
+        typo[7] = "xyz".to_string();
+        let invalid = validate_mnemonic_words(typo).unwrap();
+        assert_eq!(
+            invalid
+                .iter()
+                .map(|entry| (entry.position, entry.word.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(5, "abandon"), (8, "xyz")]
+        );
+
+        let did_info = create_did_in(
+            &store,
+            "chinese_user".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words.clone(),
+            None,
+            Some(MnemonicLanguage::ChineseTraditional),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            did_info.mnemonic_language,
+            MnemonicLanguage::ChineseTraditional
+        );
+        assert_eq!(
+            derive_bucky_public_key(mnemonic_words.clone(), None).unwrap(),
+            did_info.bucky_wallets[0].public_key
+        );
+
+        let revealed = reveal_mnemonic_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(did_info.id.clone()),
+        )
+        .unwrap();
+        assert_eq!(revealed, mnemonic_words);
+
+        delete_wallet_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(did_info.id),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_24_word_mnemonic_derives_bucky_identity() {
+        use name_lib::{generate_ed25519_key_pair_from_mnemonic, get_device_did_from_ed25519_jwk};
+
+        let (store, sessions) = memory_vault();
+
+        const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art";
+        const SHORT_PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        let (_pem, expected_jwk) =
+            generate_ed25519_key_pair_from_mnemonic(PHRASE, None, 0).unwrap();
+        let expected_did = get_device_did_from_ed25519_jwk(&expected_jwk).unwrap();
+
+        assert_eq!(
+            derive_bucky_public_key(words(PHRASE), None).unwrap(),
+            expected_jwk
+        );
+        assert_ne!(
+            derive_bucky_public_key(words(SHORT_PHRASE), None).unwrap(),
+            expected_jwk
+        );
+
+        let did_info = import_did_in(
+            &store,
+            "long_seed_user".to_string(),
+            "correct-horse-battery".to_string(),
+            words(PHRASE),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(did_info.bucky_wallets[0].did, expected_did);
+        assert_eq!(did_info.bucky_wallets[0].public_key, expected_jwk);
+
+        let revealed = reveal_mnemonic_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(did_info.id.clone()),
+        )
+        .unwrap();
+        assert_eq!(revealed, words(PHRASE));
+
+        delete_wallet_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(did_info.id),
+        )
+        .unwrap();
+
+        let generated = generate_mnemonic(None, Some(24), None).unwrap();
+        let created = create_did_in(
+            &store,
+            "generated_long_seed".to_string(),
+            "correct-horse-battery".to_string(),
+            generated.clone(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let (_pem, generated_jwk) =
+            generate_ed25519_key_pair_from_mnemonic(&generated.join(" "), None, 0).unwrap();
+        assert_eq!(created.bucky_wallets[0].public_key, generated_jwk);
+
+        delete_wallet_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            Some(created.id),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_commands_run_against_memory_backend() {
+        let (store, sessions) = memory_vault();
+        assert!(!wallet_exists_in(&store).unwrap());
+
+        let did_info = import_did_in(
+            &store,
+            "memory_user".to_string(),
+            "correct-horse-battery".to_string(),
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow"),
+            None,
+            None,
+            // 扫描服务器不可达时仍完成导入
+            Some("tcp://127.0.0.1:1".to_string()),
+        )
+        .unwrap();
+        assert!(wallet_exists_in(&store).unwrap());
+        assert_eq!(active_did_in(&store).unwrap().unwrap().id, did_info.id);
+        assert!(list_vault_snapshots_in(&store).unwrap().is_empty());
+
+        unlock_session_in(
+            &store,
+            &sessions,
+            "correct-horse-battery".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        let extended = extend_wallets_in(
+            &store,
+            &sessions,
+            None,
+            did_info.id.clone(),
+            WalletExtensionKind::Eth {
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(list_dids_in(&store).unwrap()[0].eth_addresses.len(), 2);
+        assert_eq!(extended.eth_addresses.len(), 2);
+
+        let locked = delete_wallet_in(&store, &sessions, None, None).unwrap();
+        assert_eq!(locked, vec![did_info.id]);
+        assert!(list_dids_in(&store).unwrap().is_empty());
+        assert!(sessions.status().is_empty());
+    }
+
+    #[test]
+    fn test_export_vault_backup_requires_password_or_session() {
+        let (store, sessions) = memory_vault();
+        let password = "correct-horse-battery".to_string();
+        let backup_password = "staple-orbit-lantern".to_string();
+        let mnemonic_words =
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow");
+        let did_info = import_did_in(
+            &store,
+            "backup_user".to_string(),
+            password.clone(),
+            mnemonic_words,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let err = export_vault_backup_in(&store, &sessions, None, backup_password.clone(), None)
+            .unwrap_err();
+        assert!(matches!(err, CommandErrors::SessionLocked));
+        let err = export_vault_backup_in(
+            &store,
+            &sessions,
+            Some("wrong-password".to_string()),
+            backup_password.clone(),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPassword));
+
+        let backup = export_vault_backup_in(
+            &store,
+            &sessions,
+            Some(password.clone()),
+            backup_password.clone(),
+            Some(vec![did_info.id.clone()]),
+        )
+        .unwrap();
+        let restored = VaultHandle::new(MemoryBackend::default());
+        let imported = import_vault_backup_in(&restored, backup, backup_password.clone()).unwrap();
+        assert_eq!(imported.len(), 1);
+        // 成功验证后失败计数已清零，不随备份带出
+        assert_eq!(
+            restored
+                .read(|vault| Ok(vault.dids[0].password_attempts))
+                .unwrap(),
+            PasswordAttempts::default()
+        );
+
+        unlock_session_in(&store, &sessions, password, None, None).unwrap();
+        assert!(export_vault_backup_in(&store, &sessions, None, backup_password, None).is_ok());
+    }
+
+    #[test]
+    fn test_change_password_without_did_counts_only_the_active_did() {
+        let (store, sessions) = memory_vault();
+        let mut ids = Vec::new();
+        for (nickname, phrase) in [
+            (
+                "first",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            ),
+            (
+                "second",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            ),
+        ] {
+            let info = import_did_in(
+                &store,
+                nickname.to_string(),
+                "correct-horse-battery".to_string(),
+                words(phrase),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            set_wipe_after_failures_in(
+                &store,
+                &sessions,
+                "correct-horse-battery".to_string(),
+                info.id.clone(),
+                Some(2),
+            )
+            .unwrap();
+            ids.push(info.id);
+        }
+        let change = || {
+            change_password_in(
+                &store,
+                &sessions,
+                "wrong".to_string(),
+                "renewed-horse-battery".to_string(),
+                None,
+            )
+        };
+        let failures = |id: &str| {
+            list_dids_in(&store)
+                .unwrap()
+                .into_iter()
+                .find(|did| did.id == id)
+                .map(|did| did.failed_password_attempts)
+        };
+
+        assert!(matches!(
+            change().unwrap_err(),
+            CommandErrors::InvalidPassword
+        ));
+        assert_eq!(failures(&ids[0]), Some(0));
+        assert_eq!(failures(&ids[1]), Some(1));
+
+        // 达到阈值时只清除当前激活的 DID
+        assert!(matches!(
+            change().unwrap_err(),
+            CommandErrors::NotFound(reason) if reason == "wallet_wiped"
+        ));
+        assert_eq!(failures(&ids[0]), Some(0));
+        assert_eq!(failures(&ids[1]), None);
+    }
+
+    #[test]
+    fn test_failed_passwords_back_off_and_wipe() {
+        let dir = std::env::temp_dir().join(format!("vault-attempts-{}", ulid::Ulid::new()));
+        let store = VaultHandle::new(FileBackend::new(&dir));
+        let sessions = SessionManager::default();
+        let reveal = |store: &VaultHandle<FileBackend>, password: &str, id: &str| {
+            reveal_mnemonic_in(
+                store,
+                &sessions,
+                Some(password.to_string()),
+                Some(id.to_string()),
+            )
+        };
+
+        let first = import_did_in(
+            &store,
+            "first".to_string(),
+            "correct-horse-battery".to_string(),
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        for _ in 0..3 {
+            let err = reveal(&store, "wrong", &first.id).unwrap_err();
+            assert!(matches!(err, CommandErrors::InvalidPassword));
+        }
+
+        // 计数随 vault 落盘，重新打开后仍处于退避期
+        let reopened = VaultHandle::new(FileBackend::new(&dir));
+        let err = reveal(&reopened, "correct-horse-battery", &first.id).unwrap_err();
+        assert!(matches!(
+            err,
+            CommandErrors::TooManyAttempts { retry_after_secs } if retry_after_secs > 0
+        ));
+        assert_eq!(
+            list_dids_in(&reopened).unwrap()[0].failed_password_attempts,
+            3
+        );
+
+        let second = import_did_in(
+            &reopened,
+            "second".to_string(),
+            "staple-orbit-lantern".to_string(),
+            words(
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            ),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let err = set_wipe_after_failures_in(
+            &reopened,
+            &sessions,
+            "staple-orbit-lantern".to_string(),
+            second.id.clone(),
+            Some(0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::CountMustBePositive));
+        let info = set_wipe_after_failures_in(
+            &reopened,
+            &sessions,
+            "staple-orbit-lantern".to_string(),
+            second.id.clone(),
+            Some(2),
+        )
+        .unwrap();
+        assert_eq!(info.wipe_after_failures, Some(2));
+
+        assert_eq!(
+            reveal(&reopened, "staple-orbit-lantern", &second.id)
+                .unwrap()
+                .len(),
+            12
+        );
+        assert!(matches!(
+            reveal(&reopened, "wrong", &second.id).unwrap_err(),
+            CommandErrors::InvalidPassword
+        ));
+        assert!(matches!(
+            reveal(&reopened, "wrong", &second.id).unwrap_err(),
+            CommandErrors::NotFound(reason) if reason == "wallet_wiped"
+        ));
+        let remaining = list_dids_in(&reopened).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, first.id);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recover_vault_requires_auth_and_forgets_wiped_dids() {
+        let dir = std::env::temp_dir().join(format!("vault-recover-{}", ulid::Ulid::new()));
+        let store = VaultHandle::new(FileBackend::new(&dir));
+        let sessions = SessionManager::default();
+        let reveal = |password: &str, id: &str| {
+            reveal_mnemonic_in(
+                &store,
+                &sessions,
+                Some(password.to_string()),
+                Some(id.to_string()),
+            )
+        };
+
+        let first = import_did_in(
+            &store,
+            "first".to_string(),
+            "correct-horse-battery".to_string(),
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let second = import_did_in(
+            &store,
+            "second".to_string(),
+            "staple-orbit-lantern".to_string(),
+            words(
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            ),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        set_wipe_after_failures_in(
+            &store,
+            &sessions,
+            "staple-orbit-lantern".to_string(),
+            second.id.clone(),
+            Some(3),
+        )
+        .unwrap();
+        let snapshot_id = list_vault_snapshots_in(&store).unwrap()[0].id.clone();
+        for _ in 0..2 {
+            assert!(reveal("wrong", &second.id).is_err());
+        }
+
+        // 默认按当前激活的 second 授权，这里指定 first，输错不计在 second 上
+        let recover = |password: Option<&str>| {
+            recover_vault_in(
+                &store,
+                &sessions,
+                snapshot_id.clone(),
+                password.map(str::to_string),
+                Some(first.id.clone()),
+            )
+        };
+        assert!(matches!(
+            recover(None).unwrap_err(),
+            CommandErrors::SessionLocked
+        ));
+        assert!(matches!(
+            recover(Some("wrong")).unwrap_err(),
+            CommandErrors::InvalidPassword
+        ));
+        let infos = recover(Some("correct-horse-battery")).unwrap();
+        let attempts = |id: &str| {
+            infos
+                .iter()
+                .find(|info| info.id == id)
+                .unwrap()
+                .failed_password_attempts
+        };
+        assert_eq!(attempts(&first.id), 0);
+        assert_eq!(attempts(&second.id), 2);
+
+        // 被清除的 DID 不能再从任何快照恢复
+        assert!(matches!(
+            reveal("wrong", &second.id).unwrap_err(),
+            CommandErrors::NotFound(reason) if reason == "wallet_wiped"
+        ));
+        let backend = store.backend();
+        for snapshot in backend.list_snapshots().unwrap() {
+            let raw = backend.read_snapshot(&snapshot.id).unwrap();
+            assert!(!raw.to_string().contains(&second.id));
+        }
+        assert!(matches!(
+            recover(Some("correct-horse-battery")).unwrap_err(),
+            CommandErrors::NotFound(reason) if reason == "snapshot_not_found"
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sealing_purges_plaintext_history() {
+        let dir = std::env::temp_dir().join(format!("vault-seal-{}", ulid::Ulid::new()));
+        let store = VaultHandle::new(FileBackend::new(&dir));
+        let sessions = SessionManager::default();
+        import_did_in(
+            &store,
+            "plain_nickname".to_string(),
+            "correct-horse-battery".to_string(),
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow"),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let backend = store.backend();
+        let plaintext = backend.get(STORE_KEY).unwrap().unwrap();
+        backend
+            .set(vec![(backup_key(1), plaintext.clone())])
+            .unwrap();
+
+        enable_vault_encryption_in(&store, "staple-orbit-lantern".to_string()).unwrap();
+        assert_eq!(backend.get(&backup_key(1)).unwrap(), None);
+        let snapshots = backend.list_snapshots().unwrap();
+        assert!(!snapshots.is_empty());
+        for snapshot in snapshots {
+            let raw = backend.read_snapshot(&snapshot.id).unwrap();
+            assert!(raw.get("sealed").is_some());
+            assert!(!raw.to_string().contains("plain_nickname"));
+        }
+
+        // 加密开启后不能借明文快照关闭加密
+        backend.write_snapshot(&plaintext).unwrap();
+        let snapshot_id = backend.list_snapshots().unwrap()[0].id.clone();
+        let err = recover_vault_in(
+            &store,
+            &sessions,
+            snapshot_id,
+            Some("correct-horse-battery".to_string()),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidBackup(_)));
+        assert!(vault_status_in(&store).unwrap().sealed);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_weak_passwords_are_rejected() {
+        let (store, sessions) = memory_vault();
+        let mnemonic_words =
+            words("legal winner thank year wave sausage worth useful legal winner thank yellow");
+
+        let err = import_did_in(
+            &store,
+            "alice".to_string(),
+            String::new(),
+            mnemonic_words.clone(),
+            None,
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            &err,
+            CommandErrors::WeakPassword(weak)
+                if weak.0 == [PasswordRule::MinLength, PasswordRule::MinEntropy]
+        ));
+        assert_eq!(err.message(), "weak_password: min_length,min_entropy");
+        let err = create_did_in(
+            &store,
+            "alice".to_string(),
+            "Password123".to_string(),
+            mnemonic_words.clone(),
+            None,
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.message(), "weak_password: not_common");
+        assert!(list_dids_in(&store).unwrap().is_empty());
+
+        let did = import_did_in(
+            &store,
+            "alice".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words.clone(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let err = change_password_in(
+            &store,
+            &sessions,
+            "correct-horse-battery".to_string(),
+            "qwerty".to_string(),
+            Some(did.id.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::WeakPassword(_)));
+
+        // 宽松策略由配置决定，命令本身不再另设规则
+        let relaxed =
+            VaultHandle::new(MemoryBackend::default()).with_password_policy(PasswordPolicy {
+                min_length: 0,
+                min_entropy_bits: 0.0,
+                reject_common: false,
+            });
+        assert!(import_did_in(
+            &relaxed,
+            "bob".to_string(),
+            "pw".to_string(),
+            mnemonic_words,
+            None,
+            None,
+            None,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_backup_challenge_gates_zone_config() {
+        let (store, sessions) = memory_vault();
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let mnemonic_words = words(phrase);
+        let password = || Some("correct-horse-battery".to_string());
+
+        let created = create_did_in(
+            &store,
+            "alice".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words.clone(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(created.backup_status, BackupStatus::Unverified);
+        let err = generate_zone_boot_config_jwt_in(&store, &sessions, password(), None, None, None)
+            .unwrap_err();
+        assert!(matches!(err, CommandErrors::BackupNotVerified));
+        let bind = |username: Option<&str>| {
+            set_sn_status_in(
+                &store,
+                created.id.clone(),
+                SnStatusPayload {
+                    username: username.map(str::to_string),
+                    zone_config: None,
+                },
+            )
+        };
+        assert!(matches!(
+            bind(Some("alice")).unwrap_err(),
+            CommandErrors::BackupNotVerified
+        ));
+        // 未注册的查询结果仍可缓存
+        bind(None).unwrap();
+
+        let err = verify_backup_challenge_in(&store, &sessions, password(), None, Vec::new())
+            .unwrap_err();
+        assert!(
+            matches!(err, CommandErrors::NotFound(reason) if reason == "backup_challenge_not_found")
+        );
+
+        issue_backup_challenge_in(&store, &sessions, password(), None).unwrap();
+        let err = verify_backup_challenge_in(
+            &store,
+            &sessions,
+            password(),
+            None,
+            vec!["abandon".to_string(); 3],
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::BackupMismatch));
+        assert_eq!(
+            active_did_in(&store).unwrap().unwrap().backup_status,
+            BackupStatus::Unverified
+        );
+
+        let positions = issue_backup_challenge_in(&store, &sessions, password(), None).unwrap();
+        assert_eq!(positions.len(), 3);
+        let answers = positions
+            .iter()
+            .map(|p| mnemonic_words[p - 1].clone())
+            .collect();
+        let info =
+            verify_backup_challenge_in(&store, &sessions, password(), None, answers).unwrap();
+        assert!(matches!(info.backup_status, BackupStatus::Verified { .. }));
+        assert!(
+            generate_zone_boot_config_jwt_in(&store, &sessions, password(), None, None, None)
+                .is_ok()
+        );
+        bind(Some("alice")).unwrap();
+
+        // 复查答错不会让已验证的 DID 失去导出能力
+        issue_backup_challenge_in(&store, &sessions, password(), None).unwrap();
+        let err = verify_backup_challenge_in(
+            &store,
+            &sessions,
+            password(),
+            None,
+            vec!["abandon".to_string(); 3],
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::BackupMismatch));
+        assert_eq!(
+            active_did_in(&store).unwrap().unwrap().backup_status,
+            info.backup_status
+        );
+        assert!(
+            generate_zone_boot_config_jwt_in(&store, &sessions, password(), None, None, None)
+                .is_ok()
+        );
+
+        let imported = import_did_in(
+            &VaultHandle::new(MemoryBackend::default()),
+            "bob".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(matches!(
+            imported.backup_status,
+            BackupStatus::Verified { .. }
+        ));
+    }
+
+    #[test]
+    fn test_watch_only_did_lists_but_cannot_sign() {
+        let (store, sessions) = memory_vault();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic_words = words(phrase);
+        let public_key = derive_bucky_public_key(mnemonic_words.clone(), None).unwrap();
+        // BIP84 测试向量的账户 zpub 与第一个收款地址
+        let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+        let err = import_watch_only_did_in(
+            &store,
+            "watcher".to_string(),
+            public_key.clone(),
+            None,
+            Some(vec![
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".to_string()
+            ]),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPublicKey(_)));
+
+        let info = import_watch_only_did_in(
+            &store,
+            "watcher".to_string(),
+            public_key,
+            Some(vec![WatchOnlyXpub {
+                address_type: BtcAddressType::NativeSegwit,
+                account: 0,
+                xpub: zpub.to_string(),
+                count: 2,
+            }]),
+            Some(vec![
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".to_string()
+            ]),
+            None,
+        )
+        .unwrap();
+        assert!(info.watch_only);
+        assert_eq!(info.bucky_wallets.len(), 1);
+        assert_eq!(info.btc_addresses.len(), 2);
+        assert_eq!(
+            info.btc_addresses[0].address,
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(
+            info.eth_addresses[0].address,
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        assert_eq!(list_dids_in(&store).unwrap()[0].id, info.id);
+
+        set_sn_status_in(
+            &store,
+            info.id.clone(),
+            SnStatusPayload {
+                username: Some("watcher".to_string()),
+                zone_config: None,
+            },
+        )
+        .unwrap();
+        assert!(list_sn_statuses_in(&store).unwrap().contains_key(&info.id));
+
+        let password = || Some("correct-horse-battery".to_string());
+        assert!(matches!(
+            reveal_mnemonic_in(&store, &sessions, password(), None).unwrap_err(),
+            CommandErrors::WatchOnly
+        ));
+        assert!(matches!(
+            sign_json_with_active_did_in(&store, &sessions, None, vec![serde_json::json!({})])
+                .unwrap_err(),
+            CommandErrors::WatchOnly
+        ));
+        // 缺少种子优先于备份未验证报告
+        store
+            .transaction(|vault| {
+                vault.dids[0].backup_status = BackupStatus::Unverified;
+                Ok(())
+            })
+            .unwrap();
+        assert!(matches!(
+            generate_zone_boot_config_jwt_in(
+                &store,
+                &sessions,
+                password(),
+                Some(info.id.clone()),
+                None,
+                None,
+            )
+            .unwrap_err(),
+            CommandErrors::WatchOnly
+        ));
+        // 观察钱包里的 ETH 地址并非由该种子派生，不能补上种子
+        assert!(matches!(
+            import_did_in(
+                &store,
+                "owner".to_string(),
+                "correct-horse-battery".to_string(),
+                mnemonic_words,
+                None,
+                None,
+                None,
+            )
+            .unwrap_err(),
+            CommandErrors::IdentityExists
+        ));
+
+        // 观察钱包不需要密码即可删除
+        delete_wallet_in(&store, &sessions, None, Some(info.id)).unwrap();
+        assert!(list_dids_in(&store).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_importing_the_seed_upgrades_a_watch_only_did() {
+        let (store, sessions) = memory_vault();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic_words = words(phrase);
+        let public_key = derive_bucky_public_key(mnemonic_words.clone(), None).unwrap();
+        let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+        let watcher = import_watch_only_did_in(
+            &store,
+            "watcher".to_string(),
+            public_key,
+            Some(vec![WatchOnlyXpub {
+                address_type: BtcAddressType::NativeSegwit,
+                account: 0,
+                xpub: zpub.to_string(),
+                count: 25,
+            }]),
+            Some(vec![
+                "0x9858EfFD232B4033E47d90003D41EC34EcaEda94".to_string()
+            ]),
+            None,
+        )
+        .unwrap();
+        set_sn_status_in(
+            &store,
+            watcher.id.clone(),
+            SnStatusPayload {
+                username: Some("watcher".to_string()),
+                zone_config: None,
+            },
+        )
+        .unwrap();
+
+        // 网络不一致时派生出的地址不同，拒绝补上种子
+        let err = import_did_in(
+            &store,
+            "owner".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words.clone(),
+            None,
+            Some(BtcNetwork::Testnet),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::IdentityExists));
+
+        let upgraded = import_did_in(
+            &store,
+            "owner".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(upgraded.id, watcher.id);
+        assert_eq!(upgraded.nickname, "watcher");
+        assert!(!upgraded.watch_only);
+        assert_eq!(
+            upgraded
+                .btc_addresses
+                .iter()
+                .filter(|entry| entry.address_type == BtcAddressType::NativeSegwit)
+                .count(),
+            25
+        );
+        assert_eq!(list_dids_in(&store).unwrap().len(), 1);
+        assert!(list_sn_statuses_in(&store)
+            .unwrap()
+            .contains_key(&watcher.id));
+        let mnemonic = reveal_mnemonic_in(
+            &store,
+            &sessions,
+            Some("correct-horse-battery".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(mnemonic.join(" "), phrase);
+    }
+
+    #[test]
+    fn test_btc_network_selects_coin_type_and_hrp() {
+        let (store, sessions) = memory_vault();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic_words = words(phrase);
+        let password = || Some("correct-horse-battery".to_string());
+
+        let created = create_did_in(
+            &store,
+            "qa".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words,
+            None,
+            None,
+            Some(BtcNetwork::Regtest),
+        )
+        .unwrap();
+        assert_eq!(created.btc_network, BtcNetwork::Regtest);
+
+        // m/84'/1'/0'/0/0，与 testnet 的 tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl 同一公钥
+        let info = extend_wallets_in(
+            &store,
+            &sessions,
+            password(),
+            created.id,
+            WalletExtensionKind::Btc {
+                address_type: BtcAddressType::NativeSegwit,
+                account: 0,
+                chain: AddressChain::Receive,
+                count: 1,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            info.btc_addresses[0].address,
+            "bcrt1q6rz28mcfaxtmd6v789l9rrlrusdprr9pz3cppk"
+        );
+
+        // 主网 zpub 不能导入到测试网的观察钱包
+        let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+        let native = BtcAddressType::NativeSegwit;
+        assert!(parse_account_xpub(zpub, BtcNetwork::Mainnet, native).is_ok());
+        assert!(matches!(
+            parse_account_xpub(zpub, BtcNetwork::Testnet, native).unwrap_err(),
+            CommandErrors::InvalidPublicKey(_)
+        ));
+        // zpub 只能用于原生隔离见证地址
+        assert!(matches!(
+            parse_account_xpub(zpub, BtcNetwork::Mainnet, BtcAddressType::NestedSegwit)
+                .unwrap_err(),
+            CommandErrors::InvalidPublicKey(_)
+        ));
+        // BIP32 测试向量 1 的主公钥，深度为 0，不是账户级公钥
+        let master = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+        assert!(matches!(
+            parse_account_xpub(master, BtcNetwork::Mainnet, native).unwrap_err(),
+            CommandErrors::InvalidPublicKey(_)
+        ));
+    }
+
+    #[test]
+    fn test_accounts_and_change_chains_keep_own_series() {
+        let (store, sessions) = memory_vault();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic_words = words(phrase);
+        let password = || Some("correct-horse-battery".to_string());
+        let created = create_did_in(
+            &store,
+            "accounts".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let extend = |account: u32, chain: AddressChain, count: u32| {
+            extend_wallets_in(
+                &store,
+                &sessions,
+                password(),
+                created.id.clone(),
+                WalletExtensionKind::Btc {
+                    address_type: BtcAddressType::NativeSegwit,
+                    account,
+                    chain,
+                    count,
+                },
+            )
+            .unwrap()
+        };
+
+        extend(0, AddressChain::Receive, 1);
+        extend(0, AddressChain::Change, 2);
+        let info = extend(1, AddressChain::Receive, 1);
+
+        // BIP84 测试向量：m/84'/0'/0'/0/0 与 m/84'/0'/0'/1/0
+        let find = |account: u32, chain: AddressChain, index: u32| {
+            info.btc_addresses
+                .iter()
+                .find(|a| a.account == account && a.chain == chain && a.index == index)
+                .map(|a| a.address.as_str())
+        };
+        assert_eq!(info.btc_addresses.len(), 4);
+        assert_eq!(
+            find(0, AddressChain::Receive, 0),
+            Some("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu")
+        );
+        assert_eq!(
+            find(0, AddressChain::Change, 0),
+            Some("bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el")
+        );
+        assert!(find(0, AddressChain::Change, 1).is_some());
+        assert_eq!(
+            find(1, AddressChain::Receive, 0),
+            Some("bc1qku0qh0mc00y8tk0n65x2tqw4trlspak0fnjmfz")
+        );
+
+        // 每个账户与链各自计数
+        let info = extend(0, AddressChain::Change, 1);
+        assert_eq!(info.btc_addresses.len(), 5);
+        assert!(info
+            .btc_addresses
+            .iter()
+            .any(|a| a.chain == AddressChain::Change && a.index == 2));
+    }
+
+    #[test]
+    fn test_export_account_xpubs_for_every_address_type() {
+        let (store, sessions) = memory_vault();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic_words = words(phrase);
+        let password = || Some("correct-horse-battery".to_string());
+        create_did_in(
+            &store,
+            "signet".to_string(),
+            "correct-horse-battery".to_string(),
+            mnemonic_words,
+            None,
+            None,
+            Some(BtcNetwork::Signet),
+        )
+        .unwrap();
+
+        let exports =
+            export_account_xpubs_in(&store, &sessions, password(), None, Some(1)).unwrap();
+        let prefixes: Vec<&str> = exports.iter().map(|e| &e.xpub[..4]).collect();
+        assert_eq!(prefixes, ["tpub", "upub", "vpub", "tpub"]);
+        assert_eq!(exports[2].path, "m/84'/1'/1'");
+        assert!(exports[3]
+            .descriptor
+            .starts_with("tr([73c5da0a/86'/1'/1']tpub"));
+        assert!(exports[1]
+            .change_descriptor
+            .starts_with("sh(wpkh([73c5da0a/49'/1'/1']tpub"));
+
+        // 导出的 vpub 可以作为观察钱包重新导入
+        let public_key = derive_bucky_public_key(words(phrase), None).unwrap();
+        let watcher = VaultHandle::new(MemoryBackend::default());
+        let info = import_watch_only_did_in(
+            &watcher,
+            "watcher".to_string(),
+            public_key,
+            Some(vec![WatchOnlyXpub {
+                address_type: BtcAddressType::NativeSegwit,
+                account: 1,
+                xpub: exports[2].xpub.clone(),
+                count: 1,
+            }]),
+            None,
+            Some(BtcNetwork::Signet),
+        )
+        .unwrap();
+        assert!(info.btc_addresses[0].address.starts_with("tb1q"));
+        assert!(matches!(
+            export_account_xpubs_in(&watcher, &sessions, password(), None, None).unwrap_err(),
+            CommandErrors::WatchOnly
+        ));
+    }
+
+    #[test]
+    fn test_sign_psbt_requires_unlocked_seed() {
+        let (store, sessions) = memory_vault();
+        create_did_in(
+            &store,
+            "alice".to_string(),
+            "correct-horse-battery".to_string(),
+            generate_mnemonic(None, None, None).unwrap(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            sign_psbt_in(&store, &sessions, None, None, "cHNidP8=".to_string()).unwrap_err(),
+            CommandErrors::SessionLocked
+        ));
+        assert!(matches!(
+            sign_psbt_in(
+                &store,
+                &sessions,
+                Some("wrong-password".to_string()),
+                None,
+                "cHNidP8=".to_string(),
+            )
+            .unwrap_err(),
+            CommandErrors::InvalidPassword
+        ));
+        assert!(matches!(
+            sign_psbt_in(
+                &store,
+                &sessions,
+                Some("correct-horse-battery".to_string()),
+                None,
+                "cHNidP8=".to_string(),
+            )
+            .unwrap_err(),
+            CommandErrors::InvalidPsbt(_)
+        ));
+    }
+
+    #[test]
+    fn test_sign_btc_message_for_every_stored_address() {
+        let (store, sessions) = memory_vault();
+        let password = || Some("correct-horse-battery".to_string());
+        let created = create_did_in(
+            &store,
+            "alice".to_string(),
+            "correct-horse-battery".to_string(),
+            generate_mnemonic(None, None, None).unwrap(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        for address_type in BtcAddressType::ALL {
+            extend_wallets_in(
+                &store,
+                &sessions,
+                password(),
+                created.id.clone(),
+                WalletExtensionKind::Btc {
+                    address_type,
+                    account: 0,
+                    chain: AddressChain::Change,
+                    count: 1,
+                },
+            )
+            .unwrap();
+        }
+        let info = list_dids_in(&store).unwrap().remove(0);
+        assert_eq!(info.btc_addresses.len(), 4);
+
+        for entry in &info.btc_addresses {
+            let signature = sign_btc_message_in(
+                &store,
+                &sessions,
+                password(),
+                None,
+                entry.address.clone(),
+                "proof of reserves".to_string(),
+            )
+            .unwrap();
+            assert!(verify_btc_message(
+                entry.address.clone(),
+                "proof of reserves".to_string(),
+                signature.clone(),
+                None,
+            )
+            .unwrap());
+            assert!(!verify_btc_message(
+                entry.address.clone(),
+                "proof of reserve".to_string(),
+                signature.clone(),
+                None,
+            )
+            .unwrap());
+            // 主网地址不能按测试网校验
+            assert!(matches!(
+                verify_btc_message(
+                    entry.address.clone(),
+                    "proof of reserves".to_string(),
+                    signature,
+                    Some(BtcNetwork::Testnet),
+                )
+                .unwrap_err(),
+                CommandErrors::InvalidAddress(_)
+            ));
+        }
+
+        let err = sign_btc_message_in(
+            &store,
+            &sessions,
+            password(),
+            None,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            "hello".to_string(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, CommandErrors::NotFound(ref msg) if msg == "address_not_found"));
+        assert!(matches!(
+            verify_btc_message("not-an-address".into(), "hello".into(), String::new(), None)
+                .unwrap_err(),
+            CommandErrors::InvalidAddress(_)
+        ));
+    }
 }
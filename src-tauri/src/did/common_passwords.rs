@@ -0,0 +1,36 @@
+/// Frequently used passwords, lowercased and sorted for binary search.
+#[rustfmt::skip]
+pub const COMMON_PASSWORDS: &[&str] = &[
+    "000000", "00000000", "0987654321", "1111", "11111", "111111", "11111111", "112233", "11223344",
+    "121212", "123123", "123321", "1234", "12341234", "12344321", "12345", "1234554321", "123456",
+    "1234567", "12345678", "123456789", "1234567890", "12345678910", "123456789a", "123456a",
+    "1234qwer", "1234test", "123654", "123abc", "123qwe", "131313", "1314520", "147258",
+    "147258369", "159357", "159753", "1q2w3e", "1q2w3e4r", "1q2w3e4r5t", "1qaz2wsx", "1qazxsw2",
+    "2000", "222222", "333333", "444444", "456123", "456789", "5201314", "555555", "654321",
+    "666666", "696969", "741852963", "777777", "7777777", "789456", "789456123", "88888888",
+    "963852741", "987654", "987654321", "987654321a", "999999", "a123456", "a1b2c3", "a1b2c3d4",
+    "aaaaaa", "abc123", "abcd1234", "abcdef", "abcdefg", "abcdefgh", "access", "admin", "admin1",
+    "admin123", "admin1234", "administrator", "aini1314", "amanda", "andrew", "angel", "angel1",
+    "apple", "apple123", "arsenal", "asd123", "asdasd", "asdf1234", "asdfgh", "asdfghjkl", "ashley",
+    "austin", "azerty", "azertyuiop", "barcelona", "baseball", "baseball1", "batman", "batman1",
+    "benjamin", "bitcoin", "bitcoin123", "biteme", "blessed", "buckyos", "buster", "butterfly",
+    "changeme", "charlie", "cheese", "chelsea", "chelsea1", "computer", "computer1", "crypto",
+    "dallas", "daniel", "default", "dragon", "dragon1", "ethereum", "flower", "football",
+    "football1", "freedom", "george", "ginger", "google", "guest", "hannah", "harley", "hello",
+    "hello123", "hockey", "hottie", "hunter", "iloveu", "iloveyou", "iloveyou1", "iloveyou123",
+    "internet", "jackson", "jennifer", "jessica", "jesus", "jesus1", "jordan", "joshua", "killer",
+    "killer1", "klaster", "letmein", "letmein1", "letmein123", "liverpool", "login", "love",
+    "lovely", "loveme", "lovers", "maggie", "master", "master1", "master123", "matrix", "matthew",
+    "michael", "michelle", "mobilemail", "mom", "monitor", "monitoring", "monkey", "monkey1",
+    "montana", "moon", "moscow", "mustang", "naruto", "nicole", "p@ssw0rd", "p@ssword", "pass",
+    "passpass", "passw0rd", "password", "password!", "password01", "password1", "password12",
+    "password123", "password1234", "pepper", "photoshop", "pokemon", "princess", "princess1",
+    "q1w2e3r4", "q1w2e3r4t5", "qazwsx", "qq123456", "qwe123", "qweasd", "qweasdzxc", "qwer1234",
+    "qwerty", "qwerty123", "qwertyui", "qwertyuiop", "ranger", "realmadrid", "robert", "root",
+    "root123", "samsung", "secret", "secret123", "shadow", "shadow1", "soccer", "solo", "sophie",
+    "starwars", "starwars1", "summer", "sunshine", "sunshine1", "superman", "superman1", "taylor",
+    "test", "test123", "testing", "thomas", "thunder", "tigger", "toor", "trustme", "trustno1",
+    "user", "user123", "wallet", "wallet123", "welcome", "welcome01", "welcome1", "welcome123",
+    "whatever", "william", "woaini", "woaini1314", "yankees", "zaq12wsx", "zxc123", "zxcvbn",
+    "zxcvbnm", "zxcvbnm1", "zxcvbnm123",
+];
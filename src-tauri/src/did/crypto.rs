@@ -1,58 +1,296 @@
-use aes_gcm::{aead::Aead, aead::KeyInit, Aes256Gcm, Nonce};
-use bip39::Mnemonic;
+use aes_gcm::{aead::Aead, aead::KeyInit, aead::Payload, Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use bip39::{Language, Mnemonic};
 use pbkdf2::pbkdf2_hmac;
 use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
 use sha2::Sha256;
+use zeroize::{Zeroize, Zeroizing};
 
-use super::store::EncryptedSeed;
+use super::mnemonic::parse_mnemonic;
+use super::store::{EncryptedSeed, KdfParams};
 use crate::error::{CommandErrors, CommandResult};
 
-fn kdf(password: &str, salt: &[u8], iter: u32) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iter, &mut key);
-    key
+fn kdf(password: &str, salt: &[u8], params: KdfParams) -> CommandResult<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    match params {
+        KdfParams::Pbkdf2Sha256 { iterations } => {
+            pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut key[..]);
+        }
+        KdfParams::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+        } => {
+            let params = Params::new(memory_kib, iterations, parallelism, Some(key.len()))
+                .map_err(|e| CommandErrors::crypto_failed(format!("invalid argon2 params: {e}")))?;
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), salt, &mut key[..])
+                .map_err(|e| CommandErrors::crypto_failed(format!("argon2 failed: {e}")))?;
+        }
+    }
+    Ok(key)
 }
 
-pub fn encrypt_mnemonic(password: &str, mnemonic: &Mnemonic) -> CommandResult<EncryptedSeed> {
+/// Decrypted content of an [`EncryptedSeed`]: the BIP39 phrase and its
+/// optional BIP39 passphrase ("25th word"), empty when unused.
+#[derive(Clone)]
+pub struct SeedSecret {
+    pub phrase: Zeroizing<String>,
+    pub passphrase: Zeroizing<String>,
+}
+
+impl SeedSecret {
+    pub fn new(mnemonic: &Mnemonic, passphrase: &str) -> Self {
+        Self {
+            phrase: Zeroizing::new(mnemonic.to_string()),
+            passphrase: Zeroizing::new(passphrase.to_string()),
+        }
+    }
+
+    /// Passphrase as `name_lib` expects it: `None` when unused.
+    pub fn passphrase_opt(&self) -> Option<&str> {
+        Some(self.passphrase.as_str()).filter(|passphrase| !passphrase.is_empty())
+    }
+
+    pub fn mnemonic(&self) -> CommandResult<Mnemonic> {
+        Ok(parse_mnemonic(self.phrase.as_str())?)
+    }
+
+    /// Parses the phrase in the wordlist recorded for its DID.
+    pub fn mnemonic_in(&self, language: Language) -> CommandResult<Mnemonic> {
+        Ok(Mnemonic::parse_in(language, self.phrase.as_str())?)
+    }
+}
+
+// 带口令的 seed 以 JSON 同时保存助记词和口令；无口令时明文仍是纯助记词，
+// 与旧数据保持一致。助记词本身不会以 `{` 开头。
+#[derive(Serialize, Deserialize)]
+struct SeedPlaintext {
+    mnemonic: String,
+    passphrase: String,
+}
+
+impl Drop for SeedPlaintext {
+    fn drop(&mut self) {
+        self.mnemonic.zeroize();
+        self.passphrase.zeroize();
+    }
+}
+
+pub fn encrypt_mnemonic(
+    password: &str,
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+) -> CommandResult<EncryptedSeed> {
+    encrypt_mnemonic_with(password, mnemonic, passphrase, KdfParams::recommended())
+}
+
+fn encrypt_mnemonic_with(
+    password: &str,
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    params: KdfParams,
+) -> CommandResult<EncryptedSeed> {
+    let plaintext = if passphrase.is_empty() {
+        Zeroizing::new(mnemonic.to_string().into_bytes())
+    } else {
+        let content = SeedPlaintext {
+            mnemonic: mnemonic.to_string(),
+            passphrase: passphrase.to_string(),
+        };
+        Zeroizing::new(
+            serde_json::to_vec(&content).map_err(|e| CommandErrors::internal(e.to_string()))?,
+        )
+    };
+    seal(password, &plaintext, b"", params)
+}
+
+/// Encrypts `plaintext` with AES-256-GCM under a key derived from `password`.
+/// `aad` is authenticated but not stored; [`unseal`] must be given the same bytes.
+pub fn seal(
+    password: &str,
+    plaintext: &[u8],
+    aad: &[u8],
+    params: KdfParams,
+) -> CommandResult<EncryptedSeed> {
     let mut salt = [0u8; 16];
     OsRng.fill_bytes(&mut salt);
     let mut nonce_bytes = [0u8; 12];
     OsRng.fill_bytes(&mut nonce_bytes);
 
-    let iter = 100_000u32;
-    let key = kdf(password, &salt, iter);
-    let cipher = Aes256Gcm::new_from_slice(&key).expect("aes key");
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
-    let plaintext = mnemonic.to_string();
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext.as_bytes())
-        .map_err(|e| CommandErrors::crypto_failed(format!("encrypt failed: {e}")))?;
+    let key = kdf(password, &salt, params)?;
+    let ciphertext = encrypt_with_key(&key, &nonce_bytes, plaintext, aad)?;
 
     Ok(EncryptedSeed {
-        kdf_iter: iter,
+        kdf: params,
         kdf_salt_hex: hex::encode(salt),
         cipher_nonce_hex: hex::encode(nonce_bytes),
         cipher_hex: hex::encode(ciphertext),
     })
 }
 
-pub fn decrypt_mnemonic(password: &str, seed: &EncryptedSeed) -> CommandResult<String> {
-    let salt = hex::decode(&seed.kdf_salt_hex)
+/// AES-256-GCM with a caller-provided key, for data sealed under a key that is
+/// itself stored wrapped by [`seal`].
+pub fn encrypt_with_key(
+    key: &[u8; 32],
+    nonce: &[u8; 12],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> CommandResult<Vec<u8>> {
+    Aes256Gcm::new_from_slice(key)
+        .expect("aes key")
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|e| CommandErrors::crypto_failed(format!("encrypt failed: {e}")))
+}
+
+/// Inverse of [`encrypt_with_key`]; `None` when the key or `aad` is wrong or
+/// the ciphertext was tampered with.
+pub fn decrypt_with_key(
+    key: &[u8; 32],
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Option<Zeroizing<Vec<u8>>> {
+    if nonce.len() != 12 {
+        return None;
+    }
+    Aes256Gcm::new_from_slice(key)
+        .expect("aes key")
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map(Zeroizing::new)
+        .ok()
+}
+
+pub fn unseal(
+    password: &str,
+    sealed: &EncryptedSeed,
+    aad: &[u8],
+) -> CommandResult<Zeroizing<Vec<u8>>> {
+    let salt = hex::decode(&sealed.kdf_salt_hex)
         .map_err(|e| CommandErrors::vault_corrupted(format!("invalid salt: {e}")))?;
-    let nonce_bytes = hex::decode(&seed.cipher_nonce_hex)
+    let nonce_bytes = hex::decode(&sealed.cipher_nonce_hex)
         .map_err(|e| CommandErrors::vault_corrupted(format!("invalid nonce: {e}")))?;
-    let cipher_bytes = hex::decode(&seed.cipher_hex)
+    let cipher_bytes = hex::decode(&sealed.cipher_hex)
         .map_err(|e| CommandErrors::vault_corrupted(format!("invalid cipher: {e}")))?;
+    if nonce_bytes.len() != 12 {
+        return Err(CommandErrors::vault_corrupted("invalid nonce length"));
+    }
 
-    let key = kdf(password, &salt, seed.kdf_iter);
-    let cipher = Aes256Gcm::new_from_slice(&key).expect("aes key");
-    let nonce = Nonce::from_slice(&nonce_bytes);
+    let key = kdf(password, &salt, sealed.kdf)?;
+    decrypt_with_key(&key, &nonce_bytes, &cipher_bytes, aad).ok_or(CommandErrors::InvalidPassword)
+}
 
-    let plaintext = cipher
-        .decrypt(nonce, cipher_bytes.as_ref())
-        .map_err(|_| CommandErrors::invalid_password())?;
-    let phrase = String::from_utf8(plaintext)
+fn open_seed(password: &str, seed: &EncryptedSeed) -> CommandResult<SeedSecret> {
+    let plaintext = unseal(password, seed, b"")?;
+    if plaintext.first() == Some(&b'{') {
+        let content: SeedPlaintext = serde_json::from_slice(&plaintext)
+            .map_err(|e| CommandErrors::vault_corrupted(format!("invalid seed content: {e}")))?;
+        return Ok(SeedSecret {
+            phrase: Zeroizing::new(content.mnemonic.clone()),
+            passphrase: Zeroizing::new(content.passphrase.clone()),
+        });
+    }
+    let phrase = String::from_utf8(plaintext.to_vec())
         .map_err(|e| CommandErrors::vault_corrupted(format!("invalid utf8 mnemonic: {e}")))?;
-    Ok(phrase)
+    Ok(SeedSecret {
+        phrase: Zeroizing::new(phrase),
+        passphrase: Zeroizing::default(),
+    })
+}
+
+/// Decrypts `seed` with `password`. Seeds sealed with an outdated KDF (e.g. the
+/// legacy 100k-iteration PBKDF2) are re-wrapped in place with
+/// [`KdfParams::recommended`]; the returned flag tells the caller to persist
+/// the vault.
+pub fn decrypt_mnemonic(
+    password: &str,
+    seed: &mut EncryptedSeed,
+) -> CommandResult<(SeedSecret, bool)> {
+    let secret = open_seed(password, seed)?;
+    if !seed.needs_kdf_upgrade() {
+        return Ok((secret, false));
+    }
+
+    *seed = encrypt_mnemonic(password, &secret.mnemonic()?, &secret.passphrase)?;
+    log::info!("seed re-wrapped with {:?}", KdfParams::recommended());
+    Ok((secret, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_new_seed_uses_argon2id() {
+        let mnemonic = Mnemonic::parse(PHRASE).unwrap();
+        let mut seed = encrypt_mnemonic("password123", &mnemonic, "").unwrap();
+        assert_eq!(seed.kdf, KdfParams::recommended());
+
+        let (secret, upgraded) = decrypt_mnemonic("password123", &mut seed).unwrap();
+        assert_eq!(secret.phrase.as_str(), PHRASE);
+        assert!(!upgraded);
+        assert!(matches!(
+            decrypt_mnemonic("wrong", &mut seed),
+            Err(CommandErrors::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn test_legacy_pbkdf2_seed_is_upgraded_on_unlock() {
+        let mnemonic = Mnemonic::parse(PHRASE).unwrap();
+        let legacy_kdf = KdfParams::Pbkdf2Sha256 {
+            iterations: 100_000,
+        };
+        let mut seed = encrypt_mnemonic_with("password123", &mnemonic, "", legacy_kdf).unwrap();
+        assert!(seed.needs_kdf_upgrade());
+
+        assert!(matches!(
+            decrypt_mnemonic("wrong", &mut seed),
+            Err(CommandErrors::InvalidPassword)
+        ));
+        assert_eq!(seed.kdf, legacy_kdf);
+
+        let (secret, upgraded) = decrypt_mnemonic("password123", &mut seed).unwrap();
+        assert_eq!(secret.phrase.as_str(), PHRASE);
+        assert!(upgraded);
+        assert_eq!(seed.kdf, KdfParams::recommended());
+
+        let (secret, upgraded) = decrypt_mnemonic("password123", &mut seed).unwrap();
+        assert_eq!(secret.phrase.as_str(), PHRASE);
+        assert!(!upgraded);
+    }
+
+    #[test]
+    fn test_passphrase_is_sealed_with_the_seed() {
+        let mnemonic = Mnemonic::parse(PHRASE).unwrap();
+        let legacy_kdf = KdfParams::Pbkdf2Sha256 {
+            iterations: 100_000,
+        };
+        let mut seed =
+            encrypt_mnemonic_with("password123", &mnemonic, "25th word", legacy_kdf).unwrap();
+
+        let (secret, upgraded) = decrypt_mnemonic("password123", &mut seed).unwrap();
+        assert!(upgraded);
+        assert_eq!(secret.phrase.as_str(), PHRASE);
+        assert_eq!(secret.passphrase.as_str(), "25th word");
+
+        let (secret, _) = decrypt_mnemonic("password123", &mut seed).unwrap();
+        assert_eq!(secret.passphrase.as_str(), "25th word");
+        assert_eq!(secret.mnemonic().unwrap(), mnemonic);
+    }
 }
@@ -1,13 +1,20 @@
 use bip39::Mnemonic;
-use bitcoin::bip32::{DerivationPath, Xpriv, Xpub};
+use bitcoin::base58;
+use bitcoin::bip32::{DerivationPath, Fingerprint, Xpriv, Xpub};
 use bitcoin::key::Secp256k1;
-use bitcoin::{Address, PublicKey};
+use bitcoin::{Address, Network, PublicKey};
 use sha3::{Digest, Keccak256};
 
-use super::domain::BtcAddressType;
-use super::store::NETWORK;
+use super::domain::{AddressChain, BtcAddressType, BtcNetwork};
 use crate::error::{CommandErrors, CommandResult};
 
+const XPUB_VERSION: &[u8] = &[0x04, 0x88, 0xb2, 0x1e];
+const YPUB_VERSION: &[u8] = &[0x04, 0x9d, 0x7c, 0xb2];
+const ZPUB_VERSION: &[u8] = &[0x04, 0xb2, 0x47, 0x46];
+const TPUB_VERSION: &[u8] = &[0x04, 0x35, 0x87, 0xcf];
+const UPUB_VERSION: &[u8] = &[0x04, 0x4a, 0x52, 0x62];
+const VPUB_VERSION: &[u8] = &[0x04, 0x5f, 0x1c, 0xf6];
+
 pub struct SeedCtx {
     secp: Secp256k1<bitcoin::secp256k1::All>,
     master_xprv: Xpriv,
@@ -17,7 +24,8 @@ impl SeedCtx {
     pub fn new(mnemonic: &Mnemonic, passphrase: &str) -> CommandResult<Self> {
         let seed_bytes = mnemonic.to_seed(passphrase);
         let secp = Secp256k1::new();
-        let master_xprv = Xpriv::new_master(NETWORK, &seed_bytes).map_err(|e| {
+        // 主私钥的 network 只影响序列化前缀，不影响派生结果
+        let master_xprv = Xpriv::new_master(Network::Bitcoin, &seed_bytes).map_err(|e| {
             CommandErrors::key_derivation_failed(format!("master key derivation failed: {e}"))
         })?;
         Ok(Self { secp, master_xprv })
@@ -26,43 +34,205 @@ impl SeedCtx {
     pub fn secp(&self) -> &Secp256k1<bitcoin::secp256k1::All> {
         &self.secp
     }
+
+    /// Fingerprint of the master public key, the origin of key paths in
+    /// descriptors and PSBTs.
+    pub fn master_fingerprint(&self) -> Fingerprint {
+        self.master_xprv.fingerprint(self.secp())
+    }
+
+    /// Private key at `path` below the master key.
+    pub fn derive_priv(&self, path: &DerivationPath) -> CommandResult<Xpriv> {
+        self.master_xprv
+            .derive_priv(self.secp(), path)
+            .map_err(|e| {
+                CommandErrors::key_derivation_failed(format!("btc derive_priv failed: {e}"))
+            })
+    }
+}
+
+/// Account path `m/purpose'/coin_type'/account'`.
+pub fn btc_account_path(
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+    account: u32,
+) -> CommandResult<DerivationPath> {
+    let purpose = address_type.purpose();
+    let coin_type = network.coin_type();
+    format!("m/{purpose}'/{coin_type}'/{account}'")
+        .parse()
+        .map_err(|e| CommandErrors::key_derivation_failed(format!("invalid derivation path: {e}")))
+}
+
+/// Extended public key of the account `m/purpose'/coin_type'/account'`.
+pub fn derive_account_xpub(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+    account: u32,
+) -> CommandResult<(DerivationPath, Xpub)> {
+    let path = btc_account_path(network, address_type, account)?;
+    let account_prv = ctx.derive_priv(&path)?;
+    Ok((path, Xpub::from_priv(ctx.secp(), &account_prv)))
 }
 
+/// Address at `m/purpose'/coin_type'/account'/chain/index`.
 pub fn derive_btc_address(
     ctx: &SeedCtx,
+    network: BtcNetwork,
     address_type: BtcAddressType,
+    account: u32,
+    chain: AddressChain,
     index: u32,
 ) -> CommandResult<Address> {
-    let purpose = address_type.purpose();
-    let path: DerivationPath = format!("m/{}'/0'/0'/0/{index}", purpose)
+    let account_path = btc_account_path(network, address_type, account)?;
+    let path: DerivationPath = format!("{account_path}/{}/{index}", chain.index())
         .parse()
-        .expect("static derivation path");
-    let child_prv = ctx
-        .master_xprv
-        .derive_priv(ctx.secp(), &path)
         .map_err(|e| {
-            CommandErrors::key_derivation_failed(format!("btc derive_priv failed: {e}"))
+            CommandErrors::key_derivation_failed(format!("invalid derivation index: {e}"))
         })?;
+    let child_prv = ctx.derive_priv(&path)?;
     let child_pub = Xpub::from_priv(ctx.secp(), &child_prv);
-    let secp_pk = child_pub.public_key;
+    btc_address(ctx.secp(), network, address_type, child_pub.public_key)
+}
+
+/// Parses an account-level extended public key in `xpub`, `ypub` or `zpub`
+/// form (`tpub`, `upub` or `vpub` off mainnet); the SLIP-132 forms only
+/// differ in their version bytes. A `ypub`/`zpub` prefix must match
+/// `address_type`, the key must sit at account depth (`m/purpose'/coin'/account'`)
+/// and belong to `network`.
+pub fn parse_account_xpub(
+    encoded: &str,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+) -> CommandResult<Xpub> {
+    let mut data = base58::decode_check(encoded.trim())
+        .map_err(|e| CommandErrors::invalid_public_key(format!("invalid xpub: {e}")))?;
+    let slip132_type = if data.starts_with(YPUB_VERSION) || data.starts_with(UPUB_VERSION) {
+        Some(BtcAddressType::NestedSegwit)
+    } else if data.starts_with(ZPUB_VERSION) || data.starts_with(VPUB_VERSION) {
+        Some(BtcAddressType::NativeSegwit)
+    } else {
+        None
+    };
+    if slip132_type.is_some_and(|prefix_type| prefix_type != address_type) {
+        return Err(CommandErrors::invalid_public_key(format!(
+            "xpub prefix does not match {address_type:?}"
+        )));
+    }
+    if data.starts_with(YPUB_VERSION) || data.starts_with(ZPUB_VERSION) {
+        data[..4].copy_from_slice(XPUB_VERSION);
+    } else if data.starts_with(UPUB_VERSION) || data.starts_with(VPUB_VERSION) {
+        data[..4].copy_from_slice(TPUB_VERSION);
+    }
+    let xpub = Xpub::decode(&data)
+        .map_err(|e| CommandErrors::invalid_public_key(format!("invalid xpub: {e}")))?;
+    if xpub.depth != 3 {
+        return Err(CommandErrors::invalid_public_key(format!(
+            "xpub depth {} is not an account key",
+            xpub.depth
+        )));
+    }
+    // 扩展公钥的前缀只区分主网与测试网，signet/regtest 与 testnet 共用
+    if (xpub.network == Network::Bitcoin) != (network == BtcNetwork::Mainnet) {
+        return Err(CommandErrors::invalid_public_key(format!(
+            "xpub does not belong to {network:?}"
+        )));
+    }
+    Ok(xpub)
+}
+
+/// Encodes an account key with the SLIP-132 prefix of `address_type`:
+/// `xpub`/`ypub`/`zpub` on mainnet, `tpub`/`upub`/`vpub` elsewhere. Taproot
+/// has no registered prefix and uses `xpub`/`tpub`.
+pub fn encode_slip132_xpub(
+    xpub: &Xpub,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+) -> String {
+    let mainnet = network == BtcNetwork::Mainnet;
+    let version = match address_type {
+        BtcAddressType::NestedSegwit if mainnet => YPUB_VERSION,
+        BtcAddressType::NestedSegwit => UPUB_VERSION,
+        BtcAddressType::NativeSegwit if mainnet => ZPUB_VERSION,
+        BtcAddressType::NativeSegwit => VPUB_VERSION,
+        BtcAddressType::Legacy | BtcAddressType::Taproot => {
+            return encode_xpub(xpub, network);
+        }
+    };
+    let mut data = xpub.encode();
+    data[..4].copy_from_slice(version);
+    base58::encode_check(&data)
+}
+
+/// Encodes a key as `xpub` on mainnet and `tpub` elsewhere, the form
+/// descriptors use.
+pub fn encode_xpub(xpub: &Xpub, network: BtcNetwork) -> String {
+    let mut data = xpub.encode();
+    data[..4].copy_from_slice(if network == BtcNetwork::Mainnet {
+        XPUB_VERSION
+    } else {
+        TPUB_VERSION
+    });
+    base58::encode_check(&data)
+}
+
+/// Address `chain/index` below the account key `xpub`, for watch-only DIDs.
+pub fn derive_btc_address_from_xpub(
+    xpub: &Xpub,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+    chain: AddressChain,
+    index: u32,
+) -> CommandResult<Address> {
+    let secp = Secp256k1::new();
+    let path: DerivationPath = format!("m/{}/{index}", chain.index())
+        .parse()
+        .map_err(|e| {
+            CommandErrors::key_derivation_failed(format!("invalid derivation index: {e}"))
+        })?;
+    let child = xpub
+        .derive_pub(&secp, &path)
+        .map_err(|e| CommandErrors::key_derivation_failed(format!("btc derive_pub failed: {e}")))?;
+    btc_address(&secp, network, address_type, child.public_key)
+}
+
+/// Address paying to `secp_pk`; taproot commits to the key alone, without
+/// a script tree.
+pub fn btc_address(
+    secp: &Secp256k1<bitcoin::secp256k1::All>,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+    secp_pk: bitcoin::secp256k1::PublicKey,
+) -> CommandResult<Address> {
+    let network = Network::from(network);
     let pubkey = PublicKey::new(secp_pk);
     let address = match address_type {
-        BtcAddressType::Legacy => Address::p2pkh(&pubkey, NETWORK),
-        BtcAddressType::NestedSegwit => Address::p2shwpkh(&pubkey, NETWORK)
+        BtcAddressType::Legacy => Address::p2pkh(&pubkey, network),
+        BtcAddressType::NestedSegwit => Address::p2shwpkh(&pubkey, network)
             .map_err(|e| CommandErrors::key_derivation_failed(format!("p2shwpkh failed: {e}")))?,
-        BtcAddressType::NativeSegwit => Address::p2wpkh(&pubkey, NETWORK)
+        BtcAddressType::NativeSegwit => Address::p2wpkh(&pubkey, network)
             .map_err(|e| CommandErrors::key_derivation_failed(format!("p2wpkh failed: {e}")))?,
         BtcAddressType::Taproot => {
             let (xonly, _parity) = pubkey.inner.x_only_public_key();
-            Address::p2tr(ctx.secp(), xonly, None, NETWORK)
+            Address::p2tr(secp, xonly, None, network)
         }
     };
     Ok(address)
 }
 
-pub fn derive_eth_address(ctx: &SeedCtx, index: u32) -> CommandResult<String> {
-    // 使用常见路径：m/44'/60'/0'/0/{index}
-    let path: DerivationPath = format!("m/44'/60'/0'/0/{index}").parse().unwrap();
+pub fn derive_eth_address(
+    ctx: &SeedCtx,
+    account: u32,
+    chain: AddressChain,
+    index: u32,
+) -> CommandResult<String> {
+    // 使用常见路径：m/44'/60'/{account}'/{chain}/{index}
+    let path: DerivationPath = format!("m/44'/60'/{account}'/{}/{index}", chain.index())
+        .parse()
+        .map_err(|e| {
+            CommandErrors::key_derivation_failed(format!("invalid derivation path: {e}"))
+        })?;
     let child_prv = ctx
         .master_xprv
         .derive_priv(ctx.secp(), &path)
@@ -78,6 +248,29 @@ pub fn derive_eth_address(ctx: &SeedCtx, index: u32) -> CommandResult<String> {
     Ok(to_eip55(&addr20))
 }
 
+/// Validates a `0x` ETH address and returns it in EIP-55 form. Mixed-case
+/// input must already carry a valid checksum.
+pub fn normalize_eth_address(address: &str) -> CommandResult<String> {
+    let address = address.trim();
+    let hex_part = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .ok_or_else(|| CommandErrors::invalid_public_key("eth address must start with 0x"))?;
+    let mut addr20 = [0u8; 20];
+    hex::decode_to_slice(hex_part, &mut addr20)
+        .map_err(|e| CommandErrors::invalid_public_key(format!("invalid eth address: {e}")))?;
+
+    let checksummed = to_eip55(&addr20);
+    let mixed_case = hex_part.chars().any(|c| c.is_ascii_lowercase())
+        && hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && checksummed[2..] != *hex_part {
+        return Err(CommandErrors::invalid_public_key(
+            "eth address checksum mismatch",
+        ));
+    }
+    Ok(checksummed)
+}
+
 fn to_eip55(addr20: &[u8; 20]) -> String {
     let lower_hex = hex::encode(addr20);
     let hash = Keccak256::digest(lower_hex.as_bytes());
@@ -0,0 +1,160 @@
+//! BIP380 output descriptors for the account keys of a DID, in the form
+//! watch-only wallets import: `wpkh([fpr/84'/0'/0']xpub…/0/*)#checksum`.
+
+use bitcoin::bip32::{DerivationPath, Fingerprint, Xpub};
+
+use super::derive::{derive_account_xpub, encode_slip132_xpub, encode_xpub, SeedCtx};
+use super::domain::{AccountXpub, AddressChain, BtcAddressType, BtcNetwork};
+use crate::error::CommandResult;
+
+const INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u64; 5] = [
+    0xf5dee51989,
+    0xa9fdca3312,
+    0x1bab10e32d,
+    0x3706b1677a,
+    0x644d626ffd,
+];
+
+/// Account key of `address_type` with its SLIP-132 encoding and the
+/// descriptors of its receive and change chains.
+pub fn export_account_xpub(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+    account: u32,
+) -> CommandResult<AccountXpub> {
+    let (path, xpub) = derive_account_xpub(ctx, network, address_type, account)?;
+    let fingerprint = ctx.master_fingerprint();
+    let descriptor =
+        |chain| account_descriptor(address_type, network, fingerprint, &path, &xpub, chain);
+
+    Ok(AccountXpub {
+        address_type,
+        account,
+        path: path.to_string(),
+        master_fingerprint: fingerprint.to_string(),
+        xpub: encode_slip132_xpub(&xpub, network, address_type),
+        descriptor: descriptor(AddressChain::Receive),
+        change_descriptor: descriptor(AddressChain::Change),
+    })
+}
+
+/// Descriptor of every address of `chain` below the account key, with the
+/// key origin `[fingerprint/path]` and checksum.
+pub fn account_descriptor(
+    address_type: BtcAddressType,
+    network: BtcNetwork,
+    fingerprint: Fingerprint,
+    path: &DerivationPath,
+    xpub: &Xpub,
+    chain: AddressChain,
+) -> String {
+    // DerivationPath 显示为 m/84'/0'/0'，key origin 中不带 m
+    let origin = path.to_string();
+    let origin = origin.trim_start_matches('m');
+    let key = format!(
+        "[{fingerprint}{origin}]{}/{}/*",
+        encode_xpub(xpub, network),
+        chain.index()
+    );
+    let descriptor = match address_type {
+        BtcAddressType::Legacy => format!("pkh({key})"),
+        BtcAddressType::NestedSegwit => format!("sh(wpkh({key}))"),
+        BtcAddressType::NativeSegwit => format!("wpkh({key})"),
+        BtcAddressType::Taproot => format!("tr({key})"),
+    };
+    add_checksum(&descriptor).expect("descriptor only uses checksum charset")
+}
+
+/// Appends the BIP380 checksum, `None` when `descriptor` contains a
+/// character outside the descriptor charset.
+pub fn add_checksum(descriptor: &str) -> Option<String> {
+    let mut checksum = polymod_checksum(descriptor)?;
+    let mut out = String::with_capacity(descriptor.len() + 9);
+    out.push_str(descriptor);
+    out.push('#');
+    let mut chars = [0u8; 8];
+    for slot in chars.iter_mut().rev() {
+        *slot = CHECKSUM_CHARSET[(checksum & 31) as usize];
+        checksum >>= 5;
+    }
+    out.extend(chars.iter().map(|&c| c as char));
+    Some(out)
+}
+
+fn polymod_checksum(descriptor: &str) -> Option<u64> {
+    let mut chk = 1u64;
+    let mut groups = Vec::with_capacity(3);
+    for c in descriptor.chars() {
+        let value = INPUT_CHARSET.find(c)? as u64;
+        chk = polymod_step(chk, value & 31);
+        groups.push(value >> 5);
+        if groups.len() == 3 {
+            chk = polymod_step(chk, groups[0] * 9 + groups[1] * 3 + groups[2]);
+            groups.clear();
+        }
+    }
+    match groups.as_slice() {
+        [a] => chk = polymod_step(chk, *a),
+        [a, b] => chk = polymod_step(chk, a * 3 + b),
+        _ => {}
+    }
+    for _ in 0..8 {
+        chk = polymod_step(chk, 0);
+    }
+    Some(chk ^ 1)
+}
+
+fn polymod_step(chk: u64, value: u64) -> u64 {
+    let top = chk >> 35;
+    let mut chk = ((chk & 0x7_ffff_ffff) << 5) ^ value;
+    for (i, generator) in GENERATOR.iter().enumerate() {
+        if (top >> i) & 1 == 1 {
+            chk ^= generator;
+        }
+    }
+    chk
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::Mnemonic;
+
+    #[test]
+    fn test_checksum_vector() {
+        // BIP380 示例
+        assert_eq!(
+            add_checksum("raw(deadbeef)").unwrap(),
+            "raw(deadbeef)#89f8spxm"
+        );
+        assert!(add_checksum("raw(\u{e9})").is_none());
+    }
+
+    #[test]
+    fn test_exports_bip84_account() {
+        let mnemonic = Mnemonic::parse(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+        let export =
+            export_account_xpub(&ctx, BtcNetwork::Mainnet, BtcAddressType::NativeSegwit, 0)
+                .unwrap();
+
+        // BIP84 测试向量
+        assert_eq!(export.path, "m/84'/0'/0'");
+        assert_eq!(export.master_fingerprint, "73c5da0a");
+        assert_eq!(
+            export.xpub,
+            "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs"
+        );
+        assert!(export.descriptor.starts_with(
+            "wpkh([73c5da0a/84'/0'/0']xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/0/*)#"
+        ));
+        assert!(export.change_descriptor.contains("/1/*)#"));
+    }
+}
@@ -0,0 +1,541 @@
+//! BIP44 account discovery: asks an Electrum-protocol server which derived
+//! scripts have history and keeps every address up to the last used one.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bitcoin::ScriptBuf;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::derive::{derive_btc_address, SeedCtx};
+use super::domain::{AddressChain, BtcAddress, BtcAddressType, BtcNetwork, WalletCollection};
+use crate::error::{CommandErrors, CommandResult};
+
+/// Consecutive unused addresses after which a chain is considered exhausted.
+pub const GAP_LIMIT: u32 = 20;
+/// Accounts scanned per address type before the server is distrusted.
+pub const MAX_ACCOUNTS: u32 = 100;
+/// Addresses scanned per chain before the server is distrusted.
+pub const MAX_SCANNED_INDEX: u32 = 10_000;
+const ELECTRUM_TIMEOUT: Duration = Duration::from_secs(15);
+/// Longest response line accepted from the server.
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+const ELECTRUM_PROTOCOL_VERSION: &str = "1.4";
+const CLIENT_NAME: &str = "buckyos-wallet";
+
+/// Answers whether scripts have ever appeared on chain.
+pub trait HistorySource {
+    /// One flag per script in `scripts`, in the same order.
+    fn has_history(&mut self, scripts: &[ScriptBuf]) -> CommandResult<Vec<bool>>;
+}
+
+/// Bounds of a discovery scan. A server reporting history beyond them is
+/// treated as broken rather than scanned further.
+#[derive(Debug, Clone, Copy)]
+pub struct DiscoveryLimits {
+    pub max_accounts: u32,
+    pub max_index: u32,
+}
+
+impl Default for DiscoveryLimits {
+    fn default() -> Self {
+        Self {
+            max_accounts: MAX_ACCOUNTS,
+            max_index: MAX_SCANNED_INDEX,
+        }
+    }
+}
+
+enum Transport {
+    Tcp(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.read(buf),
+            Self::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Tcp(stream) => stream.write(buf),
+            Self::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tcp(stream) => stream.flush(),
+            Self::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Minimal Electrum client over TCP or TLS, speaking newline-delimited
+/// JSON-RPC as described in the Electrum protocol.
+pub struct ElectrumClient {
+    stream: BufReader<Transport>,
+    next_id: u64,
+}
+
+impl ElectrumClient {
+    /// Connects to `server`, given as `host:port`, `tcp://host:port` or
+    /// `ssl://host:port`, and negotiates the protocol version. TLS servers
+    /// must present a certificate chaining to the Mozilla root store.
+    pub fn connect(server: &str) -> CommandResult<Self> {
+        let server = server.trim();
+        let (tls, address) = match server.split_once("://") {
+            None => (false, server),
+            Some(("tcp", rest)) => (false, rest),
+            Some(("ssl" | "tls", rest)) => (true, rest),
+            Some((scheme, _)) => {
+                return Err(CommandErrors::electrum_failed(format!(
+                    "unsupported scheme: {scheme}"
+                )))
+            }
+        };
+        let socket = address
+            .to_socket_addrs()
+            .map_err(|e| CommandErrors::electrum_failed(format!("{address}: {e}")))?
+            .next()
+            .ok_or_else(|| CommandErrors::electrum_failed(format!("{address}: no address")))?;
+        let stream = TcpStream::connect_timeout(&socket, ELECTRUM_TIMEOUT)
+            .map_err(|e| CommandErrors::electrum_failed(format!("{address}: {e}")))?;
+        stream
+            .set_read_timeout(Some(ELECTRUM_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(ELECTRUM_TIMEOUT)))
+            .map_err(|e| CommandErrors::electrum_failed(e.to_string()))?;
+        let transport = if tls {
+            Transport::Tls(Box::new(tls_stream(address, stream)?))
+        } else {
+            Transport::Tcp(stream)
+        };
+
+        let mut client = Self {
+            stream: BufReader::new(transport),
+            next_id: 0,
+        };
+        client.call_batch(
+            "server.version",
+            vec![json!([CLIENT_NAME, ELECTRUM_PROTOCOL_VERSION])],
+        )?;
+        Ok(client)
+    }
+
+    /// Sends one request per entry of `params` in a single write and returns
+    /// the results in request order.
+    fn call_batch(&mut self, method: &str, params: Vec<Value>) -> CommandResult<Vec<Value>> {
+        let first_id = self.next_id;
+        let mut payload = String::new();
+        for params in &params {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": self.next_id,
+                "method": method,
+                "params": params,
+            });
+            payload.push_str(&request.to_string());
+            payload.push('\n');
+            self.next_id += 1;
+        }
+        let writer = self.stream.get_mut();
+        writer
+            .write_all(payload.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| CommandErrors::electrum_failed(e.to_string()))?;
+
+        let mut results = vec![None; params.len()];
+        let mut pending = params.len();
+        let mut line = String::new();
+        while pending > 0 {
+            line.clear();
+            let read = (&mut self.stream)
+                .take(MAX_RESPONSE_BYTES)
+                .read_line(&mut line)
+                .map_err(|e| CommandErrors::electrum_failed(e.to_string()))?;
+            if read == 0 {
+                return Err(CommandErrors::electrum_failed("connection closed"));
+            }
+            if !line.ends_with('\n') && read as u64 == MAX_RESPONSE_BYTES {
+                return Err(CommandErrors::electrum_failed("response too large"));
+            }
+            let response: Value = serde_json::from_str(&line)
+                .map_err(|e| CommandErrors::electrum_failed(e.to_string()))?;
+            // 订阅通知没有 id，直接跳过
+            let Some(id) = response.get("id").and_then(Value::as_u64) else {
+                continue;
+            };
+            let slot = id
+                .checked_sub(first_id)
+                .and_then(|offset| results.get_mut(offset as usize))
+                .ok_or_else(|| CommandErrors::electrum_failed(format!("unexpected id {id}")))?;
+            if let Some(error) = response.get("error").filter(|error| !error.is_null()) {
+                return Err(CommandErrors::electrum_failed(error.to_string()));
+            }
+            if slot.is_none() {
+                pending -= 1;
+            }
+            *slot = Some(response.get("result").cloned().unwrap_or(Value::Null));
+        }
+
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
+/// Wraps `stream` in a TLS session for the host part of `address`.
+fn tls_stream(
+    address: &str,
+    stream: TcpStream,
+) -> CommandResult<StreamOwned<ClientConnection, TcpStream>> {
+    let host = address
+        .rsplit_once(':')
+        .map_or(address, |(host, _)| host)
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|e| CommandErrors::electrum_failed(format!("{host}: {e}")))?;
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| CommandErrors::electrum_failed(e.to_string()))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    let connection = ClientConnection::new(Arc::new(config), server_name)
+        .map_err(|e| CommandErrors::electrum_failed(e.to_string()))?;
+    Ok(StreamOwned::new(connection, stream))
+}
+
+impl HistorySource for ElectrumClient {
+    fn has_history(&mut self, scripts: &[ScriptBuf]) -> CommandResult<Vec<bool>> {
+        let params = scripts
+            .iter()
+            .map(|script| json!([script_hash(script)]))
+            .collect();
+        // subscribe 只返回状态哈希，无历史时为 null，响应大小与交易数量无关
+        self.call_batch("blockchain.scripthash.subscribe", params)?
+            .into_iter()
+            .map(|status| match status {
+                Value::Null => Ok(false),
+                Value::String(_) => Ok(true),
+                other => Err(CommandErrors::electrum_failed(format!(
+                    "unexpected status: {other}"
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Electrum script hash: SHA-256 of the scriptPubKey, byte-reversed, in hex.
+pub fn script_hash(script: &ScriptBuf) -> String {
+    let mut digest = Sha256::digest(script.as_bytes());
+    digest.reverse();
+    hex::encode(digest)
+}
+
+/// Scans every address type, walking accounts from 0 until one has no
+/// history, and returns the receive and change addresses up to the last used
+/// index of each chain. Fails once the history runs past `limits`.
+pub fn discover_btc_wallets(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    source: &mut impl HistorySource,
+    limits: DiscoveryLimits,
+) -> CommandResult<WalletCollection> {
+    let mut wallets = WalletCollection::default();
+
+    for address_type in BtcAddressType::ALL {
+        for account in 0u32.. {
+            if account >= limits.max_accounts {
+                return Err(CommandErrors::electrum_failed(format!(
+                    "history reported beyond account {}",
+                    limits.max_accounts
+                )));
+            }
+            let mut account_used = false;
+            for chain in [AddressChain::Receive, AddressChain::Change] {
+                let used = scan_chain(
+                    ctx,
+                    network,
+                    address_type,
+                    account,
+                    chain,
+                    source,
+                    limits.max_index,
+                )?;
+                if used.is_empty() {
+                    continue;
+                }
+                account_used = true;
+                let series = wallets.btc_series_mut(address_type, account, chain);
+                for entry in used {
+                    series.push_with_index(entry.index, entry);
+                }
+            }
+            if !account_used {
+                break;
+            }
+        }
+    }
+
+    Ok(wallets)
+}
+
+/// Addresses `0..=last_used` of one chain, or nothing if no address within
+/// the gap limit has history.
+fn scan_chain(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    address_type: BtcAddressType,
+    account: u32,
+    chain: AddressChain,
+    source: &mut impl HistorySource,
+    max_index: u32,
+) -> CommandResult<Vec<BtcAddress>> {
+    let mut addresses = Vec::new();
+    let mut last_used: Option<u32> = None;
+    let mut start = 0;
+
+    loop {
+        // 保证最后一个已用地址之后始终检查满 GAP_LIMIT 个地址
+        let end = last_used.map_or(GAP_LIMIT, |index| index + 1 + GAP_LIMIT);
+        if start >= end {
+            break;
+        }
+        if end > max_index {
+            return Err(CommandErrors::electrum_failed(format!(
+                "history reported beyond index {max_index}"
+            )));
+        }
+
+        let mut scripts = Vec::with_capacity((end - start) as usize);
+        for index in start..end {
+            let address = derive_btc_address(ctx, network, address_type, account, chain, index)?;
+            scripts.push(address.script_pubkey());
+            addresses.push(BtcAddress {
+                address_type,
+                account,
+                chain,
+                index,
+                address: address.to_string(),
+            });
+        }
+
+        let used = source.has_history(&scripts)?;
+        if used.len() != scripts.len() {
+            return Err(CommandErrors::electrum_failed("history count mismatch"));
+        }
+        if let Some(offset) = used.iter().rposition(|used| *used) {
+            last_used = Some(start + offset as u32);
+        }
+        start = end;
+    }
+
+    let keep = last_used.map_or(0, |index| index as usize + 1);
+    addresses.truncate(keep);
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bip39::Mnemonic;
+    use std::collections::HashSet;
+    use std::net::TcpListener;
+    use std::thread;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Serves `server.version` and `blockchain.scripthash.subscribe` for one
+    /// connection, reporting history for script hashes accepted by `used`.
+    fn spawn_mock_electrum(used: impl Fn(&str) -> bool + Send + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            for line in BufReader::new(stream).lines() {
+                let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+                let result = match request["method"].as_str().unwrap() {
+                    "server.version" => json!(["mock", ELECTRUM_PROTOCOL_VERSION]),
+                    "blockchain.scripthash.subscribe" => {
+                        let hash = request["params"][0].as_str().unwrap();
+                        if used(hash) {
+                            json!("ab".repeat(32))
+                        } else {
+                            Value::Null
+                        }
+                    }
+                    other => panic!("unexpected method {other}"),
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+                if writeln!(writer, "{response}").is_err() {
+                    break;
+                }
+            }
+        });
+        address
+    }
+
+    fn script_of(
+        ctx: &SeedCtx,
+        address_type: BtcAddressType,
+        account: u32,
+        chain: AddressChain,
+        index: u32,
+    ) -> String {
+        let address = derive_btc_address(
+            ctx,
+            BtcNetwork::Mainnet,
+            address_type,
+            account,
+            chain,
+            index,
+        )
+        .unwrap();
+        script_hash(&address.script_pubkey())
+    }
+
+    #[test]
+    fn test_script_hash_matches_electrum_docs() {
+        let script =
+            ScriptBuf::from_hex("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap();
+        assert_eq!(
+            script_hash(&script),
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+    }
+
+    #[test]
+    fn test_discovery_fills_series_up_to_last_used_index() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+        let segwit = BtcAddressType::NativeSegwit;
+        let taproot = BtcAddressType::Taproot;
+        let used = HashSet::from([
+            script_of(&ctx, segwit, 0, AddressChain::Receive, 0),
+            // 在第一个已用地址的间隔内，应继续扫描到它之后
+            script_of(&ctx, segwit, 0, AddressChain::Receive, 19),
+            script_of(&ctx, segwit, 0, AddressChain::Receive, 35),
+            script_of(&ctx, segwit, 0, AddressChain::Change, 2),
+            script_of(&ctx, segwit, 1, AddressChain::Receive, 4),
+            // 超出间隔限制，不应被发现
+            script_of(&ctx, segwit, 1, AddressChain::Receive, 30),
+            // 账户 0 未使用，按 BIP44 不再扫描账户 1
+            script_of(&ctx, taproot, 1, AddressChain::Receive, 0),
+        ]);
+
+        let server = spawn_mock_electrum(move |hash| used.contains(hash));
+        let mut client = ElectrumClient::connect(&format!("tcp://{server}")).unwrap();
+        let wallets = discover_btc_wallets(
+            &ctx,
+            BtcNetwork::Mainnet,
+            &mut client,
+            DiscoveryLimits::default(),
+        )
+        .unwrap();
+
+        let receive = wallets
+            .btc_series(segwit, 0, AddressChain::Receive)
+            .unwrap();
+        assert_eq!(receive.entries.len(), 36);
+        assert_eq!(
+            receive.entries[0].address,
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(receive.next_index(), 36);
+        let change = wallets.btc_series(segwit, 0, AddressChain::Change).unwrap();
+        assert_eq!(change.entries.len(), 3);
+        let account_one = wallets
+            .btc_series(segwit, 1, AddressChain::Receive)
+            .unwrap();
+        assert_eq!(account_one.entries.len(), 5);
+        assert!(wallets
+            .btc_series(segwit, 1, AddressChain::Change)
+            .is_none());
+        assert!(wallets
+            .btc_series(segwit, 2, AddressChain::Receive)
+            .is_none());
+        assert!(!wallets.btc.contains_key(&taproot));
+        assert!(!wallets.btc.contains_key(&BtcAddressType::Legacy));
+    }
+
+    #[test]
+    fn test_discovery_stops_at_limits_when_everything_is_used() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+        let limits = DiscoveryLimits {
+            max_accounts: 2,
+            max_index: 100,
+        };
+
+        let server = spawn_mock_electrum(|_| true);
+        let mut client = ElectrumClient::connect(&server).unwrap();
+        let err = discover_btc_wallets(&ctx, BtcNetwork::Mainnet, &mut client, limits).unwrap_err();
+        assert!(matches!(err, CommandErrors::ElectrumFailure(_)));
+
+        // 每条链只用第一个地址，账户数量触顶
+        let used: HashSet<String> = (0..limits.max_accounts)
+            .map(|account| {
+                script_of(
+                    &ctx,
+                    BtcAddressType::Legacy,
+                    account,
+                    AddressChain::Receive,
+                    0,
+                )
+            })
+            .collect();
+        let server = spawn_mock_electrum(move |hash| used.contains(hash));
+        let mut client = ElectrumClient::connect(&server).unwrap();
+        let err = discover_btc_wallets(&ctx, BtcNetwork::Mainnet, &mut client, limits).unwrap_err();
+        assert!(matches!(err, CommandErrors::ElectrumFailure(_)));
+    }
+
+    #[test]
+    fn test_rejects_oversized_responses() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let line = "x".repeat(MAX_RESPONSE_BYTES as usize * 2);
+            let _ = writeln!(stream, "{line}");
+        });
+
+        let result = ElectrumClient::connect(&address);
+        assert!(
+            matches!(result, Err(CommandErrors::ElectrumFailure(ref msg)) if msg == "response too large")
+        );
+    }
+
+    #[test]
+    fn test_connect_rejects_unknown_schemes() {
+        let result = ElectrumClient::connect("ws://electrum.example:50004");
+        assert!(matches!(result, Err(CommandErrors::ElectrumFailure(_))));
+    }
+
+    #[test]
+    fn test_tls_handshake_fails_against_plain_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = writeln!(stream, "{{\"jsonrpc\":\"2.0\"}}");
+        });
+
+        let result = ElectrumClient::connect(&format!("ssl://{address}"));
+        assert!(matches!(result, Err(CommandErrors::ElectrumFailure(_))));
+    }
+}
@@ -1,7 +1,10 @@
-use serde::{Deserialize, Serialize};
+use bip39::Language;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+use super::verification::BackupStatus;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
 #[serde(rename_all = "snake_case")]
@@ -13,6 +16,13 @@ pub enum BtcAddressType {
 }
 
 impl BtcAddressType {
+    pub const ALL: [BtcAddressType; 4] = [
+        Self::Legacy,
+        Self::NestedSegwit,
+        Self::NativeSegwit,
+        Self::Taproot,
+    ];
+
     pub fn purpose(self) -> u32 {
         match self {
             Self::Legacy => 44,
@@ -25,24 +35,154 @@ impl BtcAddressType {
 
 pub const DEFAULT_BTC_ADDRESS_TYPE: BtcAddressType = BtcAddressType::NativeSegwit;
 
+/// Bitcoin network a DID's BTC addresses belong to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BtcNetwork {
+    #[default]
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl BtcNetwork {
+    /// BIP44 coin type: 0 on mainnet, 1 on every test network.
+    pub fn coin_type(self) -> u32 {
+        match self {
+            Self::Mainnet => 0,
+            Self::Testnet | Self::Signet | Self::Regtest => 1,
+        }
+    }
+}
+
+impl From<BtcNetwork> for bitcoin::Network {
+    fn from(value: BtcNetwork) -> Self {
+        match value {
+            BtcNetwork::Mainnet => bitcoin::Network::Bitcoin,
+            BtcNetwork::Testnet => bitcoin::Network::Testnet,
+            BtcNetwork::Signet => bitcoin::Network::Signet,
+            BtcNetwork::Regtest => bitcoin::Network::Regtest,
+        }
+    }
+}
+
+/// BIP39 wordlist a DID's mnemonic was created in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MnemonicLanguage {
+    #[default]
+    English,
+    ChineseSimplified,
+    ChineseTraditional,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl From<MnemonicLanguage> for Language {
+    fn from(value: MnemonicLanguage) -> Self {
+        match value {
+            MnemonicLanguage::English => Language::English,
+            MnemonicLanguage::ChineseSimplified => Language::SimplifiedChinese,
+            MnemonicLanguage::ChineseTraditional => Language::TraditionalChinese,
+            MnemonicLanguage::Czech => Language::Czech,
+            MnemonicLanguage::French => Language::French,
+            MnemonicLanguage::Italian => Language::Italian,
+            MnemonicLanguage::Japanese => Language::Japanese,
+            MnemonicLanguage::Korean => Language::Korean,
+            MnemonicLanguage::Portuguese => Language::Portuguese,
+            MnemonicLanguage::Spanish => Language::Spanish,
+        }
+    }
+}
+
+impl From<Language> for MnemonicLanguage {
+    fn from(value: Language) -> Self {
+        match value {
+            Language::English => MnemonicLanguage::English,
+            Language::SimplifiedChinese => MnemonicLanguage::ChineseSimplified,
+            Language::TraditionalChinese => MnemonicLanguage::ChineseTraditional,
+            Language::Czech => MnemonicLanguage::Czech,
+            Language::French => MnemonicLanguage::French,
+            Language::Italian => MnemonicLanguage::Italian,
+            Language::Japanese => MnemonicLanguage::Japanese,
+            Language::Korean => MnemonicLanguage::Korean,
+            Language::Portuguese => MnemonicLanguage::Portuguese,
+            Language::Spanish => MnemonicLanguage::Spanish,
+        }
+    }
+}
+
+/// BIP44 chain below an account: external receive addresses or internal
+/// change addresses.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressChain {
+    #[default]
+    Receive,
+    Change,
+}
+
+impl AddressChain {
+    /// Path component of the chain: 0 for receive, 1 for change.
+    pub fn index(self) -> u32 {
+        match self {
+            Self::Receive => 0,
+            Self::Change => 1,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BtcAddress {
     #[serde(default = "default_btc_address_type")]
     pub address_type: BtcAddressType,
+    #[serde(default)]
+    pub account: u32,
+    #[serde(default)]
+    pub chain: AddressChain,
     pub index: u32,
     pub address: String,
 }
 
+impl BtcAddress {
+    /// Orders addresses by type, account, chain and index.
+    pub fn sort_key(&self) -> (u8, u32, u32, u32) {
+        (
+            self.address_type as u8,
+            self.account,
+            self.chain.index(),
+            self.index,
+        )
+    }
+}
+
 fn default_btc_address_type() -> BtcAddressType {
     DEFAULT_BTC_ADDRESS_TYPE
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChainAddress {
+    #[serde(default)]
+    pub account: u32,
+    #[serde(default)]
+    pub chain: AddressChain,
     pub index: u32,
     pub address: String,
 }
 
+impl ChainAddress {
+    /// Orders addresses by account, chain and index.
+    pub fn sort_key(&self) -> (u32, u32, u32) {
+        (self.account, self.chain.index(), self.index)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BuckyIdentity {
     pub index: u32,
@@ -58,20 +198,36 @@ pub struct SnStatusInfo {
     pub zone_config: Option<String>,
 }
 
+/// Addresses derived below one account and chain, in index order.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AddressSeries<T> {
+    /// 旧数据没有 account/chain，均为账户 0 的收款链
+    #[serde(default)]
+    pub account: u32,
+    #[serde(default)]
+    pub chain: AddressChain,
     pub entries: Vec<T>,
     pub next_index: u32,
 }
 
 impl<T> AddressSeries<T> {
     pub fn empty() -> Self {
+        Self::new(0, AddressChain::Receive)
+    }
+
+    pub fn new(account: u32, chain: AddressChain) -> Self {
         Self {
+            account,
+            chain,
             entries: Vec::new(),
             next_index: 0,
         }
     }
 
+    pub fn is_at(&self, account: u32, chain: AddressChain) -> bool {
+        self.account == account && self.chain == chain
+    }
+
     pub fn next_index(&self) -> u32 {
         self.next_index
     }
@@ -114,36 +270,221 @@ impl<T> Default for AddressSeries<T> {
     }
 }
 
+/// One series per account and chain. Records written before accounts were
+/// tracked hold a single series instead of a list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SeriesList<T> {
+    One(AddressSeries<T>),
+    Many(Vec<AddressSeries<T>>),
+}
+
+impl<T> SeriesList<T> {
+    fn into_vec(self) -> Vec<AddressSeries<T>> {
+        match self {
+            SeriesList::One(series) => vec![series],
+            SeriesList::Many(list) => list,
+        }
+    }
+}
+
+fn series_list<'de, D, T>(deserializer: D) -> Result<Vec<AddressSeries<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(SeriesList::deserialize(deserializer)?.into_vec())
+}
+
+fn btc_series_lists<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<BtcAddressType, Vec<AddressSeries<BtcAddress>>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let lists = HashMap::<BtcAddressType, SeriesList<BtcAddress>>::deserialize(deserializer)?;
+    Ok(lists
+        .into_iter()
+        .map(|(addr_type, list)| (addr_type, list.into_vec()))
+        .collect())
+}
+
+fn series_mut<T>(
+    list: &mut Vec<AddressSeries<T>>,
+    account: u32,
+    chain: AddressChain,
+) -> &mut AddressSeries<T> {
+    match list.iter().position(|series| series.is_at(account, chain)) {
+        Some(position) => &mut list[position],
+        None => {
+            list.push(AddressSeries::new(account, chain));
+            list.last_mut().expect("series just pushed")
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct WalletCollection {
-    #[serde(default)]
-    pub btc: HashMap<BtcAddressType, AddressSeries<BtcAddress>>,
-    #[serde(default)]
-    pub eth: AddressSeries<ChainAddress>,
+    #[serde(default, deserialize_with = "btc_series_lists")]
+    pub btc: HashMap<BtcAddressType, Vec<AddressSeries<BtcAddress>>>,
+    #[serde(default, deserialize_with = "series_list")]
+    pub eth: Vec<AddressSeries<ChainAddress>>,
     #[serde(default)]
     pub bucky: AddressSeries<BuckyIdentity>,
 }
 
 impl WalletCollection {
     pub fn merge(&mut self, other: WalletCollection) {
-        for (addr_type, series) in other.btc {
-            let target = self
-                .btc
-                .entry(addr_type)
-                .or_insert_with(AddressSeries::default);
-            target.extend_from(series.entries, |entry| entry.index);
+        for (addr_type, list) in other.btc {
+            for series in list {
+                self.btc_series_mut(addr_type, series.account, series.chain)
+                    .extend_from(series.entries, |entry| entry.index);
+            }
         }
 
-        self.eth.extend_from(other.eth.entries, |entry| entry.index);
+        for series in other.eth {
+            self.eth_series_mut(series.account, series.chain)
+                .extend_from(series.entries, |entry| entry.index);
+        }
         self.bucky
             .extend_from(other.bucky.entries, |entry| entry.index);
     }
 
-    pub fn btc_series_mut(&mut self, addr_type: BtcAddressType) -> &mut AddressSeries<BtcAddress> {
+    /// Like [`WalletCollection::merge`], but skips entries of `other` whose
+    /// index a series of `self` already holds. Both must come from the same
+    /// seed, so a shared index is the same address.
+    pub fn merge_missing(&mut self, mut other: WalletCollection) {
+        for (addr_type, list) in other.btc.iter_mut() {
+            for series in list {
+                if let Some(known) = self.btc_series(*addr_type, series.account, series.chain) {
+                    series
+                        .entries
+                        .retain(|entry| entry.index >= known.next_index());
+                }
+            }
+        }
+        for series in other.eth.iter_mut() {
+            if let Some(known) = self.eth_series(series.account, series.chain) {
+                series
+                    .entries
+                    .retain(|entry| entry.index >= known.next_index());
+            }
+        }
+        if !self.bucky.entries.is_empty() {
+            let known = self.bucky.next_index();
+            other.bucky.entries.retain(|entry| entry.index >= known);
+        }
+        self.merge(other);
+    }
+
+    pub fn btc_series(
+        &self,
+        addr_type: BtcAddressType,
+        account: u32,
+        chain: AddressChain,
+    ) -> Option<&AddressSeries<BtcAddress>> {
         self.btc
-            .entry(addr_type)
-            .or_insert_with(AddressSeries::default)
+            .get(&addr_type)?
+            .iter()
+            .find(|series| series.is_at(account, chain))
     }
+
+    pub fn btc_series_mut(
+        &mut self,
+        addr_type: BtcAddressType,
+        account: u32,
+        chain: AddressChain,
+    ) -> &mut AddressSeries<BtcAddress> {
+        series_mut(self.btc.entry(addr_type).or_default(), account, chain)
+    }
+
+    pub fn eth_series(
+        &self,
+        account: u32,
+        chain: AddressChain,
+    ) -> Option<&AddressSeries<ChainAddress>> {
+        self.eth.iter().find(|series| series.is_at(account, chain))
+    }
+
+    pub fn eth_series_mut(
+        &mut self,
+        account: u32,
+        chain: AddressChain,
+    ) -> &mut AddressSeries<ChainAddress> {
+        series_mut(&mut self.eth, account, chain)
+    }
+
+    pub fn btc_addresses(&self) -> impl Iterator<Item = &BtcAddress> {
+        self.btc
+            .values()
+            .flatten()
+            .flat_map(|series| series.entries.iter())
+    }
+
+    pub fn eth_addresses(&self) -> impl Iterator<Item = &ChainAddress> {
+        self.eth.iter().flat_map(|series| series.entries.iter())
+    }
+}
+
+/// Account-level extended public key of one BTC address type, for loading
+/// the account into watch-only wallets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountXpub {
+    pub address_type: BtcAddressType,
+    pub account: u32,
+    /// Account path, e.g. `m/84'/0'/0'`.
+    pub path: String,
+    /// Fingerprint of the master key as 8 hex characters.
+    pub master_fingerprint: String,
+    /// SLIP-132 encoded key, e.g. `zpub…` for native segwit on mainnet.
+    pub xpub: String,
+    /// BIP380 descriptor of the receive chain, with checksum.
+    pub descriptor: String,
+    /// BIP380 descriptor of the change chain, with checksum.
+    pub change_descriptor: String,
+}
+
+/// A PSBT after signing, with what it spends for the user to confirm.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedPsbt {
+    /// Updated PSBT in base64.
+    pub psbt: String,
+    pub inputs: Vec<PsbtInputSummary>,
+    pub outputs: Vec<PsbtOutputSummary>,
+    /// Inputs minus outputs, if every input carries its previous output.
+    pub fee_sats: Option<u64>,
+    /// Whether the input amounts behind `fee_sats` are committed to: every
+    /// input carries its txid-checked previous transaction, or a taproot
+    /// signature covers all previous outputs. Otherwise the fee is only what
+    /// the PSBT claims.
+    pub fee_verified: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PsbtInputSummary {
+    /// Spent outpoint as `txid:vout`.
+    pub previous_output: String,
+    pub address: Option<String>,
+    pub amount_sats: Option<u64>,
+    /// Whether this DID added a signature to the input.
+    pub signed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PsbtOutputSummary {
+    pub address: Option<String>,
+    pub amount_sats: u64,
+    /// Whether the output pays back to a key of this DID, i.e. is change.
+    pub is_mine: bool,
+}
+
+/// A word of an entered mnemonic that is not in the wordlist.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMnemonicWord {
+    /// 1-based position in the entered words.
+    pub position: usize,
+    pub word: String,
+    pub suggestions: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -153,6 +494,22 @@ pub struct DidInfo {
     pub btc_addresses: Vec<BtcAddress>,
     pub eth_addresses: Vec<ChainAddress>,
     pub bucky_wallets: Vec<BuckyIdentity>,
+    pub mnemonic_language: MnemonicLanguage,
+    #[serde(default)]
+    pub btc_network: BtcNetwork,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sn_status: Option<SnStatusInfo>,
+    /// Wrong passwords entered in a row since the last successful unlock.
+    #[serde(default)]
+    pub failed_password_attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wipe_after_failures: Option<u32>,
+    #[serde(default)]
+    pub backup_status: BackupStatus,
+    /// Imported from public keys only; nothing can be signed with it.
+    #[serde(default)]
+    pub watch_only: bool,
+    /// Set for DIDs of a locked vault, which only expose their id.
+    #[serde(default)]
+    pub redacted: bool,
 }
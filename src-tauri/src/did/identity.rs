@@ -2,13 +2,24 @@ use bip39::Mnemonic;
 use name_lib::{generate_ed25519_key_pair_from_mnemonic, get_device_did_from_ed25519_jwk};
 
 use super::derive::{derive_btc_address, derive_eth_address, SeedCtx};
-use super::domain::{BtcAddress, BtcAddressType, BuckyIdentity, ChainAddress, WalletCollection};
+use super::domain::{
+    AddressChain, BtcAddress, BtcAddressType, BtcNetwork, BuckyIdentity, ChainAddress,
+    WalletCollection,
+};
 use crate::error::{CommandErrors, CommandResult};
 
 #[derive(Clone, Debug)]
 pub enum WalletKind {
-    Btc { address_type: BtcAddressType },
-    Eth,
+    Btc {
+        network: BtcNetwork,
+        address_type: BtcAddressType,
+        account: u32,
+        chain: AddressChain,
+    },
+    Eth {
+        account: u32,
+        chain: AddressChain,
+    },
     Bucky,
 }
 
@@ -34,37 +45,53 @@ impl DidDerivationPlan {
         for request in requests {
             match request {
                 WalletRequest::Btc {
+                    network,
                     address_type,
+                    account,
+                    chain,
                     count,
                 } => {
                     if *count == 0 {
                         continue;
                     }
                     let start = existing
-                        .and_then(|collection| collection.btc.get(address_type))
+                        .and_then(|collection| {
+                            collection.btc_series(*address_type, *account, *chain)
+                        })
                         .map(|series| series.next_index())
                         .unwrap_or(0);
                     let indices: Vec<u32> = (start..start.saturating_add(*count)).collect();
                     if !indices.is_empty() {
                         wallets.push(WalletPlan {
                             kind: WalletKind::Btc {
+                                network: *network,
                                 address_type: *address_type,
+                                account: *account,
+                                chain: *chain,
                             },
                             indices,
                         });
                     }
                 }
-                WalletRequest::Eth { count } => {
+                WalletRequest::Eth {
+                    account,
+                    chain,
+                    count,
+                } => {
                     if *count == 0 {
                         continue;
                     }
                     let start = existing
-                        .map(|collection| collection.eth.next_index())
+                        .and_then(|collection| collection.eth_series(*account, *chain))
+                        .map(|series| series.next_index())
                         .unwrap_or(0);
                     let indices: Vec<u32> = (start..start.saturating_add(*count)).collect();
                     if !indices.is_empty() {
                         wallets.push(WalletPlan {
-                            kind: WalletKind::Eth,
+                            kind: WalletKind::Eth {
+                                account: *account,
+                                chain: *chain,
+                            },
                             indices,
                         });
                     }
@@ -98,10 +125,15 @@ impl DidDerivationPlan {
 #[derive(Clone, Debug)]
 pub enum WalletRequest {
     Btc {
+        network: BtcNetwork,
         address_type: BtcAddressType,
+        account: u32,
+        chain: AddressChain,
         count: u32,
     },
     Eth {
+        account: u32,
+        chain: AddressChain,
         count: u32,
     },
     Bucky {
@@ -110,15 +142,28 @@ pub enum WalletRequest {
 }
 
 impl WalletRequest {
-    pub fn btc(address_type: BtcAddressType, count: u32) -> Self {
+    pub fn btc(
+        network: BtcNetwork,
+        address_type: BtcAddressType,
+        account: u32,
+        chain: AddressChain,
+        count: u32,
+    ) -> Self {
         Self::Btc {
+            network,
             address_type,
+            account,
+            chain,
             count,
         }
     }
 
-    pub fn eth(count: u32) -> Self {
-        Self::Eth { count }
+    pub fn eth(account: u32, chain: AddressChain, count: u32) -> Self {
+        Self::Eth {
+            account,
+            chain,
+            count,
+        }
     }
 
     pub fn bucky(count: u32) -> Self {
@@ -158,20 +203,37 @@ pub fn derive_did_from_mnemonic(
 
     for wallet in &plan.wallets {
         match &wallet.kind {
-            WalletKind::Btc { address_type } => {
+            WalletKind::Btc {
+                network,
+                address_type,
+                account,
+                chain,
+            } => {
                 for index in &wallet.indices {
-                    let derived = derive_btc_address(&ctx, *address_type, *index)?.to_string();
+                    let derived = derive_btc_address(
+                        &ctx,
+                        *network,
+                        *address_type,
+                        *account,
+                        *chain,
+                        *index,
+                    )?
+                    .to_string();
                     result.btc.push(BtcAddress {
                         address_type: *address_type,
+                        account: *account,
+                        chain: *chain,
                         index: *index,
                         address: derived,
                     });
                 }
             }
-            WalletKind::Eth => {
+            WalletKind::Eth { account, chain } => {
                 for index in &wallet.indices {
-                    let derived = derive_eth_address(&ctx, *index)?;
+                    let derived = derive_eth_address(&ctx, *account, *chain, *index)?;
                     result.eth.push(ChainAddress {
+                        account: *account,
+                        chain: *chain,
                         index: *index,
                         address: derived,
                     });
@@ -200,11 +262,9 @@ pub fn derive_did_from_mnemonic(
         }
     }
 
-    result
-        .btc
-        .sort_by(|a, b| (a.address_type as u8, a.index).cmp(&(b.address_type as u8, b.index)));
-    result.eth.sort_by(|a, b| a.index.cmp(&b.index));
-    result.bucky.sort_by(|a, b| a.index.cmp(&b.index));
+    result.btc.sort_by_key(BtcAddress::sort_key);
+    result.eth.sort_by_key(ChainAddress::sort_key);
+    result.bucky.sort_by_key(|wallet| wallet.index);
 
     Ok(result)
 }
@@ -216,11 +276,16 @@ impl DerivedDid {
         for entry in self.btc {
             let index = entry.index;
             wallets
-                .btc_series_mut(entry.address_type)
+                .btc_series_mut(entry.address_type, entry.account, entry.chain)
                 .push_with_index(index, entry);
         }
 
-        wallets.eth.extend_from(self.eth, |entry| entry.index);
+        for entry in self.eth {
+            let index = entry.index;
+            wallets
+                .eth_series_mut(entry.account, entry.chain)
+                .push_with_index(index, entry);
+        }
         wallets.bucky.extend_from(self.bucky, |entry| entry.index);
 
         wallets
@@ -0,0 +1,411 @@
+//! Proof of address ownership. P2PKH addresses use the legacy `signmessage`
+//! format; segwit and taproot addresses use BIP322, as a simple signature
+//! (the witness stack) for native addresses and a full signature (the whole
+//! `to_sign` transaction) for nested segwit, whose scriptSig a witness alone
+//! cannot carry.
+
+use bitcoin::absolute::LockTime;
+use bitcoin::address::AddressType;
+use bitcoin::base64::prelude::{Engine as _, BASE64_STANDARD};
+use bitcoin::consensus::{deserialize, serialize};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::key::{Keypair, TapTweak, XOnlyPublicKey};
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::opcodes::OP_0;
+use bitcoin::script::{Builder, Instruction, PushBytesBuf};
+use bitcoin::secp256k1::{All, Message, Secp256k1, SecretKey};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::sign_message::{signed_msg_hash, MessageSignature};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    ecdsa, taproot, Address, Amount, OutPoint, PublicKey, ScriptBuf, Sequence, Transaction, TxIn,
+    TxOut, Witness,
+};
+
+use super::derive::{btc_account_path, SeedCtx};
+use super::domain::{BtcAddress, BtcAddressType, BtcNetwork};
+use crate::error::{CommandErrors, CommandResult};
+
+const BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+
+/// Signs `message` with the key of the stored `address` and returns the
+/// base64 signature.
+pub fn sign_message(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    address: &BtcAddress,
+    message: &str,
+) -> CommandResult<String> {
+    let account_path = btc_account_path(network, address.address_type, address.account)?;
+    let path = format!("{account_path}/{}/{}", address.chain.index(), address.index)
+        .parse()
+        .map_err(|e| {
+            CommandErrors::key_derivation_failed(format!("invalid derivation index: {e}"))
+        })?;
+    let secret = ctx.derive_priv(&path)?.private_key;
+    sign_with_key(ctx.secp(), &secret, address.address_type, message)
+}
+
+fn sign_with_key(
+    secp: &Secp256k1<All>,
+    secret: &SecretKey,
+    address_type: BtcAddressType,
+    message: &str,
+) -> CommandResult<String> {
+    let public_key = PublicKey::new(secret.public_key(secp));
+    let wpkh = || {
+        let hash = public_key
+            .wpubkey_hash()
+            .ok_or_else(|| CommandErrors::crypto_failed("uncompressed public key"))?;
+        Ok::<_, CommandErrors>(ScriptBuf::new_p2wpkh(&hash))
+    };
+
+    let signature = match address_type {
+        BtcAddressType::Legacy => {
+            let digest = Message::from_digest(signed_msg_hash(message).to_byte_array());
+            let signature = secp.sign_ecdsa_recoverable(&digest, secret);
+            return Ok(MessageSignature::new(signature, true).to_base64());
+        }
+        BtcAddressType::NativeSegwit => {
+            let script_pubkey = wpkh()?;
+            let witness = p2wpkh_witness(secp, secret, &script_pubkey, &script_pubkey, message)?;
+            serialize(&witness)
+        }
+        BtcAddressType::NestedSegwit => {
+            let redeem_script = wpkh()?;
+            let script_pubkey = ScriptBuf::new_p2sh(&redeem_script.script_hash());
+            let witness = p2wpkh_witness(secp, secret, &script_pubkey, &redeem_script, message)?;
+            let redeem_push = PushBytesBuf::try_from(redeem_script.to_bytes())
+                .map_err(|e| CommandErrors::crypto_failed(e.to_string()))?;
+            let script_sig = Builder::new().push_slice(redeem_push).into_script();
+            serialize(&to_sign(
+                &to_spend(&script_pubkey, message),
+                script_sig,
+                witness,
+            ))
+        }
+        BtcAddressType::Taproot => {
+            let keypair = Keypair::from_secret_key(secp, secret);
+            let script_pubkey = ScriptBuf::new_p2tr(secp, keypair.x_only_public_key().0, None);
+            let tweaked = keypair.tap_tweak(secp, None).to_inner();
+            let to_spend = to_spend(&script_pubkey, message);
+            let unsigned = to_sign(&to_spend, ScriptBuf::new(), Witness::new());
+            let sighash = SighashCache::new(&unsigned)
+                .taproot_key_spend_signature_hash(
+                    0,
+                    &Prevouts::All(&to_spend.output),
+                    TapSighashType::Default,
+                )
+                .map_err(|e| CommandErrors::crypto_failed(e.to_string()))?;
+            let sig = secp.sign_schnorr(&Message::from_digest(sighash.to_byte_array()), &tweaked);
+            let signature = taproot::Signature {
+                sig,
+                hash_ty: TapSighashType::Default,
+            };
+            serialize(&Witness::from_slice(&[signature.to_vec()]))
+        }
+    };
+    Ok(BASE64_STANDARD.encode(signature))
+}
+
+/// Witness spending the P2WPKH `program` (the redeem script when nested) of
+/// `script_pubkey` in the BIP322 `to_sign` transaction.
+fn p2wpkh_witness(
+    secp: &Secp256k1<All>,
+    secret: &SecretKey,
+    script_pubkey: &ScriptBuf,
+    program: &ScriptBuf,
+    message: &str,
+) -> CommandResult<Witness> {
+    let unsigned = to_sign(
+        &to_spend(script_pubkey, message),
+        ScriptBuf::new(),
+        Witness::new(),
+    );
+    let sighash = SighashCache::new(&unsigned)
+        .p2wpkh_signature_hash(0, program, Amount::ZERO, EcdsaSighashType::All)
+        .map_err(|e| CommandErrors::crypto_failed(e.to_string()))?;
+    // 与 Bitcoin Core 一样取低 R 值，签名与 BIP322 测试向量逐字节一致
+    let sig = secp.sign_ecdsa_low_r(&Message::from_digest(sighash.to_byte_array()), secret);
+    Ok(Witness::p2wpkh(
+        &ecdsa::Signature::sighash_all(sig),
+        &secret.public_key(secp),
+    ))
+}
+
+/// Checks a base64 `signature` of `message` by `address`. Signatures that do
+/// not decode count as invalid; address types without a supported scheme are
+/// an error.
+pub fn verify_message(address: &Address, message: &str, signature: &str) -> CommandResult<bool> {
+    let Ok(bytes) = BASE64_STANDARD.decode(signature.trim()) else {
+        return Ok(false);
+    };
+    let secp = Secp256k1::verification_only();
+    let script_pubkey = address.script_pubkey();
+
+    match address.address_type() {
+        Some(AddressType::P2pkh) => Ok(MessageSignature::from_slice(&bytes)
+            .and_then(|sig| sig.is_signed_by_address(&secp, address, signed_msg_hash(message)))
+            .unwrap_or(false)),
+        Some(AddressType::P2wpkh) | Some(AddressType::P2tr) => {
+            let Ok(witness) = deserialize::<Witness>(&bytes) else {
+                return Ok(false);
+            };
+            Ok(verify_bip322(
+                &secp,
+                &script_pubkey,
+                ScriptBuf::new(),
+                witness,
+                message,
+            ))
+        }
+        Some(AddressType::P2sh) => {
+            let Ok(tx) = deserialize::<Transaction>(&bytes) else {
+                return Ok(false);
+            };
+            let [input] = &tx.input[..] else {
+                return Ok(false);
+            };
+            // 完整签名必须正是按 BIP322 构造的 to_sign 交易
+            let expected = to_sign(
+                &to_spend(&script_pubkey, message),
+                input.script_sig.clone(),
+                input.witness.clone(),
+            );
+            if tx != expected {
+                return Ok(false);
+            }
+            Ok(verify_bip322(
+                &secp,
+                &script_pubkey,
+                input.script_sig.clone(),
+                input.witness.clone(),
+                message,
+            ))
+        }
+        _ => Err(CommandErrors::invalid_address(format!(
+            "message signing is not supported for {address}"
+        ))),
+    }
+}
+
+fn verify_bip322(
+    secp: &Secp256k1<bitcoin::secp256k1::VerifyOnly>,
+    script_pubkey: &ScriptBuf,
+    script_sig: ScriptBuf,
+    witness: Witness,
+    message: &str,
+) -> bool {
+    let to_spend = to_spend(script_pubkey, message);
+    let to_sign = to_sign(&to_spend, script_sig.clone(), witness.clone());
+    let mut cache = SighashCache::new(&to_sign);
+
+    if script_pubkey.is_p2tr() {
+        let (Some(sig), 1) = (witness.nth(0), witness.len()) else {
+            return false;
+        };
+        let (Ok(sig), Ok(output_key)) = (
+            taproot::Signature::from_slice(sig),
+            XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..]),
+        ) else {
+            return false;
+        };
+        return cache
+            .taproot_key_spend_signature_hash(0, &Prevouts::All(&to_spend.output), sig.hash_ty)
+            .map(|sighash| Message::from_digest(sighash.to_byte_array()))
+            .is_ok_and(|digest| secp.verify_schnorr(&sig.sig, &digest, &output_key).is_ok());
+    }
+
+    let program = if script_pubkey.is_p2wpkh() {
+        if !script_sig.is_empty() {
+            return false;
+        }
+        script_pubkey.clone()
+    } else {
+        // P2SH 只接受单个压入 P2WPKH 赎回脚本的 scriptSig
+        let mut instructions = script_sig.instructions();
+        let (Some(Ok(Instruction::PushBytes(redeem))), None) =
+            (instructions.next(), instructions.next())
+        else {
+            return false;
+        };
+        let redeem_script = ScriptBuf::from(redeem.as_bytes().to_vec());
+        if !redeem_script.is_p2wpkh()
+            || ScriptBuf::new_p2sh(&redeem_script.script_hash()) != *script_pubkey
+        {
+            return false;
+        }
+        redeem_script
+    };
+
+    let (Some(sig), Some(key), 2) = (witness.nth(0), witness.nth(1), witness.len()) else {
+        return false;
+    };
+    let (Ok(sig), Ok(key)) = (
+        ecdsa::Signature::from_slice(sig),
+        PublicKey::from_slice(key),
+    ) else {
+        return false;
+    };
+    if key
+        .wpubkey_hash()
+        .map(|hash| ScriptBuf::new_p2wpkh(&hash))
+        .as_ref()
+        != Some(&program)
+    {
+        return false;
+    }
+    cache
+        .p2wpkh_signature_hash(0, &program, Amount::ZERO, sig.hash_ty)
+        .map(|sighash| Message::from_digest(sighash.to_byte_array()))
+        .is_ok_and(|digest| secp.verify_ecdsa(&digest, &sig.sig, &key.inner).is_ok())
+}
+
+/// BIP322 tagged hash of the message.
+fn message_hash(message: &str) -> sha256::Hash {
+    let tag = sha256::Hash::hash(BIP322_TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_byte_array());
+    engine.input(tag.as_byte_array());
+    engine.input(message.as_bytes());
+    sha256::Hash::from_engine(engine)
+}
+
+/// Virtual transaction committing to the message, whose only output the
+/// signature spends.
+fn to_spend(script_pubkey: &ScriptBuf, message: &str) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: Builder::new()
+                .push_opcode(OP_0)
+                .push_slice(message_hash(message).to_byte_array())
+                .into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: script_pubkey.clone(),
+        }],
+    }
+}
+
+fn to_sign(to_spend: &Transaction, script_sig: ScriptBuf, witness: Witness) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.txid(), 0),
+            script_sig,
+            sequence: Sequence::ZERO,
+            witness,
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::PrivateKey;
+    use std::str::FromStr;
+
+    // BIP322 测试向量
+    const BIP322_KEY: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const BIP322_P2WPKH: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const BIP322_P2TR: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+
+    fn address(encoded: &str) -> Address {
+        Address::from_str(encoded).unwrap().assume_checked()
+    }
+
+    #[test]
+    fn test_bip322_message_hash_and_to_spend() {
+        assert_eq!(
+            message_hash("").to_string(),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            message_hash("Hello World").to_string(),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+        let script_pubkey = address(BIP322_P2WPKH).script_pubkey();
+        assert_eq!(
+            to_spend(&script_pubkey, "").txid().to_string(),
+            "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7"
+        );
+        assert_eq!(
+            to_spend(&script_pubkey, "Hello World").txid().to_string(),
+            "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b"
+        );
+    }
+
+    #[test]
+    fn test_bip322_p2wpkh_vectors() {
+        let secp = Secp256k1::new();
+        let key = PrivateKey::from_wif(BIP322_KEY).unwrap().inner;
+        let vectors = [
+            ("", "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI="),
+            ("Hello World", "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI="),
+        ];
+        for (message, expected) in vectors {
+            let signature =
+                sign_with_key(&secp, &key, BtcAddressType::NativeSegwit, message).unwrap();
+            assert_eq!(signature, expected);
+            assert!(verify_message(&address(BIP322_P2WPKH), message, expected).unwrap());
+        }
+        assert!(!verify_message(&address(BIP322_P2WPKH), "Hello World", vectors[0].1).unwrap());
+    }
+
+    #[test]
+    fn test_bip322_p2tr_vector() {
+        let signature = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+        assert!(verify_message(&address(BIP322_P2TR), "Hello World", signature).unwrap());
+        assert!(!verify_message(&address(BIP322_P2TR), "", signature).unwrap());
+
+        let secp = Secp256k1::new();
+        let key = PrivateKey::from_wif(BIP322_KEY).unwrap().inner;
+        let signed = sign_with_key(&secp, &key, BtcAddressType::Taproot, "Hello World").unwrap();
+        assert!(verify_message(&address(BIP322_P2TR), "Hello World", &signed).unwrap());
+    }
+
+    #[test]
+    fn test_legacy_signmessage_vector() {
+        // Bitcoin Core rpc_signmessage.py
+        let address = address("mpLQjfK79b7CCV4VMJWEWAj5Mpx8Up5zxB");
+        let message = "This is just a test message";
+        let expected = "INbVnW4e6PeRmsv2Qgu8NuopvrVjkcxob+sX8OcZG0SALhWybUjzMLPdAsXI46YZGb0KQTRii+wWIQzRpG/U+S0=";
+        assert!(verify_message(&address, message, expected).unwrap());
+
+        let secp = Secp256k1::new();
+        let key = PrivateKey::from_wif("cUeKHd5orzT3mz8P9pxyREHfsWtVfgsfDjiZZBcjUBAaGk1BTj7N")
+            .unwrap()
+            .inner;
+        let signature = sign_with_key(&secp, &key, BtcAddressType::Legacy, message).unwrap();
+        assert_eq!(signature, expected);
+    }
+
+    #[test]
+    fn test_nested_segwit_round_trip() {
+        let secp = Secp256k1::new();
+        let key = PrivateKey::from_wif(BIP322_KEY).unwrap();
+        let public_key = key.public_key(&secp);
+        let nested = Address::p2shwpkh(&public_key, bitcoin::Network::Bitcoin).unwrap();
+        let signed = sign_with_key(
+            &secp,
+            &key.inner,
+            BtcAddressType::NestedSegwit,
+            "Hello World",
+        )
+        .unwrap();
+        assert!(verify_message(&nested, "Hello World", &signed).unwrap());
+        assert!(!verify_message(&nested, "Hello", &signed).unwrap());
+        assert!(!verify_message(&nested, "Hello World", "not base64!").unwrap());
+    }
+}
@@ -0,0 +1,157 @@
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroizing;
+
+use super::crypto::{decrypt_with_key, encrypt_with_key, seal, unseal};
+use super::store::{EncryptedSeed, KdfParams, StoredDid};
+use crate::error::{CommandErrors, CommandResult};
+
+const VAULT_KEY_AAD: &[u8] = b"buckyos-vault-key";
+const METADATA_AAD: &[u8] = b"buckyos-vault-metadata";
+
+/// Random key that seals the DID records of a vault with metadata encryption
+/// enabled. It is stored wrapped under the vault password.
+#[derive(Clone)]
+pub struct VaultKey(Zeroizing<[u8; 32]>);
+
+impl fmt::Debug for VaultKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VaultKey(..)")
+    }
+}
+
+impl VaultKey {
+    pub fn generate() -> Self {
+        let mut key = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(&mut key[..]);
+        Self(key)
+    }
+
+    pub fn wrap(&self, password: &str) -> CommandResult<EncryptedSeed> {
+        seal(
+            password,
+            &self.0[..],
+            VAULT_KEY_AAD,
+            KdfParams::recommended(),
+        )
+    }
+
+    /// Recovers the key from `wrapped`; `InvalidPassword` when `password` is wrong.
+    pub fn unwrap(password: &str, wrapped: &EncryptedSeed) -> CommandResult<Self> {
+        let plaintext = unseal(password, wrapped, VAULT_KEY_AAD)?;
+        let mut key = Zeroizing::new([0u8; 32]);
+        if plaintext.len() != key.len() {
+            return Err(CommandErrors::vault_corrupted("invalid vault key length"));
+        }
+        key.copy_from_slice(&plaintext);
+        Ok(Self(key))
+    }
+}
+
+/// What a locked vault still shows for each DID.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DidIndexEntry {
+    pub id: String,
+}
+
+/// Sealed form of `VaultStore::dids`: the wrapped [`VaultKey`], the plaintext
+/// index and the AES-256-GCM encrypted records (seeds included).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SealedMetadata {
+    pub key: EncryptedSeed,
+    #[serde(default)]
+    pub index: Vec<DidIndexEntry>,
+    pub cipher_nonce_hex: String,
+    pub cipher_hex: String,
+}
+
+impl SealedMetadata {
+    pub fn seal(
+        key: &VaultKey,
+        wrapped_key: EncryptedSeed,
+        dids: &[StoredDid],
+    ) -> CommandResult<Self> {
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(dids).map_err(|e| CommandErrors::internal(e.to_string()))?,
+        );
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = encrypt_with_key(&key.0, &nonce, &plaintext, METADATA_AAD)?;
+
+        Ok(Self {
+            key: wrapped_key,
+            index: dids
+                .iter()
+                .map(|did| DidIndexEntry { id: did.id.clone() })
+                .collect(),
+            cipher_nonce_hex: hex::encode(nonce),
+            cipher_hex: hex::encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, key: &VaultKey) -> CommandResult<Vec<StoredDid>> {
+        let nonce = hex::decode(&self.cipher_nonce_hex)
+            .map_err(|e| CommandErrors::vault_corrupted(format!("invalid nonce: {e}")))?;
+        let ciphertext = hex::decode(&self.cipher_hex)
+            .map_err(|e| CommandErrors::vault_corrupted(format!("invalid cipher: {e}")))?;
+        // 密钥来自同一 vault 的 key 字段，解不开说明数据被篡改或来自另一把密钥
+        let plaintext = decrypt_with_key(&key.0, &nonce, &ciphertext, METADATA_AAD)
+            .ok_or_else(|| CommandErrors::vault_corrupted("sealed metadata cannot be opened"))?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| CommandErrors::vault_corrupted(format!("invalid sealed metadata: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::domain::{BtcNetwork, MnemonicLanguage, SnStatusInfo, WalletCollection};
+
+    fn sample_did() -> StoredDid {
+        StoredDid {
+            id: "did:bk:1:01J0000000000000000000000".to_string(),
+            nickname: "alice".to_string(),
+            seed: Some(EncryptedSeed {
+                kdf: KdfParams::recommended(),
+                kdf_salt_hex: "00".to_string(),
+                cipher_nonce_hex: "00".to_string(),
+                cipher_hex: "00".to_string(),
+            }),
+            mnemonic_language: MnemonicLanguage::English,
+            btc_network: BtcNetwork::Mainnet,
+            wallets: WalletCollection::default(),
+            sn_status: Some(SnStatusInfo {
+                username: Some("alice".to_string()),
+                zone_config: None,
+            }),
+            password_attempts: Default::default(),
+            wipe_after_failures: None,
+            backup_status: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_sealed_metadata_hides_everything_but_ids() {
+        let key = VaultKey::generate();
+        let sealed =
+            SealedMetadata::seal(&key, key.wrap("vault_pw").unwrap(), &[sample_did()]).unwrap();
+
+        let stored = serde_json::to_string(&sealed).unwrap();
+        assert!(stored.contains("did:bk:1:01J0000000000000000000000"));
+        assert!(!stored.contains("alice"));
+
+        let unwrapped = VaultKey::unwrap("vault_pw", &sealed.key).unwrap();
+        let dids = sealed.open(&unwrapped).unwrap();
+        assert_eq!(dids[0].nickname, "alice");
+
+        assert!(matches!(
+            VaultKey::unwrap("wrong", &sealed.key),
+            Err(CommandErrors::InvalidPassword)
+        ));
+        assert!(matches!(
+            sealed.open(&VaultKey::generate()),
+            Err(CommandErrors::VaultCorrupted(_))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+use serde_json::{json, Value};
+
+use crate::error::{CommandErrors, CommandResult};
+
+type Migration = fn(&mut Value) -> CommandResult<()>;
+
+/// `MIGRATIONS[n]` upgrades a vault from version `n + 1` to `n + 2`. Append
+/// new steps at the end; never reorder or edit a released one.
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2, migrate_v2_to_v3];
+
+pub const VAULT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+pub fn vault_version(raw: &Value) -> CommandResult<u32> {
+    match raw.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| CommandErrors::vault_corrupted("invalid vault version")),
+    }
+}
+
+/// Runs every migration between the version recorded in `raw` and
+/// [`VAULT_VERSION`]. Returns the version `raw` was written with.
+pub fn migrate(raw: &mut Value) -> CommandResult<u32> {
+    let from = vault_version(raw)?;
+    if from > VAULT_VERSION {
+        return Err(CommandErrors::VaultVersionUnsupported(from));
+    }
+    if from == 0 {
+        return Err(CommandErrors::vault_corrupted("invalid vault version"));
+    }
+
+    for (step, migration) in MIGRATIONS.iter().enumerate().skip(from as usize - 1) {
+        migration(raw)?;
+        let to = step as u32 + 2;
+        raw.as_object_mut()
+            .ok_or_else(|| CommandErrors::vault_corrupted("vault is not an object"))?
+            .insert("version".to_string(), json!(to));
+        log::info!("vault migrated to v{to}");
+    }
+    Ok(from)
+}
+
+/// Whether migrating `raw` changes anything besides its version. Steps that
+/// only bump the version leave nothing worth keeping a backup of.
+pub fn rewrites_records(raw: &Value) -> CommandResult<bool> {
+    let from = vault_version(raw)?;
+    let mut migrated = raw.clone();
+    for migration in MIGRATIONS.iter().skip(from.saturating_sub(1) as usize) {
+        migration(&mut migrated)?;
+    }
+    Ok(migrated != *raw)
+}
+
+fn dids_mut(raw: &mut Value) -> CommandResult<Vec<&mut Value>> {
+    match raw.get_mut("dids") {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(dids)) => Ok(dids.iter_mut().collect()),
+        Some(_) => Err(CommandErrors::vault_corrupted("dids is not an array")),
+    }
+}
+
+/// v1 seeds only recorded `kdf_iter` (PBKDF2-HMAC-SHA256); v2 describes the
+/// KDF explicitly in `seed.kdf`.
+fn migrate_v1_to_v2(raw: &mut Value) -> CommandResult<()> {
+    for did in dids_mut(raw)? {
+        let seed = did
+            .get_mut("seed")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| CommandErrors::vault_corrupted("missing seed"))?;
+        if seed.contains_key("kdf") {
+            continue;
+        }
+        let iterations = seed
+            .remove("kdf_iter")
+            .and_then(|iter| iter.as_u64())
+            .ok_or_else(|| CommandErrors::vault_corrupted("missing kdf_iter"))?;
+        seed.insert(
+            "kdf".to_string(),
+            json!({ "algorithm": "pbkdf2_sha256", "iterations": iterations }),
+        );
+    }
+    Ok(())
+}
+
+/// v3 may seal `dids` under a vault key (`sealed`). Existing vaults need no
+/// change; the bump only stops older apps from rewriting a sealed vault
+/// without its records.
+fn migrate_v2_to_v3(_raw: &mut Value) -> CommandResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::domain::{AddressChain, BtcAddressType};
+    use crate::did::store::{KdfParams, VaultStore};
+
+    fn v1_vault() -> Value {
+        json!({
+            "version": 1,
+            "active_did": "did:bk:1:01J0000000000000000000000",
+            "dids": [{
+                "id": "did:bk:1:01J0000000000000000000000",
+                "nickname": "alice",
+                "seed": {
+                    "kdf_iter": 100000,
+                    "kdf_salt_hex": "00",
+                    "cipher_nonce_hex": "00",
+                    "cipher_hex": "00"
+                }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_migrate_v1_vault() {
+        let mut raw = v1_vault();
+        assert!(rewrites_records(&raw).unwrap());
+        assert_eq!(migrate(&mut raw).unwrap(), 1);
+        assert_eq!(vault_version(&raw).unwrap(), VAULT_VERSION);
+        assert!(raw["dids"][0]["seed"].get("kdf_iter").is_none());
+
+        let vault: VaultStore = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(
+            vault.dids[0].seed.as_ref().unwrap().kdf,
+            KdfParams::Pbkdf2Sha256 {
+                iterations: 100_000
+            }
+        );
+
+        // 已是最新版本时不做任何修改。
+        let before = raw.clone();
+        assert_eq!(migrate(&mut raw).unwrap(), VAULT_VERSION);
+        assert_eq!(raw, before);
+
+        // v2→v3 只改版本号
+        raw["version"] = json!(2);
+        assert!(!rewrites_records(&raw).unwrap());
+    }
+
+    #[test]
+    fn test_single_series_wallets_are_account_zero_receive() {
+        let mut raw = v1_vault();
+        raw["dids"][0]["wallets"] = json!({
+            "btc": {
+                "native_segwit": {
+                    "entries": [{ "index": 0, "address": "bc1q" }],
+                    "next_index": 1
+                }
+            },
+            "eth": { "entries": [], "next_index": 3 }
+        });
+        migrate(&mut raw).unwrap();
+
+        let vault: VaultStore = serde_json::from_value(raw).unwrap();
+        let wallets = &vault.dids[0].wallets;
+        let series = wallets
+            .btc_series(BtcAddressType::NativeSegwit, 0, AddressChain::Receive)
+            .unwrap();
+        assert_eq!(series.entries[0].chain, AddressChain::Receive);
+        assert!(wallets
+            .btc_series(BtcAddressType::NativeSegwit, 0, AddressChain::Change)
+            .is_none());
+        assert_eq!(
+            wallets
+                .eth_series(0, AddressChain::Receive)
+                .unwrap()
+                .next_index(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_newer_vault_is_rejected() {
+        let mut raw = json!({ "version": VAULT_VERSION + 1, "dids": [] });
+        assert!(matches!(
+            migrate(&mut raw),
+            Err(CommandErrors::VaultVersionUnsupported(v)) if v == VAULT_VERSION + 1
+        ));
+    }
+}
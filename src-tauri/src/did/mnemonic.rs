@@ -0,0 +1,199 @@
+//! BIP39 wordlist helpers (language detection, word matching) used by the
+//! DID commands.
+
+use bip39::{Error, Language, Mnemonic};
+
+/// Parses `phrase` in whichever wordlist it belongs to. A phrase that fits
+/// several lists (e.g. simplified and traditional Chinese share characters)
+/// is read in the first one: the words, and therefore the seed, are the same.
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, Error> {
+    match Mnemonic::parse(phrase) {
+        Err(Error::AmbiguousLanguages(candidates)) => {
+            let language = candidates.iter().next().unwrap_or_default();
+            Mnemonic::parse_in(language, phrase)
+        }
+        other => other,
+    }
+}
+
+/// Entropy encoded by `mnemonic`. Unlike [`Mnemonic::to_entropy`] this does
+/// not panic for phrases that fit several wordlists, which is common for
+/// Chinese ones.
+pub fn mnemonic_entropy(mnemonic: &Mnemonic) -> Vec<u8> {
+    let mut entropy = vec![0u8; mnemonic.word_count() / 3 * 4];
+    let entropy_bits = entropy.len() * 8;
+    for (i, index) in mnemonic.word_indices().enumerate() {
+        for bit in 0..11 {
+            let pos = i * 11 + bit;
+            if pos < entropy_bits && (index >> (10 - bit)) & 1 == 1 {
+                entropy[pos / 8] |= 0x80 >> (pos % 8);
+            }
+        }
+    }
+    entropy
+}
+
+/// Picks the wordlist containing most of `words`, so a phrase with a few
+/// typos is still checked against its own list. Ties go to the earlier
+/// language in [`Language::ALL`], English first.
+pub fn detect_language<S: AsRef<str>>(words: &[S]) -> Language {
+    let mut best = (Language::English, 0);
+    for &language in Language::ALL {
+        let hits = words
+            .iter()
+            .filter(|word| language.find_word(word.as_ref().trim()).is_some())
+            .count();
+        if hits > best.1 {
+            best = (language, hits);
+        }
+    }
+    best.0
+}
+
+/// Levenshtein distance between `a` and `b`, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Words of `language` at most `max_distance` edits away from `word`,
+/// closest first, then in wordlist order.
+pub fn closest_words(
+    language: Language,
+    word: &str,
+    max_distance: usize,
+) -> Vec<(&'static str, usize)> {
+    let mut matches: Vec<(&'static str, usize)> = language
+        .word_list()
+        .iter()
+        .map(|candidate| (*candidate, edit_distance(word, candidate)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .collect();
+    matches.sort_by_key(|(_, distance)| *distance);
+    matches
+}
+
+/// Characters that identify an English BIP39 word on their own.
+const UNIQUE_PREFIX_CHARS: usize = 4;
+
+/// Words of `language` that `input` may stand for while it is being typed,
+/// at most `limit` of them: words starting with `input`, the word sharing
+/// its first four characters, then words close to it by edit distance.
+pub fn complete_word(language: Language, input: &str, limit: usize) -> Vec<&'static str> {
+    let input = input.trim().to_lowercase();
+    let length = input.chars().count();
+    if length == 0 {
+        return Vec::new();
+    }
+
+    let prefix: String = input.chars().take(UNIQUE_PREFIX_CHARS).collect();
+    let mut matches: Vec<&'static str> = language
+        .word_list()
+        .iter()
+        .filter(|word| word.starts_with(input.as_str()))
+        .copied()
+        .collect();
+    if length > UNIQUE_PREFIX_CHARS {
+        matches.extend(
+            language
+                .word_list()
+                .iter()
+                .filter(|word| word.starts_with(prefix.as_str())),
+        );
+    }
+    // 太短的输入做模糊匹配只会得到大量无关的词
+    if length >= 3 {
+        let max_distance = if length <= UNIQUE_PREFIX_CHARS { 1 } else { 2 };
+        matches.extend(
+            closest_words(language, &input, max_distance)
+                .into_iter()
+                .map(|(word, _)| word),
+        );
+    }
+
+    let mut completions = Vec::new();
+    for word in matches {
+        if completions.len() == limit {
+            break;
+        }
+        if !completions.contains(&word) {
+            completions.push(word);
+        }
+    }
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHINESE: &str = "的 的 的 的 的 的 的 的 的 的 的 在";
+
+    #[test]
+    fn test_detects_language_of_phrase() {
+        let english = parse_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        assert_eq!(english.language(), Language::English);
+
+        // 简繁词表在这些字上重合，按先出现的简体解析
+        let chinese = parse_mnemonic(CHINESE).unwrap();
+        assert_eq!(chinese.language(), Language::SimplifiedChinese);
+        assert_eq!(mnemonic_entropy(&chinese), vec![0u8; 16]);
+
+        let entropy: Vec<u8> = (0..32).collect();
+        let spanish = Mnemonic::from_entropy_in(Language::Spanish, &entropy).unwrap();
+        let parsed = parse_mnemonic(&spanish.to_string()).unwrap();
+        assert_eq!(parsed.language(), Language::Spanish);
+        assert_eq!(mnemonic_entropy(&parsed), entropy);
+    }
+
+    #[test]
+    fn test_majority_language_wins_despite_typos() {
+        let mut words: Vec<&str> = CHINESE.split(' ').collect();
+        words[3] = "abandon";
+        words[5] = "xyz";
+        assert_eq!(detect_language(&words), Language::SimplifiedChinese);
+        assert_eq!(detect_language::<&str>(&[]), Language::English);
+    }
+
+    #[test]
+    fn test_closest_words() {
+        assert_eq!(edit_distance("abandon", "abandon"), 0);
+        assert_eq!(edit_distance("abandn", "abandon"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "的"), 1);
+
+        let matches = closest_words(Language::English, "abandn", 1);
+        assert_eq!(matches, vec![("abandon", 1)]);
+        let matches = closest_words(Language::English, "wave", 1);
+        assert_eq!(matches[0], ("wave", 0));
+        assert!(matches.iter().any(|(word, _)| *word == "save"));
+    }
+
+    #[test]
+    fn test_complete_word() {
+        assert_eq!(complete_word(Language::English, "aban", 8)[0], "abandon");
+        // 前四个字母已唯一确定单词，多打或打错的后续字母不影响
+        assert_eq!(complete_word(Language::English, "abanx", 8)[0], "abandon");
+        assert_eq!(complete_word(Language::English, "sausge", 8)[0], "sausage");
+        assert_eq!(
+            complete_word(Language::English, "zo", 8),
+            vec!["zone", "zoo"]
+        );
+        assert_eq!(complete_word(Language::English, "a", 3).len(), 3);
+        assert!(complete_word(Language::English, " ", 8).is_empty());
+        assert_eq!(complete_word(Language::SimplifiedChinese, "的", 8)[0], "的");
+    }
+}
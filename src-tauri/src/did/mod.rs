@@ -1,9 +1,29 @@
+pub mod attempts;
+pub mod backend;
+pub mod backup;
 pub mod crypto;
 pub mod derive;
+pub mod descriptor;
+pub mod discovery;
 pub mod domain;
+pub mod message;
+pub mod metadata;
+pub mod migration;
+pub mod mnemonic;
+pub mod password_policy;
+pub mod psbt;
+pub mod repair;
+pub mod session;
+pub mod slip39;
+pub mod snapshot;
 pub mod store;
+pub mod verification;
 
 mod commands;
+mod common_passwords;
 mod identity;
+mod slip39_words;
 
 pub use commands::*;
+pub use session::{spawn_session_watcher, SessionManager};
+pub use store::VaultService;
@@ -0,0 +1,173 @@
+//! Password rules for DID seeds.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use thiserror::Error;
+
+use super::common_passwords::COMMON_PASSWORDS;
+
+/// Rules a new seed password must meet. Read from the `password_policy`
+/// section of the app config; missing fields keep their defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// Minimum number of characters (not bytes).
+    pub min_length: usize,
+    /// Minimum estimated entropy in bits, see [`estimate_entropy_bits`].
+    pub min_entropy_bits: f64,
+    /// Rejects passwords from the bundled common-password list.
+    pub reject_common: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            min_entropy_bits: 40.0,
+            reject_common: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordRule {
+    MinLength,
+    MinEntropy,
+    NotCommon,
+}
+
+impl PasswordRule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PasswordRule::MinLength => "min_length",
+            PasswordRule::MinEntropy => "min_entropy",
+            PasswordRule::NotCommon => "not_common",
+        }
+    }
+}
+
+/// The rules a rejected password did not meet, in [`PasswordRule`] order.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("weak password, unmet rules: {}", join_rules(.0))]
+pub struct WeakPassword(pub Vec<PasswordRule>);
+
+impl WeakPassword {
+    /// Comma separated rule names, e.g. `min_length,not_common`.
+    pub fn rule_names(&self) -> String {
+        join_rules(&self.0)
+    }
+}
+
+fn join_rules(rules: &[PasswordRule]) -> String {
+    rules
+        .iter()
+        .map(|rule| rule.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl PasswordPolicy {
+    pub fn unmet_rules(&self, password: &str) -> Vec<PasswordRule> {
+        let mut unmet = Vec::new();
+        if password.chars().count() < self.min_length {
+            unmet.push(PasswordRule::MinLength);
+        }
+        if estimate_entropy_bits(password) < self.min_entropy_bits {
+            unmet.push(PasswordRule::MinEntropy);
+        }
+        if self.reject_common && is_common_password(password) {
+            unmet.push(PasswordRule::NotCommon);
+        }
+        unmet
+    }
+
+    pub fn check(&self, password: &str) -> Result<(), WeakPassword> {
+        let unmet = self.unmet_rules(password);
+        if unmet.is_empty() {
+            Ok(())
+        } else {
+            Err(WeakPassword(unmet))
+        }
+    }
+}
+
+/// Rough brute-force entropy: bits per character of the character classes
+/// used, times the length, where repeated characters only count half.
+pub fn estimate_entropy_bits(password: &str) -> f64 {
+    // 依次为小写、大写、数字、ASCII 符号、其他字符（如中文）
+    let mut classes = [false; 5];
+    let mut seen = HashSet::new();
+    let mut length = 0usize;
+    for c in password.chars() {
+        length += 1;
+        seen.insert(c);
+        let class = match c {
+            'a'..='z' => 0,
+            'A'..='Z' => 1,
+            '0'..='9' => 2,
+            c if c.is_ascii() => 3,
+            _ => 4,
+        };
+        classes[class] = true;
+    }
+
+    let pool: u32 = classes
+        .iter()
+        .zip([26, 26, 10, 33, 100])
+        .filter(|(used, _)| **used)
+        .map(|(_, size)| size)
+        .sum();
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let distinct = seen.len();
+    let effective_length = distinct as f64 + (length - distinct) as f64 / 2.0;
+    effective_length * f64::from(pool).log2()
+}
+
+/// Case-insensitive lookup in the bundled common-password list.
+pub fn is_common_password(password: &str) -> bool {
+    let lowered = password.to_lowercase();
+    COMMON_PASSWORDS.binary_search(&lowered.as_str()).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_password_list_is_sorted() {
+        assert!(COMMON_PASSWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_unmet_rules() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            policy.unmet_rules(""),
+            vec![PasswordRule::MinLength, PasswordRule::MinEntropy]
+        );
+        assert_eq!(
+            policy.unmet_rules("Password123"),
+            vec![PasswordRule::NotCommon]
+        );
+        assert_eq!(
+            policy.unmet_rules("aaaaaaaaaaaa"),
+            vec![PasswordRule::MinEntropy]
+        );
+        assert!(policy.unmet_rules("correct-horse-battery").is_empty());
+        assert!(policy.unmet_rules("钱包密码不要告诉别人").is_empty());
+
+        let err = policy.check("qwerty").unwrap_err();
+        assert_eq!(err.rule_names(), "min_length,min_entropy,not_common");
+
+        let relaxed = PasswordPolicy {
+            min_length: 0,
+            min_entropy_bits: 0.0,
+            reject_common: false,
+        };
+        assert!(relaxed.check("").is_ok());
+    }
+}
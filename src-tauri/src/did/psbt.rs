@@ -0,0 +1,551 @@
+//! BIP174 signing. Inputs are matched to the DID through their BIP32 key
+//! origins, so any BTC account path of the DID's network can be signed, not
+//! only the addresses stored in the vault.
+
+use std::str::FromStr;
+
+use bitcoin::bip32::{ChildNumber, DerivationPath};
+use bitcoin::hashes::Hash;
+use bitcoin::key::{Keypair, TapTweak};
+use bitcoin::psbt::{Output, Psbt};
+use bitcoin::secp256k1::Message;
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::{
+    ecdsa, taproot, Address, EcdsaSighashType, Network, PublicKey, ScriptBuf, TapSighashType,
+    Transaction, TxOut,
+};
+
+use super::derive::{btc_address, SeedCtx};
+use super::domain::{BtcAddressType, BtcNetwork, PsbtInputSummary, PsbtOutputSummary, SignedPsbt};
+use crate::error::{CommandErrors, CommandResult};
+
+/// Signs every input of the base64 `encoded` PSBT whose key origin is this
+/// seed. ECDSA signatures go to `partial_sigs`, taproot key-path signatures
+/// to `tap_key_sig`; finalizing is left to the wallet that built the PSBT.
+/// Only `SIGHASH_ALL` is signed, so the summary covers everything the
+/// signatures commit to.
+pub fn sign(ctx: &SeedCtx, network: BtcNetwork, encoded: &str) -> CommandResult<SignedPsbt> {
+    let mut psbt =
+        Psbt::from_str(encoded.trim()).map_err(|e| CommandErrors::invalid_psbt(e.to_string()))?;
+    check_previous_outputs(&psbt)?;
+    let unsigned_tx = psbt.unsigned_tx.clone();
+    let mut cache = SighashCache::new(&unsigned_tx);
+
+    let mut signed = Vec::with_capacity(psbt.inputs.len());
+    let mut signed_taproot = false;
+    for index in 0..psbt.inputs.len() {
+        let script_pubkey = psbt
+            .spend_utxo(index)
+            .map(|utxo| utxo.script_pubkey.clone())
+            .map_err(|e| CommandErrors::invalid_psbt(format!("input {index}: {e}")))?;
+        signed.push(if script_pubkey.is_p2tr() {
+            let done =
+                sign_taproot_input(ctx, network, &mut psbt, index, &script_pubkey, &mut cache)?;
+            signed_taproot |= done;
+            done
+        } else {
+            sign_ecdsa_input(ctx, network, &mut psbt, index, &script_pubkey, &mut cache)?
+        });
+    }
+    if !signed.contains(&true) {
+        return Err(CommandErrors::invalid_psbt(
+            "no input belongs to this wallet",
+        ));
+    }
+    // segwit v0 的签名只覆盖本输入的金额；前序交易或 taproot 签名才能担保全部输入金额
+    let fee_verified = signed_taproot
+        || psbt
+            .inputs
+            .iter()
+            .all(|input| input.non_witness_utxo.is_some());
+
+    let address_network = Network::from(network);
+    let inputs = unsigned_tx
+        .input
+        .iter()
+        .zip(&signed)
+        .enumerate()
+        .map(|(index, (txin, signed))| {
+            let utxo = psbt.spend_utxo(index).ok();
+            PsbtInputSummary {
+                previous_output: txin.previous_output.to_string(),
+                address: utxo
+                    .and_then(|utxo| display_address(&utxo.script_pubkey, address_network)),
+                amount_sats: utxo.map(|utxo| utxo.value.to_sat()),
+                signed: *signed,
+            }
+        })
+        .collect();
+    let outputs = unsigned_tx
+        .output
+        .iter()
+        .zip(&psbt.outputs)
+        .map(|(txout, output)| {
+            Ok(PsbtOutputSummary {
+                address: display_address(&txout.script_pubkey, address_network),
+                amount_sats: txout.value.to_sat(),
+                is_mine: owns_output(ctx, network, &txout.script_pubkey, output)?,
+            })
+        })
+        .collect::<CommandResult<_>>()?;
+
+    Ok(SignedPsbt {
+        fee_sats: psbt.fee().ok().map(|fee| fee.to_sat()),
+        fee_verified,
+        psbt: psbt.to_string(),
+        inputs,
+        outputs,
+    })
+}
+
+fn sign_ecdsa_input(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    psbt: &mut Psbt,
+    index: usize,
+    script_pubkey: &ScriptBuf,
+    cache: &mut SighashCache<&Transaction>,
+) -> CommandResult<bool> {
+    let fingerprint = ctx.master_fingerprint();
+    let mut keys = Vec::new();
+    for (pk, (origin, path)) in &psbt.inputs[index].bip32_derivation {
+        if *origin != fingerprint {
+            continue;
+        }
+        let Some(address_type) = signing_address_type(path, network) else {
+            continue;
+        };
+        let secret = ctx.derive_priv(path)?.private_key;
+        // 指纹可能碰撞，派生出的公钥必须与 PSBT 中声明的一致
+        if secret.public_key(ctx.secp()) != *pk {
+            continue;
+        }
+        // 派生出的地址必须正是被花费的输出，否则金额与脚本都不可信
+        if btc_address(ctx.secp(), network, address_type, *pk)?.script_pubkey() != *script_pubkey {
+            continue;
+        }
+        keys.push((*pk, secret));
+    }
+    if keys.is_empty() {
+        return Ok(false);
+    }
+
+    let hash_ty = psbt.inputs[index]
+        .ecdsa_hash_ty()
+        .map_err(|e| CommandErrors::invalid_psbt(format!("input {index}: {e}")))?;
+    if hash_ty != EcdsaSighashType::All {
+        return Err(CommandErrors::invalid_psbt(format!(
+            "input {index}: sighash type {hash_ty} is not allowed"
+        )));
+    }
+    let (message, hash_ty) = psbt
+        .sighash_ecdsa(index, cache)
+        .map_err(|e| CommandErrors::invalid_psbt(format!("input {index}: {e}")))?;
+    for (pk, secret) in keys {
+        let sig = ecdsa::Signature {
+            sig: ctx.secp().sign_ecdsa(&message, &secret),
+            hash_ty,
+        };
+        psbt.inputs[index]
+            .partial_sigs
+            .insert(PublicKey::new(pk), sig);
+    }
+    Ok(true)
+}
+
+/// Key-path spend of a taproot input whose internal key is ours and whose
+/// output commits to no script tree. Script-path leaves are not signed.
+fn sign_taproot_input(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    psbt: &mut Psbt,
+    index: usize,
+    script_pubkey: &ScriptBuf,
+    cache: &mut SighashCache<&Transaction>,
+) -> CommandResult<bool> {
+    let input = &psbt.inputs[index];
+    let Some(internal_key) = input.tap_internal_key else {
+        return Ok(false);
+    };
+    let Some((_, (origin, path))) = input.tap_key_origins.get(&internal_key) else {
+        return Ok(false);
+    };
+    if *origin != ctx.master_fingerprint()
+        || signing_address_type(path, network) != Some(BtcAddressType::Taproot)
+    {
+        return Ok(false);
+    }
+    let keypair = Keypair::from_secret_key(ctx.secp(), &ctx.derive_priv(path)?.private_key);
+    if keypair.x_only_public_key().0 != internal_key {
+        return Ok(false);
+    }
+    let pk = keypair.public_key();
+    if btc_address(ctx.secp(), network, BtcAddressType::Taproot, pk)?.script_pubkey()
+        != *script_pubkey
+    {
+        return Ok(false);
+    }
+
+    let hash_ty = input
+        .taproot_hash_ty()
+        .map_err(|e| CommandErrors::invalid_psbt(format!("input {index}: {e}")))?;
+    if !matches!(hash_ty, TapSighashType::Default | TapSighashType::All) {
+        return Err(CommandErrors::invalid_psbt(format!(
+            "input {index}: sighash type {hash_ty} is not allowed"
+        )));
+    }
+    // taproot 的签名哈希覆盖所有输入的金额与脚本
+    let prevouts = (0..psbt.inputs.len())
+        .map(|other| psbt.spend_utxo(other).cloned())
+        .collect::<Result<Vec<TxOut>, _>>()
+        .map_err(|e| CommandErrors::invalid_psbt(format!("input {index}: {e}")))?;
+    let sighash = cache
+        .taproot_key_spend_signature_hash(index, &Prevouts::All(&prevouts), hash_ty)
+        .map_err(|e| CommandErrors::invalid_psbt(format!("input {index}: {e}")))?;
+    let tweaked = keypair.tap_tweak(ctx.secp(), None);
+    let message = Message::from_digest(sighash.to_byte_array());
+    let sig = ctx.secp().sign_schnorr(&message, &tweaked.to_inner());
+
+    psbt.inputs[index].tap_key_sig = Some(taproot::Signature { sig, hash_ty });
+    Ok(true)
+}
+
+/// Address type a key of this seed may sign for: `path` must be
+/// `m/purpose'/coin_type'/account'/chain/index` with a BTC purpose and the
+/// coin type of `network`, so ETH keys and other networks stay untouched.
+fn signing_address_type(path: &DerivationPath, network: BtcNetwork) -> Option<BtcAddressType> {
+    let [ChildNumber::Hardened { index: purpose }, ChildNumber::Hardened { index: coin_type }, ChildNumber::Hardened { .. }, ChildNumber::Normal { index: chain }, ChildNumber::Normal { .. }] =
+        path.as_ref()
+    else {
+        return None;
+    };
+    if *coin_type != network.coin_type() || *chain > 1 {
+        return None;
+    }
+    BtcAddressType::ALL
+        .into_iter()
+        .find(|address_type| address_type.purpose() == *purpose)
+}
+
+/// Rejects previous transactions that are not the ones the inputs spend, and
+/// `witness_utxo`s that disagree with them.
+fn check_previous_outputs(psbt: &Psbt) -> CommandResult<()> {
+    for (index, (txin, input)) in psbt.unsigned_tx.input.iter().zip(&psbt.inputs).enumerate() {
+        let Some(previous) = &input.non_witness_utxo else {
+            continue;
+        };
+        let outpoint = txin.previous_output;
+        if previous.txid() != outpoint.txid {
+            return Err(CommandErrors::invalid_psbt(format!(
+                "input {index}: previous transaction does not match {outpoint}"
+            )));
+        }
+        let Some(output) = previous.output.get(outpoint.vout as usize) else {
+            return Err(CommandErrors::invalid_psbt(format!(
+                "input {index}: previous transaction has no output {}",
+                outpoint.vout
+            )));
+        };
+        if input
+            .witness_utxo
+            .as_ref()
+            .is_some_and(|utxo| utxo != output)
+        {
+            return Err(CommandErrors::invalid_psbt(format!(
+                "input {index}: witness utxo does not match the previous transaction"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `script` pays to one of the keys `output` claims to derive from
+/// this seed. The keys are re-derived, so a PSBT cannot pass off a foreign
+/// output as change.
+fn owns_output(
+    ctx: &SeedCtx,
+    network: BtcNetwork,
+    script: &ScriptBuf,
+    output: &Output,
+) -> CommandResult<bool> {
+    let fingerprint = ctx.master_fingerprint();
+    let sources = output
+        .bip32_derivation
+        .values()
+        .chain(output.tap_key_origins.values().map(|(_, source)| source));
+    for (origin, path) in sources {
+        if *origin != fingerprint {
+            continue;
+        }
+        let Some(address_type) = signing_address_type(path, network) else {
+            continue;
+        };
+        let pk = ctx.derive_priv(path)?.private_key.public_key(ctx.secp());
+        if btc_address(ctx.secp(), network, address_type, pk)?.script_pubkey() == *script {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn display_address(script: &ScriptBuf, network: Network) -> Option<String> {
+    Address::from_script(script, network)
+        .ok()
+        .map(|address| address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::derive::derive_btc_address;
+    use crate::did::domain::AddressChain;
+    use bip39::Mnemonic;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::bip32::{DerivationPath, KeySource};
+    use bitcoin::key::XOnlyPublicKey;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, OutPoint, TxIn};
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const PAYEE: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    fn key_source(ctx: &SeedCtx, address_type: BtcAddressType, chain: u32) -> KeySource {
+        let path: DerivationPath = format!("m/{}'/0'/0'/{chain}/0", address_type.purpose())
+            .parse()
+            .unwrap();
+        (ctx.master_fingerprint(), path)
+    }
+
+    /// Spends 100 000 sats from receive address 0 of `address_type`, paying
+    /// 60 000 to `PAYEE` and 39 000 back to change address 0.
+    fn spending_psbt(ctx: &SeedCtx, address_type: BtcAddressType) -> Psbt {
+        let address = |chain| {
+            derive_btc_address(ctx, BtcNetwork::Mainnet, address_type, 0, chain, 0).unwrap()
+        };
+        let funding = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(100_000),
+                script_pubkey: address(AddressChain::Receive).script_pubkey(),
+            }],
+        };
+        let payee = Address::from_str(PAYEE).unwrap().assume_checked();
+        let spending = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(funding.txid(), 0),
+                ..TxIn::default()
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(60_000),
+                    script_pubkey: payee.script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::from_sat(39_000),
+                    script_pubkey: address(AddressChain::Change).script_pubkey(),
+                },
+            ],
+        };
+
+        let mut psbt = Psbt::from_unsigned_tx(spending).unwrap();
+        let input_source = key_source(ctx, address_type, 0);
+        let change_source = key_source(ctx, address_type, 1);
+        let public_key = |source: &KeySource| {
+            ctx.derive_priv(&source.1)
+                .unwrap()
+                .private_key
+                .public_key(ctx.secp())
+        };
+        let input_key = public_key(&input_source);
+        let change_key = public_key(&change_source);
+
+        let input = &mut psbt.inputs[0];
+        input.witness_utxo = Some(funding.output[0].clone());
+        match address_type {
+            BtcAddressType::Taproot => {
+                let internal_key = XOnlyPublicKey::from(input_key);
+                input.tap_internal_key = Some(internal_key);
+                input
+                    .tap_key_origins
+                    .insert(internal_key, (vec![], input_source));
+                psbt.outputs[1]
+                    .tap_key_origins
+                    .insert(XOnlyPublicKey::from(change_key), (vec![], change_source));
+            }
+            _ => {
+                input.bip32_derivation.insert(input_key, input_source);
+                psbt.outputs[1]
+                    .bip32_derivation
+                    .insert(change_key, change_source);
+            }
+        }
+        if address_type == BtcAddressType::NestedSegwit {
+            let wpkh = PublicKey::new(input_key).wpubkey_hash().unwrap();
+            input.redeem_script = Some(ScriptBuf::new_p2wpkh(&wpkh));
+        }
+        if address_type != BtcAddressType::Taproot {
+            input.non_witness_utxo = Some(funding);
+        }
+        psbt
+    }
+
+    #[test]
+    fn test_signs_every_address_type() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+
+        for address_type in BtcAddressType::ALL {
+            let unsigned = spending_psbt(&ctx, address_type);
+            let result = sign(&ctx, BtcNetwork::Mainnet, &unsigned.to_string()).unwrap();
+
+            assert_eq!(result.fee_sats, Some(1_000));
+            assert!(result.fee_verified);
+            assert!(result.inputs[0].signed);
+            assert_eq!(result.inputs[0].amount_sats, Some(100_000));
+            assert_eq!(result.outputs[0].address.as_deref(), Some(PAYEE));
+            assert!(!result.outputs[0].is_mine);
+            assert!(result.outputs[1].is_mine);
+
+            let signed = Psbt::from_str(&result.psbt).unwrap();
+            let tx = signed.unsigned_tx.clone();
+            let mut cache = SighashCache::new(&tx);
+            let secp = ctx.secp();
+            let input = &signed.inputs[0];
+            if address_type == BtcAddressType::Taproot {
+                let internal_key = input.tap_internal_key.unwrap();
+                let (output_key, _) = internal_key.tap_tweak(secp, None);
+                let prevouts = [input.witness_utxo.clone().unwrap()];
+                let sighash = cache
+                    .taproot_key_spend_signature_hash(
+                        0,
+                        &Prevouts::All(&prevouts),
+                        bitcoin::TapSighashType::Default,
+                    )
+                    .unwrap();
+                let sig = input.tap_key_sig.unwrap();
+                let message = Message::from_digest(sighash.to_byte_array());
+                secp.verify_schnorr(&sig.sig, &message, &output_key.to_inner())
+                    .unwrap();
+            } else {
+                let (message, _) = signed.sighash_ecdsa(0, &mut cache).unwrap();
+                let (pk, sig) = input.partial_sigs.iter().next().unwrap();
+                secp.verify_ecdsa(&message, &sig.sig, &pk.inner).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_psbt_without_own_inputs() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+        let other = SeedCtx::new(&mnemonic, "TREZOR").unwrap();
+        let foreign = spending_psbt(&other, BtcAddressType::NativeSegwit);
+
+        let err = sign(&ctx, BtcNetwork::Mainnet, &foreign.to_string()).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+        let err = sign(&ctx, BtcNetwork::Mainnet, "not a psbt").unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+    }
+
+    #[test]
+    fn test_rejects_sighash_types_other_than_all() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+
+        for hash_ty in [
+            EcdsaSighashType::None,
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        ] {
+            let mut psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+            psbt.inputs[0].sighash_type = Some(hash_ty.into());
+            let err = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap_err();
+            assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+        }
+        for hash_ty in [TapSighashType::Single, TapSighashType::AllPlusAnyoneCanPay] {
+            let mut psbt = spending_psbt(&ctx, BtcAddressType::Taproot);
+            psbt.inputs[0].sighash_type = Some(hash_ty.into());
+            let err = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap_err();
+            assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+        }
+
+        let mut psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::All.into());
+        assert!(sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_only_signs_btc_paths_of_the_network() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+
+        // 主网路径不能在测试网下签名
+        let psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+        let err = sign(&ctx, BtcNetwork::Testnet, &psbt.to_string()).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+
+        // ETH 路径的密钥即使脚本匹配也不签
+        let eth_path: DerivationPath = "m/44'/60'/0'/0/0".parse().unwrap();
+        let eth_key = ctx
+            .derive_priv(&eth_path)
+            .unwrap()
+            .private_key
+            .public_key(ctx.secp());
+        let mut psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+        let script = btc_address(
+            ctx.secp(),
+            BtcNetwork::Mainnet,
+            BtcAddressType::NativeSegwit,
+            eth_key,
+        )
+        .unwrap()
+        .script_pubkey();
+        let input = &mut psbt.inputs[0];
+        input.non_witness_utxo = None;
+        input.witness_utxo.as_mut().unwrap().script_pubkey = script;
+        input.bip32_derivation.clear();
+        input
+            .bip32_derivation
+            .insert(eth_key, (ctx.master_fingerprint(), eth_path));
+        let err = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+    }
+
+    #[test]
+    fn test_checks_previous_outputs() {
+        let mnemonic = Mnemonic::parse(MNEMONIC).unwrap();
+        let ctx = SeedCtx::new(&mnemonic, "").unwrap();
+
+        // 前序交易与 outpoint 的 txid 不符
+        let mut psbt = spending_psbt(&ctx, BtcAddressType::Legacy);
+        let previous = psbt.inputs[0].non_witness_utxo.as_mut().unwrap();
+        previous.output[0].value = Amount::from_sat(1_000_000);
+        let err = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+
+        // witness_utxo 与前序交易不一致
+        let mut psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+        psbt.inputs[0].witness_utxo.as_mut().unwrap().value = Amount::from_sat(1_000_000);
+        let err = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+
+        // 被花费的脚本不是派生出的地址
+        let mut psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+        let payee = Address::from_str(PAYEE).unwrap().assume_checked();
+        psbt.inputs[0].non_witness_utxo = None;
+        psbt.inputs[0].witness_utxo.as_mut().unwrap().script_pubkey = payee.script_pubkey();
+        let err = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap_err();
+        assert!(matches!(err, CommandErrors::InvalidPsbt(_)));
+
+        // 只有 witness_utxo 时照常签名，但手续费标为未验证
+        let mut psbt = spending_psbt(&ctx, BtcAddressType::NativeSegwit);
+        psbt.inputs[0].non_witness_utxo = None;
+        let result = sign(&ctx, BtcNetwork::Mainnet, &psbt.to_string()).unwrap();
+        assert!(result.inputs[0].signed);
+        assert_eq!(result.fee_sats, Some(1_000));
+        assert!(!result.fee_verified);
+    }
+}
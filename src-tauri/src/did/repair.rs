@@ -0,0 +1,247 @@
+//! Guesses corrections for a mnemonic that fails to parse: one misspelled,
+//! unreadable or wrong word, or two neighbouring words written in the wrong
+//! order. Only candidates passing the BIP39 checksum are returned.
+
+use bip39::{Language, Mnemonic};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::error::{CommandErrors, CommandResult};
+
+use super::mnemonic::closest_words;
+
+/// Largest edit distance tried when replacing a word.
+pub const MAX_EDIT_DISTANCE: usize = 2;
+
+/// Placeholder users type for a word they cannot read.
+const UNREADABLE_WORD: &str = "?";
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RepairChange {
+    /// The phrase already passes the checksum.
+    Unchanged,
+    /// The word at the 1-based `position` was replaced. `distance` is the
+    /// edit distance to the original, absent for unreadable words.
+    Substitution {
+        position: usize,
+        original: String,
+        replacement: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        distance: Option<usize>,
+    },
+    /// The words at the 1-based `position` and `position + 1` were swapped.
+    Swap { position: usize },
+}
+
+impl RepairChange {
+    /// Edits needed to get from the input to the candidate, used for ranking.
+    fn cost(&self) -> usize {
+        match self {
+            RepairChange::Unchanged => 0,
+            RepairChange::Substitution { distance, .. } => {
+                distance.unwrap_or(MAX_EDIT_DISTANCE + 1)
+            }
+            RepairChange::Swap { .. } => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MnemonicRepair {
+    pub words: Vec<String>,
+    pub change: RepairChange,
+    /// Whether the candidate derives the DID the caller expected; absent
+    /// when no DID was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches_expected_did: Option<bool>,
+}
+
+/// Candidates for `words` in `language` that pass the checksum, fewest edits
+/// first. At most one word may be missing from the wordlist: an empty or
+/// `?` word is tried with all 2048 words, a misspelled one with its closest
+/// matches. When every word is known, each one is tried with its close
+/// matches and every pair of neighbours is swapped.
+pub fn repair_candidates(
+    words: &[String],
+    language: Language,
+) -> CommandResult<Vec<MnemonicRepair>> {
+    let words: Vec<String> = words
+        .iter()
+        .map(|word| word.trim().to_lowercase())
+        .collect();
+    if !matches!(words.len(), 12 | 15 | 18 | 21 | 24) {
+        return Err(bip39::Error::BadWordCount(words.len()).into());
+    }
+
+    let unknown: Vec<usize> = (0..words.len())
+        .filter(|&index| language.find_word(&words[index]).is_none())
+        .collect();
+
+    let mut candidates = Vec::new();
+    match unknown.as_slice() {
+        [] => {
+            if passes_checksum(&words, language) {
+                candidates.push(MnemonicRepair {
+                    words: words.clone(),
+                    change: RepairChange::Unchanged,
+                    matches_expected_did: None,
+                });
+            }
+            for index in 0..words.len() {
+                substitute(&words, index, language, &mut candidates);
+            }
+            for (index, pair) in words.windows(2).enumerate() {
+                if pair[0] == pair[1] {
+                    continue;
+                }
+                let mut swapped = words.clone();
+                swapped.swap(index, index + 1);
+                if passes_checksum(&swapped, language) {
+                    candidates.push(MnemonicRepair {
+                        words: swapped,
+                        change: RepairChange::Swap {
+                            position: index + 1,
+                        },
+                        matches_expected_did: None,
+                    });
+                }
+            }
+        }
+        [index] => substitute(&words, *index, language, &mut candidates),
+        _ => {
+            return Err(CommandErrors::invalid_mnemonic(format!(
+                "{} words are not in the wordlist, at most one can be repaired",
+                unknown.len()
+            )));
+        }
+    }
+
+    // 稳定排序：编辑次数相同的候选按位置先后
+    candidates.sort_by_key(|candidate| candidate.change.cost());
+    let mut seen = HashSet::new();
+    candidates.retain(|candidate| seen.insert(candidate.words.clone()));
+    Ok(candidates)
+}
+
+/// Pushes every replacement of `words[index]` that passes the checksum.
+fn substitute(words: &[String], index: usize, language: Language, out: &mut Vec<MnemonicRepair>) {
+    let original = &words[index];
+    let replacements: Vec<(&'static str, Option<usize>)> =
+        if original.is_empty() || original == UNREADABLE_WORD {
+            language
+                .word_list()
+                .iter()
+                .map(|word| (*word, None))
+                .collect()
+        } else {
+            let close = closest_words(language, original, MAX_EDIT_DISTANCE);
+            if close.is_empty() && language.find_word(original).is_none() {
+                // 拼写错得太离谱时退化为尝试整个词表
+                language
+                    .word_list()
+                    .iter()
+                    .map(|word| (*word, None))
+                    .collect()
+            } else {
+                close
+                    .into_iter()
+                    .filter(|(_, distance)| *distance > 0)
+                    .map(|(word, distance)| (word, Some(distance)))
+                    .collect()
+            }
+        };
+
+    let mut candidate = words.to_vec();
+    for (replacement, distance) in replacements {
+        candidate[index] = replacement.to_string();
+        if passes_checksum(&candidate, language) {
+            out.push(MnemonicRepair {
+                words: candidate.clone(),
+                change: RepairChange::Substitution {
+                    position: index + 1,
+                    original: original.clone(),
+                    replacement: replacement.to_string(),
+                    distance,
+                },
+                matches_expected_did: None,
+            });
+        }
+    }
+}
+
+fn passes_checksum(words: &[String], language: Language) -> bool {
+    Mnemonic::parse_in(language, words.join(" ")).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str =
+        "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+    fn words(phrase: &str) -> Vec<String> {
+        phrase.split(' ').map(str::to_string).collect()
+    }
+
+    fn contains_phrase(candidates: &[MnemonicRepair]) -> bool {
+        candidates
+            .iter()
+            .any(|candidate| candidate.words == words(PHRASE))
+    }
+
+    #[test]
+    fn test_valid_phrase_is_first() {
+        let candidates = repair_candidates(&words(PHRASE), Language::English).unwrap();
+        assert_eq!(candidates[0].words, words(PHRASE));
+        assert_eq!(candidates[0].change, RepairChange::Unchanged);
+    }
+
+    #[test]
+    fn test_repairs_unreadable_and_misspelled_words() {
+        let mut input = words(PHRASE);
+        input[5] = "?".to_string();
+        let candidates = repair_candidates(&input, Language::English).unwrap();
+        assert!(contains_phrase(&candidates));
+        // 12 个词只有 4 位校验和，约 1/16 的单词能通过
+        assert!(candidates.len() < 2048 / 8);
+
+        let mut input = words(PHRASE);
+        input[5] = "sausge".to_string();
+        let candidates = repair_candidates(&input, Language::English).unwrap();
+        assert_eq!(
+            candidates[0].change,
+            RepairChange::Substitution {
+                position: 6,
+                original: "sausge".to_string(),
+                replacement: "sausage".to_string(),
+                distance: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_repairs_wrong_word_and_swap() {
+        let mut input = words(PHRASE);
+        input.swap(10, 11);
+        let candidates = repair_candidates(&input, Language::English).unwrap();
+        assert!(candidates
+            .iter()
+            .any(|c| c.change == RepairChange::Swap { position: 11 } && c.words == words(PHRASE)));
+
+        let mut input = words(PHRASE);
+        input[4] = "wage".to_string();
+        let candidates = repair_candidates(&input, Language::English).unwrap();
+        assert!(contains_phrase(&candidates));
+    }
+
+    #[test]
+    fn test_rejects_unrepairable_input() {
+        let mut input = words(PHRASE);
+        input[0] = "?".to_string();
+        input[1] = "?".to_string();
+        assert!(repair_candidates(&input, Language::English).is_err());
+        assert!(repair_candidates(&input[..11], Language::English).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use super::crypto::SeedSecret;
+
+pub const SESSION_UNLOCKED_EVENT: &str = "did-session-unlocked";
+pub const SESSION_LOCKED_EVENT: &str = "did-session-locked";
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 5 * 60;
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionInfo {
+    pub did_id: String,
+    pub idle_timeout_secs: u64,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionLockedPayload {
+    pub did_ids: Vec<String>,
+}
+
+struct UnlockedSeed {
+    secret: SeedSecret,
+    idle_timeout: Duration,
+    last_used: Instant,
+}
+
+impl UnlockedSeed {
+    fn expired(&self, now: Instant) -> bool {
+        now.duration_since(self.last_used) >= self.idle_timeout
+    }
+
+    fn info(&self, did_id: &str, now: Instant) -> SessionInfo {
+        let idle = now.duration_since(self.last_used);
+        SessionInfo {
+            did_id: did_id.to_string(),
+            idle_timeout_secs: self.idle_timeout.as_secs(),
+            expires_in_secs: self.idle_timeout.saturating_sub(idle).as_secs(),
+        }
+    }
+}
+
+/// Decrypted seeds of unlocked DIDs, kept in zeroizing memory until they sit
+/// idle longer than their timeout or are locked explicitly. The vault key of
+/// sealed metadata is not kept here but by the
+/// [`VaultHandle`](super::store::VaultHandle) and the opened vault it caches.
+#[derive(Default)]
+pub struct SessionManager {
+    sessions: Mutex<HashMap<String, UnlockedSeed>>,
+}
+
+impl SessionManager {
+    pub fn unlock(&self, did_id: &str, secret: SeedSecret, idle_timeout: Duration) -> SessionInfo {
+        let now = Instant::now();
+        let seed = UnlockedSeed {
+            secret,
+            idle_timeout,
+            last_used: now,
+        };
+        let info = seed.info(did_id, now);
+        self.sessions
+            .lock()
+            .expect("session lock poisoned")
+            .insert(did_id.to_string(), seed);
+        info
+    }
+
+    /// Locks `did_id`, or every session when `None`. Returns the ids that were locked.
+    pub fn lock(&self, did_id: Option<&str>) -> Vec<String> {
+        let mut sessions = self.sessions.lock().expect("session lock poisoned");
+        match did_id {
+            Some(id) => sessions
+                .remove(id)
+                .map(|_| vec![id.to_string()])
+                .unwrap_or_default(),
+            None => sessions.drain().map(|(id, _)| id).collect(),
+        }
+    }
+
+    /// Returns the seed of an unlocked, unexpired session and restarts its idle timer.
+    pub fn secret(&self, did_id: &str) -> Option<SeedSecret> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().expect("session lock poisoned");
+        let seed = sessions.get_mut(did_id)?;
+        if seed.expired(now) {
+            sessions.remove(did_id);
+            return None;
+        }
+        seed.last_used = now;
+        Some(seed.secret.clone())
+    }
+
+    pub fn status(&self) -> Vec<SessionInfo> {
+        let now = Instant::now();
+        let sessions = self.sessions.lock().expect("session lock poisoned");
+        sessions
+            .iter()
+            .filter(|(_, seed)| !seed.expired(now))
+            .map(|(id, seed)| seed.info(id, now))
+            .collect()
+    }
+
+    /// Drops every session past its idle timeout. Returns the ids that were locked.
+    pub fn lock_expired(&self) -> Vec<String> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().expect("session lock poisoned");
+        let expired: Vec<String> = sessions
+            .iter()
+            .filter(|(_, seed)| seed.expired(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &expired {
+            sessions.remove(id);
+        }
+        expired
+    }
+}
+
+pub fn emit_locked<R: Runtime>(app_handle: &AppHandle<R>, did_ids: Vec<String>) {
+    if did_ids.is_empty() {
+        return;
+    }
+    if let Err(err) = app_handle.emit(SESSION_LOCKED_EVENT, SessionLockedPayload { did_ids }) {
+        log::warn!("emit {SESSION_LOCKED_EVENT} failed: {err}");
+    }
+}
+
+pub fn emit_unlocked<R: Runtime>(app_handle: &AppHandle<R>, info: &SessionInfo) {
+    if let Err(err) = app_handle.emit(SESSION_UNLOCKED_EVENT, info) {
+        log::warn!("emit {SESSION_UNLOCKED_EVENT} failed: {err}");
+    }
+}
+
+/// Starts the background thread that auto-locks idle sessions.
+pub fn spawn_session_watcher<R: Runtime>(app_handle: AppHandle<R>) {
+    thread::spawn(move || loop {
+        thread::sleep(WATCH_INTERVAL);
+        let expired = app_handle.state::<SessionManager>().lock_expired();
+        emit_locked(&app_handle, expired);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zeroize::Zeroizing;
+
+    fn secret(phrase: &str) -> SeedSecret {
+        SeedSecret {
+            phrase: Zeroizing::new(phrase.to_string()),
+            passphrase: Zeroizing::default(),
+        }
+    }
+
+    fn phrase(sessions: &SessionManager, did_id: &str) -> Option<String> {
+        sessions
+            .secret(did_id)
+            .map(|secret| secret.phrase.to_string())
+    }
+
+    #[test]
+    fn test_session_expires_after_idle_timeout() {
+        let sessions = SessionManager::default();
+        sessions.unlock("did-a", secret("phrase a"), Duration::from_millis(50));
+        sessions.unlock("did-b", secret("phrase b"), Duration::from_secs(60));
+
+        assert_eq!(phrase(&sessions, "did-a").as_deref(), Some("phrase a"));
+        assert_eq!(sessions.status().len(), 2);
+
+        thread::sleep(Duration::from_millis(80));
+        assert_eq!(sessions.lock_expired(), vec!["did-a".to_string()]);
+        assert!(phrase(&sessions, "did-a").is_none());
+        assert_eq!(phrase(&sessions, "did-b").as_deref(), Some("phrase b"));
+
+        assert_eq!(sessions.lock(None), vec!["did-b".to_string()]);
+        assert!(sessions.status().is_empty());
+    }
+}
@@ -0,0 +1,569 @@
+//! SLIP-0039 Shamir backup. The shared master secret is the BIP39 entropy of
+//! a DID, so the recovered secret rebuilds the original phrase and every
+//! identity derived from it.
+
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use super::slip39_words::WORDLIST;
+
+const RADIX_BITS: usize = 10;
+const HEADER_WORDS: usize = 4;
+const CHECKSUM_WORDS: usize = 3;
+const MIN_MNEMONIC_WORDS: usize = 20;
+const MIN_SECRET_BYTES: usize = 16;
+pub const MAX_SHARE_COUNT: u8 = 16;
+const DIGEST_LENGTH: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+const ROUND_COUNT: u8 = 4;
+pub const DEFAULT_ITERATION_EXPONENT: u8 = 1;
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+const RS1024_GEN: [u32; 10] = [
+    0x00E0_E040,
+    0x01C1_C080,
+    0x0383_8100,
+    0x0707_0200,
+    0x0E0E_0009,
+    0x1C0C_2412,
+    0x3808_6C24,
+    0x3090_FC48,
+    0x21B1_F890,
+    0x03F3_F120,
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Slip39Error {
+    #[error("unknown share word: {0}")]
+    UnknownWord(String),
+    #[error("invalid share length")]
+    InvalidLength,
+    #[error("invalid share padding")]
+    InvalidPadding,
+    #[error("invalid share checksum")]
+    InvalidChecksum,
+    #[error("shares belong to different backups")]
+    MismatchedShares,
+    #[error("duplicate share index {0}")]
+    DuplicateShare(u8),
+    #[error("not enough shares: {0}")]
+    InsufficientShares(String),
+    #[error("share digest mismatch")]
+    InvalidDigest,
+    #[error("invalid sharing scheme: {0}")]
+    InvalidScheme(String),
+}
+
+pub type Slip39Result<T> = Result<T, Slip39Error>;
+
+// GF(256) 使用 Rijndael 多项式 x^8 + x^4 + x^3 + x + 1，生成元为 3。
+const GF_TABLES: ([u8; 255], [u8; 256]) = gf_tables();
+
+const fn gf_tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+type RawShare = (u8, Zeroizing<Vec<u8>>);
+
+fn interpolate(shares: &[RawShare], x: u8) -> Slip39Result<Zeroizing<Vec<u8>>> {
+    let (exp, log) = (&GF_TABLES.0, &GF_TABLES.1);
+    let len = shares
+        .first()
+        .map(|(_, value)| value.len())
+        .ok_or_else(|| Slip39Error::InsufficientShares("no shares".to_string()))?;
+    for (i, (xi, value)) in shares.iter().enumerate() {
+        if value.len() != len {
+            return Err(Slip39Error::MismatchedShares);
+        }
+        if shares[..i].iter().any(|(xj, _)| xj == xi) {
+            return Err(Slip39Error::DuplicateShare(*xi));
+        }
+        if *xi == x {
+            return Ok(value.clone());
+        }
+    }
+
+    let log_prod: i32 = shares
+        .iter()
+        .map(|(xi, _)| log[(xi ^ x) as usize] as i32)
+        .sum();
+    let mut result = Zeroizing::new(vec![0u8; len]);
+    for (xi, value) in shares {
+        let others: i32 = shares
+            .iter()
+            .map(|(xj, _)| log[(xi ^ xj) as usize] as i32)
+            .sum();
+        let basis = (log_prod - log[(xi ^ x) as usize] as i32 - others).rem_euclid(255);
+        for (out, &byte) in result.iter_mut().zip(value.iter()) {
+            if byte != 0 {
+                *out ^= exp[((log[byte as usize] as i32 + basis) % 255) as usize];
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn random_bytes(len: usize) -> Zeroizing<Vec<u8>> {
+    let mut bytes = Zeroizing::new(vec![0u8; len]);
+    OsRng.fill_bytes(&mut bytes[..]);
+    bytes
+}
+
+fn create_digest(random: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(random).expect("hmac accepts any key");
+    mac.update(secret);
+    let mut digest = [0u8; DIGEST_LENGTH];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH]);
+    digest
+}
+
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> Slip39Result<Vec<RawShare>> {
+    if threshold == 1 {
+        return Ok((0..count)
+            .map(|x| (x, Zeroizing::new(secret.to_vec())))
+            .collect());
+    }
+
+    let random_count = threshold - 2;
+    let mut shares: Vec<RawShare> = (0..random_count)
+        .map(|x| (x, random_bytes(secret.len())))
+        .collect();
+    let random_part = random_bytes(secret.len() - DIGEST_LENGTH);
+    let mut digest_share = Zeroizing::new(create_digest(&random_part, secret).to_vec());
+    digest_share.extend_from_slice(&random_part);
+
+    let mut base = shares.clone();
+    base.push((DIGEST_INDEX, digest_share));
+    base.push((SECRET_INDEX, Zeroizing::new(secret.to_vec())));
+    for x in random_count..count {
+        shares.push((x, interpolate(&base, x)?));
+    }
+    Ok(shares)
+}
+
+fn recover_secret(threshold: u8, shares: &[RawShare]) -> Slip39Result<Zeroizing<Vec<u8>>> {
+    if threshold == 1 {
+        return shares
+            .first()
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| Slip39Error::InsufficientShares("no shares".to_string()));
+    }
+    let secret = interpolate(shares, SECRET_INDEX)?;
+    let digest_share = interpolate(shares, DIGEST_INDEX)?;
+    let (digest, random) = digest_share.split_at(DIGEST_LENGTH);
+    if digest != create_digest(random, &secret) {
+        return Err(Slip39Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+fn feistel(
+    input: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    rounds: impl Iterator<Item = u8>,
+) -> Zeroizing<Vec<u8>> {
+    let mut salt = Vec::new();
+    if !extendable {
+        salt.extend_from_slice(CUSTOMIZATION);
+        salt.extend_from_slice(&identifier.to_be_bytes());
+    }
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / ROUND_COUNT as u32;
+
+    let half = input.len() / 2;
+    let mut left = Zeroizing::new(input[..half].to_vec());
+    let mut right = Zeroizing::new(input[half..].to_vec());
+    for round in rounds {
+        let mut password = Zeroizing::new(vec![round]);
+        password.extend_from_slice(passphrase);
+        let mut round_salt = salt.clone();
+        round_salt.extend_from_slice(&right);
+        let mut f = Zeroizing::new(vec![0u8; right.len()]);
+        pbkdf2_hmac::<Sha256>(&password, &round_salt, iterations, &mut f);
+
+        let next: Zeroizing<Vec<u8>> =
+            Zeroizing::new(left.iter().zip(f.iter()).map(|(l, f)| l ^ f).collect());
+        left = std::mem::replace(&mut right, next);
+    }
+
+    let mut output = Zeroizing::new(Vec::with_capacity(input.len()));
+    output.extend_from_slice(&right);
+    output.extend_from_slice(&left);
+    output
+}
+
+fn rs1024_polymod(customization: &[u8], values: &[u16]) -> u32 {
+    let mut chk: u32 = 1;
+    let all = customization
+        .iter()
+        .map(|&b| b as u32)
+        .chain(values.iter().map(|&v| v as u32));
+    for value in all {
+        let top = chk >> 20;
+        chk = ((chk & 0xF_FFFF) << 10) ^ value;
+        for (i, generator) in RS1024_GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_EXTENDABLE
+    } else {
+        CUSTOMIZATION
+    }
+}
+
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    let mut words = Vec::with_capacity(word_count);
+    // 高位补零，使比特数凑满整数个单词。
+    let mut acc: u32 = 0;
+    let mut acc_bits = word_count * RADIX_BITS - bytes.len() * 8;
+    for &byte in bytes {
+        acc = (acc << 8) | byte as u32;
+        acc_bits += 8;
+        while acc_bits >= RADIX_BITS {
+            acc_bits -= RADIX_BITS;
+            words.push(((acc >> acc_bits) & 0x3FF) as u16);
+        }
+        acc &= (1 << acc_bits) - 1;
+    }
+    words
+}
+
+fn words_to_bytes(words: &[u16], padding: usize) -> Slip39Result<Zeroizing<Vec<u8>>> {
+    let mut bytes = Zeroizing::new(Vec::with_capacity((words.len() * RADIX_BITS) / 8));
+    let mut acc: u32 = 0;
+    let mut acc_bits = 0usize;
+    let mut skip = padding;
+    for &word in words {
+        acc = (acc << RADIX_BITS) | word as u32;
+        acc_bits += RADIX_BITS;
+        if skip > 0 {
+            acc_bits -= skip;
+            if acc >> acc_bits != 0 {
+                return Err(Slip39Error::InvalidPadding);
+            }
+            skip = 0;
+        }
+        while acc_bits >= 8 {
+            acc_bits -= 8;
+            bytes.push((acc >> acc_bits) as u8);
+        }
+        acc &= (1 << acc_bits) - 1;
+    }
+    Ok(bytes)
+}
+
+#[derive(Clone)]
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    fn common_params(&self) -> (u16, bool, u8, u8, u8) {
+        (
+            self.identifier,
+            self.extendable,
+            self.iteration_exponent,
+            self.group_threshold,
+            self.group_count,
+        )
+    }
+
+    fn to_mnemonic(&self) -> Zeroizing<String> {
+        let id_exp = ((self.identifier as u32) << 5)
+            | ((self.extendable as u32) << 4)
+            | self.iteration_exponent as u32;
+        let params = ((self.group_index as u32) << 16)
+            | (((self.group_threshold - 1) as u32) << 12)
+            | (((self.group_count - 1) as u32) << 8)
+            | ((self.member_index as u32) << 4)
+            | (self.member_threshold - 1) as u32;
+
+        let mut indices = Zeroizing::new(vec![
+            (id_exp >> 10) as u16,
+            (id_exp & 0x3FF) as u16,
+            (params >> 10) as u16,
+            (params & 0x3FF) as u16,
+        ]);
+        indices.extend(bytes_to_words(&self.value));
+        let checksum = rs1024_polymod(
+            customization(self.extendable),
+            &[indices.as_slice(), &[0; CHECKSUM_WORDS][..]].concat(),
+        ) ^ 1;
+        indices.extend([
+            ((checksum >> 20) & 0x3FF) as u16,
+            ((checksum >> 10) & 0x3FF) as u16,
+            (checksum & 0x3FF) as u16,
+        ]);
+
+        let words: Vec<&str> = indices.iter().map(|&i| WORDLIST[i as usize]).collect();
+        Zeroizing::new(words.join(" "))
+    }
+
+    fn parse(mnemonic: &str) -> Slip39Result<Self> {
+        let indices = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                let word = word.to_lowercase();
+                WORDLIST
+                    .binary_search(&word.as_str())
+                    .map(|i| i as u16)
+                    .map_err(|_| Slip39Error::UnknownWord(word))
+            })
+            .collect::<Slip39Result<Vec<u16>>>()
+            .map(Zeroizing::new)?;
+        if indices.len() < MIN_MNEMONIC_WORDS {
+            return Err(Slip39Error::InvalidLength);
+        }
+        let value_words = &indices[HEADER_WORDS..indices.len() - CHECKSUM_WORDS];
+        let padding = (value_words.len() * RADIX_BITS) % 16;
+        if padding > 8 {
+            return Err(Slip39Error::InvalidLength);
+        }
+
+        let id_exp = ((indices[0] as u32) << 10) | indices[1] as u32;
+        let extendable = (id_exp >> 4) & 1 == 1;
+        if rs1024_polymod(customization(extendable), &indices) != 1 {
+            return Err(Slip39Error::InvalidChecksum);
+        }
+
+        let params = ((indices[2] as u32) << 10) | indices[3] as u32;
+        let group_threshold = ((params >> 12) & 0xF) as u8 + 1;
+        let group_count = ((params >> 8) & 0xF) as u8 + 1;
+        if group_threshold > group_count {
+            return Err(Slip39Error::InvalidScheme(
+                "group threshold exceeds group count".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xF) as u8,
+            group_index: (params >> 16) as u8,
+            group_threshold,
+            group_count,
+            member_index: ((params >> 4) & 0xF) as u8,
+            member_threshold: (params & 0xF) as u8 + 1,
+            value: words_to_bytes(value_words, padding)?,
+        })
+    }
+}
+
+/// Splits `secret` into `count` share mnemonics of a single group, any
+/// `threshold` of which recover it with [`combine_mnemonics`].
+pub fn split_mnemonics(
+    secret: &[u8],
+    passphrase: &[u8],
+    threshold: u8,
+    count: u8,
+) -> Slip39Result<Vec<Zeroizing<String>>> {
+    if secret.len() < MIN_SECRET_BYTES || !secret.len().is_multiple_of(2) {
+        return Err(Slip39Error::InvalidScheme(format!(
+            "secret must be an even number of bytes, at least {MIN_SECRET_BYTES}"
+        )));
+    }
+    if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+        return Err(Slip39Error::InvalidScheme(format!(
+            "threshold must be between 1 and share count, share count at most {MAX_SHARE_COUNT}"
+        )));
+    }
+    if threshold == 1 && count > 1 {
+        return Err(Slip39Error::InvalidScheme(
+            "1-of-n sharing is not allowed, use 1-of-1".to_string(),
+        ));
+    }
+
+    let identifier = (OsRng.next_u32() & 0x7FFF) as u16;
+    let encrypted = feistel(
+        secret,
+        passphrase,
+        DEFAULT_ITERATION_EXPONENT,
+        identifier,
+        true,
+        0..ROUND_COUNT,
+    );
+
+    let shares = split_secret(threshold, count, &encrypted)?
+        .into_iter()
+        .map(|(member_index, value)| {
+            Share {
+                identifier,
+                extendable: true,
+                iteration_exponent: DEFAULT_ITERATION_EXPONENT,
+                group_index: 0,
+                group_threshold: 1,
+                group_count: 1,
+                member_index,
+                member_threshold: threshold,
+                value,
+            }
+            .to_mnemonic()
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// Recovers the master secret from share mnemonics. Shares beyond the
+/// thresholds are ignored.
+pub fn combine_mnemonics<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &[u8],
+) -> Slip39Result<Zeroizing<Vec<u8>>> {
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::parse(mnemonic.as_ref()))
+        .collect::<Slip39Result<Vec<_>>>()?;
+    let first = shares
+        .first()
+        .ok_or_else(|| Slip39Error::InsufficientShares("no shares".to_string()))?;
+    if shares
+        .iter()
+        .any(|share| share.common_params() != first.common_params())
+    {
+        return Err(Slip39Error::MismatchedShares);
+    }
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+    for share in &shares {
+        groups.entry(share.group_index).or_default().push(share);
+    }
+
+    let mut group_secrets = Vec::new();
+    for (group_index, members) in &groups {
+        let threshold = members[0].member_threshold;
+        if members.iter().any(|m| m.member_threshold != threshold) {
+            return Err(Slip39Error::MismatchedShares);
+        }
+        if members.len() < threshold as usize {
+            continue;
+        }
+        let raw: Vec<RawShare> = members
+            .iter()
+            .take(threshold as usize)
+            .map(|m| (m.member_index, m.value.clone()))
+            .collect();
+        group_secrets.push((*group_index, recover_secret(threshold, &raw)?));
+        if group_secrets.len() == first.group_threshold as usize {
+            break;
+        }
+    }
+    if group_secrets.len() < first.group_threshold as usize {
+        return Err(Slip39Error::InsufficientShares(format!(
+            "{} of {} groups complete",
+            group_secrets.len(),
+            first.group_threshold
+        )));
+    }
+
+    let encrypted = recover_secret(first.group_threshold, &group_secrets)?;
+    Ok(feistel(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+        (0..ROUND_COUNT).rev(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SLIP-0039 官方测试向量，口令为 "TREZOR"。
+    const PASSPHRASE: &[u8] = b"TREZOR";
+
+    #[test]
+    fn test_official_vectors() {
+        let single = ["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"];
+        assert_eq!(
+            hex::encode(combine_mnemonics(&single, PASSPHRASE).unwrap().as_slice()),
+            "bb54aac4b89dc868ba37d9cc21b2cece"
+        );
+
+        let two_of_three = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ];
+        assert_eq!(
+            hex::encode(
+                combine_mnemonics(&two_of_three, PASSPHRASE)
+                    .unwrap()
+                    .as_slice()
+            ),
+            "b43ceb7e57a0ea8766221624d01b0864"
+        );
+        assert!(matches!(
+            combine_mnemonics(&two_of_three[..1], PASSPHRASE),
+            Err(Slip39Error::InsufficientShares(_))
+        ));
+
+        let bits_256 = ["theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck"];
+        assert_eq!(
+            hex::encode(combine_mnemonics(&bits_256, PASSPHRASE).unwrap().as_slice()),
+            "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92"
+        );
+
+        let bad_checksum = ["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision kidney"];
+        assert_eq!(
+            combine_mnemonics(&bad_checksum, PASSPHRASE).err(),
+            Some(Slip39Error::InvalidChecksum)
+        );
+    }
+
+    #[test]
+    fn test_split_and_combine_any_threshold_subset() {
+        let secret = hex::decode("0c1e24e5917779d297e14d45f14e1a1a").unwrap();
+        let shares = split_mnemonics(&secret, b"", 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert!(shares.iter().all(|s| s.split_whitespace().count() == 20));
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<&str> = subset.iter().map(|&i| shares[i].as_str()).collect();
+            assert_eq!(combine_mnemonics(&picked, b"").unwrap().as_slice(), secret);
+        }
+        let two: Vec<&str> = shares[..2].iter().map(|s| s.as_str()).collect();
+        assert!(combine_mnemonics(&two, b"").is_err());
+
+        assert!(split_mnemonics(&secret, b"", 4, 3).is_err());
+        assert!(split_mnemonics(&secret, b"", 1, 3).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use ulid::{Generator, Ulid};
+
+use crate::error::{CommandErrors, CommandResult};
+
+/// Number of vault snapshots kept next to the store file.
+pub const SNAPSHOT_LIMIT: usize = 5;
+const SNAPSHOT_PREFIX: &str = "vault-";
+const SNAPSHOT_SUFFIX: &str = ".json";
+
+// 同一毫秒内生成的 id 也需要保持递增，列表排序依赖这一点。
+static SNAPSHOT_IDS: Mutex<Generator> = Mutex::new(Generator::new());
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VaultSnapshot {
+    pub id: String,
+    pub created_at_ms: u64,
+}
+
+fn io_failed(context: &str, path: &Path, err: std::io::Error) -> CommandErrors {
+    CommandErrors::store_unavailable(format!("{context} {}: {err}", path.display()))
+}
+
+/// Writes `bytes` to `path` through a temp file, fsync and rename, so a crash
+/// leaves either the old or the new content on disk, never a truncated file.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> CommandResult<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| CommandErrors::store_unavailable("invalid store path"))?;
+    fs::create_dir_all(dir).map_err(|e| io_failed("create dir", dir, e))?;
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = dir.join(tmp_name);
+
+    let mut file = File::create(&tmp_path).map_err(|e| io_failed("create", &tmp_path, e))?;
+    file.write_all(bytes)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| io_failed("write", &tmp_path, e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, path).map_err(|e| io_failed("rename", path, e))?;
+    #[cfg(unix)]
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| io_failed("sync dir", dir, e))?;
+    Ok(())
+}
+
+fn snapshot_path(dir: &Path, id: &str) -> CommandResult<PathBuf> {
+    // 只接受 ULID，避免 id 被用来拼出目录之外的路径。
+    Ulid::from_string(id).map_err(|_| CommandErrors::not_found("snapshot_not_found"))?;
+    Ok(dir.join(format!("{SNAPSHOT_PREFIX}{id}{SNAPSHOT_SUFFIX}")))
+}
+
+/// Stores `vault` as a new snapshot and drops all but the newest [`SNAPSHOT_LIMIT`].
+pub fn write_snapshot(dir: &Path, vault: &Value) -> CommandResult<VaultSnapshot> {
+    let id = SNAPSHOT_IDS
+        .lock()
+        .expect("snapshot id lock poisoned")
+        .generate()
+        .map_err(|e| CommandErrors::internal(format!("snapshot id: {e}")))?;
+    let bytes =
+        serde_json::to_vec(vault).map_err(|e| CommandErrors::vault_corrupted(e.to_string()))?;
+    write_atomic(&snapshot_path(dir, &id.to_string())?, &bytes)?;
+
+    for stale in list_snapshots(dir)?.iter().skip(SNAPSHOT_LIMIT) {
+        let path = snapshot_path(dir, &stale.id)?;
+        if let Err(err) = fs::remove_file(&path) {
+            log::warn!("remove stale snapshot {} failed: {err}", path.display());
+        }
+    }
+
+    Ok(VaultSnapshot {
+        id: id.to_string(),
+        created_at_ms: id.timestamp_ms(),
+    })
+}
+
+/// Lists snapshots, newest first.
+pub fn list_snapshots(dir: &Path) -> CommandResult<Vec<VaultSnapshot>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(io_failed("read dir", dir, err)),
+    };
+
+    let mut ids: Vec<Ulid> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let id = name
+                .strip_prefix(SNAPSHOT_PREFIX)?
+                .strip_suffix(SNAPSHOT_SUFFIX)?;
+            Ulid::from_string(id).ok()
+        })
+        .collect();
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+
+    Ok(ids
+        .into_iter()
+        .map(|id| VaultSnapshot {
+            id: id.to_string(),
+            created_at_ms: id.timestamp_ms(),
+        })
+        .collect())
+}
+
+/// Deletes snapshot `id`; one that is already gone is not an error.
+pub fn remove_snapshot(dir: &Path, id: &str) -> CommandResult<()> {
+    let path = snapshot_path(dir, id)?;
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(io_failed("remove", &path, err)),
+    }
+}
+
+pub fn read_snapshot(dir: &Path, id: &str) -> CommandResult<Value> {
+    let path = snapshot_path(dir, id)?;
+    let bytes = fs::read(&path).map_err(|err| match err.kind() {
+        ErrorKind::NotFound => CommandErrors::not_found("snapshot_not_found"),
+        _ => io_failed("read", &path, err),
+    })?;
+    serde_json::from_slice(&bytes).map_err(|e| CommandErrors::vault_corrupted(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshots_rotate_and_round_trip() {
+        let dir = std::env::temp_dir().join(format!("buckyos-snapshot-{}", Ulid::new()));
+
+        let mut written = Vec::new();
+        for n in 0..SNAPSHOT_LIMIT + 2 {
+            written.push(write_snapshot(&dir, &json!({ "version": 2, "n": n })).unwrap());
+        }
+
+        let listed = list_snapshots(&dir).unwrap();
+        assert_eq!(listed.len(), SNAPSHOT_LIMIT);
+        assert_eq!(listed[0].id, written.last().unwrap().id);
+        assert_eq!(
+            read_snapshot(&dir, &listed[0].id).unwrap()["n"],
+            SNAPSHOT_LIMIT + 1
+        );
+        assert!(matches!(
+            read_snapshot(&dir, &written[0].id),
+            Err(CommandErrors::NotFound(_))
+        ));
+        assert!(read_snapshot(&dir, "../wallet.store").is_err());
+
+        remove_snapshot(&dir, &listed[0].id).unwrap();
+        remove_snapshot(&dir, &listed[0].id).unwrap();
+        assert_eq!(list_snapshots(&dir).unwrap().len(), SNAPSHOT_LIMIT - 1);
+
+        let store_path = dir.join("wallet.store");
+        write_atomic(&store_path, b"{}").unwrap();
+        write_atomic(&store_path, b"{\"vault\":null}").unwrap();
+        assert_eq!(fs::read(&store_path).unwrap(), b"{\"vault\":null}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,54 +1,127 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::io::ErrorKind;
-use tauri::{AppHandle, Wry};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, Wry};
 use tauri_plugin_store::{Error as StoreError, Store, StoreExt};
 use ulid::Ulid;
 
-use super::domain::{BtcAddress, DidInfo, SnStatusInfo, WalletCollection};
+use super::attempts::PasswordAttempts;
+use super::backend::{FileBackend, VaultBackend, STORE_FILE};
+use super::domain::{
+    BtcAddress, BtcNetwork, ChainAddress, DidInfo, MnemonicLanguage, SnStatusInfo, WalletCollection,
+};
+use super::metadata::{SealedMetadata, VaultKey};
+use super::migration::{migrate, rewrites_records, VAULT_VERSION};
+use super::password_policy::PasswordPolicy;
+use super::snapshot::VaultSnapshot;
+use super::verification::BackupStatus;
+use crate::config::password_policy;
 use crate::error::{CommandErrors, CommandResult};
 
-// 固定使用主网，后续可以抽象为配置项。
-pub const NETWORK: bitcoin::Network = bitcoin::Network::Bitcoin;
 pub const STORE_KEY: &str = "vault";
 const DID_PREFIX: &str = "did:bk:1:";
-const VAULT_VERSION: u32 = 1;
 
 pub fn new_did_id() -> String {
     format!("{}{}", DID_PREFIX, Ulid::new())
 }
 
+/// Key derivation used to turn the vault password into the AES-256-GCM key.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+pub enum KdfParams {
+    Pbkdf2Sha256 {
+        iterations: u32,
+    },
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl KdfParams {
+    /// Parameters used for every newly written seed.
+    pub const fn recommended() -> Self {
+        Self::Argon2id {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EncryptedSeed {
-    pub kdf_iter: u32,
+    pub kdf: KdfParams,
     pub kdf_salt_hex: String,
     pub cipher_nonce_hex: String,
     pub cipher_hex: String,
 }
 
+impl EncryptedSeed {
+    /// Whether the seed should be re-wrapped with [`KdfParams::recommended`].
+    pub fn needs_kdf_upgrade(&self) -> bool {
+        self.kdf != KdfParams::recommended()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StoredDid {
     pub id: String,
     pub nickname: String,
-    pub seed: EncryptedSeed,
+    /// 观察钱包（watch-only）没有 seed，只保存公钥与地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<EncryptedSeed>,
+    /// 旧数据没有该字段，均为 English 词表
+    #[serde(default)]
+    pub mnemonic_language: MnemonicLanguage,
+    /// BTC 地址所属网络，决定 coin_type 与地址前缀；旧数据均为主网
+    #[serde(default)]
+    pub btc_network: BtcNetwork,
     #[serde(default)]
     pub wallets: WalletCollection,
     #[serde(default)]
     pub sn_status: Option<SnStatusInfo>,
+    /// 连续输错密码的次数，解锁成功后清零
+    #[serde(default)]
+    pub password_attempts: PasswordAttempts,
+    /// 连续输错达到该次数后删除此 DID；`None` 表示只退避不删除
+    #[serde(default)]
+    pub wipe_after_failures: Option<u32>,
+    /// 助记词备份的确认进度；缺省表示该记录早于此字段
+    #[serde(default)]
+    pub backup_status: BackupStatus,
 }
 
 impl StoredDid {
+    pub fn is_watch_only(&self) -> bool {
+        self.seed.is_none()
+    }
+
+    /// DID of Bucky identity 0, used to recognise the same seed across vaults.
+    pub fn primary_bucky_did(&self) -> Option<&str> {
+        self.wallets
+            .bucky
+            .entries
+            .first()
+            .map(|entry| entry.did.as_str())
+    }
+
+    pub fn owns_bucky_did(&self, did: &str) -> bool {
+        self.wallets
+            .bucky
+            .entries
+            .iter()
+            .any(|entry| entry.did == did)
+    }
+
     pub fn to_info(&self) -> DidInfo {
-        let mut btc_addresses: Vec<BtcAddress> = self
-            .wallets
-            .btc
-            .values()
-            .flat_map(|series| series.entries.clone())
-            .collect();
-        btc_addresses
-            .sort_by(|a, b| (a.address_type as u8, a.index).cmp(&(b.address_type as u8, b.index)));
-
-        let mut eth_addresses = self.wallets.eth.entries.clone();
-        eth_addresses.sort_by_key(|entry| entry.index);
+        let mut btc_addresses: Vec<BtcAddress> = self.wallets.btc_addresses().cloned().collect();
+        btc_addresses.sort_by_key(BtcAddress::sort_key);
+
+        let mut eth_addresses: Vec<ChainAddress> = self.wallets.eth_addresses().cloned().collect();
+        eth_addresses.sort_by_key(ChainAddress::sort_key);
 
         let mut bucky_wallets = self.wallets.bucky.entries.clone();
         bucky_wallets.sort_by_key(|entry| entry.index);
@@ -59,56 +132,727 @@ impl StoredDid {
             btc_addresses,
             eth_addresses,
             bucky_wallets,
+            mnemonic_language: self.mnemonic_language,
+            btc_network: self.btc_network,
             sn_status: self.sn_status.clone(),
+            failed_password_attempts: self.password_attempts.failed,
+            wipe_after_failures: self.wipe_after_failures,
+            backup_status: self.backup_status.clone(),
+            watch_only: self.is_watch_only(),
+            redacted: false,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// What `list_dids` shows for a DID of a locked vault: only its id.
+fn redacted_info(id: &str) -> DidInfo {
+    DidInfo {
+        id: id.to_string(),
+        nickname: String::new(),
+        btc_addresses: Vec::new(),
+        eth_addresses: Vec::new(),
+        bucky_wallets: Vec::new(),
+        mnemonic_language: MnemonicLanguage::default(),
+        btc_network: BtcNetwork::default(),
+        sn_status: None,
+        failed_password_attempts: 0,
+        wipe_after_failures: None,
+        backup_status: BackupStatus::default(),
+        watch_only: false,
+        redacted: true,
+    }
+}
+
+/// Earlier copies of the vault, migration backups and snapshots, to delete
+/// once the running transaction is saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StaleHistory {
+    /// Copies still holding a wiped DID.
+    Did(String),
+    /// Copies written before metadata encryption was turned on.
+    Plaintext,
+}
+
+impl StaleHistory {
+    fn matches(&self, raw: &Value) -> bool {
+        match self {
+            StaleHistory::Did(did_id) => {
+                // 明文记录与密封后的索引都带有 DID id
+                let holds = |entries: Option<&Value>| {
+                    entries
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .any(|entry| entry["id"] == did_id.as_str())
+                };
+                holds(raw.get("dids")) || holds(raw.pointer("/sealed/index"))
+            }
+            StaleHistory::Plaintext => raw.get("sealed").is_none_or(Value::is_null),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VaultStore {
     version: u32,
     pub active_did: Option<String>,
     #[serde(default)]
     pub dids: Vec<StoredDid>,
+    /// 启用元数据加密后，落盘的 `dids` 为空，完整记录密封在这里
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed: Option<SealedMetadata>,
+    /// 打开 `sealed` 所用 key 的副本，保存时重新密封；`VaultHandle::set_vault_key`
+    /// 丢弃缓存的 vault 时一并清除
+    #[serde(skip)]
+    vault_key: Option<VaultKey>,
+    #[serde(skip)]
+    keep_on_error: bool,
+    #[serde(skip)]
+    stale_history: Vec<StaleHistory>,
+}
+
+impl Default for VaultStore {
+    fn default() -> Self {
+        Self {
+            version: VAULT_VERSION,
+            active_did: None,
+            dids: Vec::new(),
+            sealed: None,
+            vault_key: None,
+            keep_on_error: false,
+            stale_history: Vec::new(),
+        }
+    }
 }
 
 impl VaultStore {
-    pub fn ensure_version(mut self) -> Self {
-        self.version = VAULT_VERSION;
+    /// Saves the changes of the running [`VaultHandle::transaction`] even
+    /// when it fails, for state that must outlive the error such as a counted
+    /// wrong password.
+    pub fn keep_on_error(&mut self) {
+        self.keep_on_error = true;
+    }
+
+    /// Deletes every migration backup and snapshot still holding `did_id`
+    /// once the running transaction is saved, so a wiped DID cannot be
+    /// recovered from them.
+    pub fn forget_did(&mut self, did_id: &str) {
+        self.stale_history
+            .push(StaleHistory::Did(did_id.to_string()));
+    }
+
+    pub fn ensure_unlocked(&self) -> CommandResult<()> {
+        if self.is_locked() {
+            return Err(CommandErrors::VaultLocked);
+        }
+        Ok(())
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.is_some()
+    }
+
+    /// Sealed and not yet opened with the vault key: `dids` is empty and only
+    /// the redacted index is known.
+    pub fn is_locked(&self) -> bool {
+        self.sealed.is_some() && self.vault_key.is_none()
+    }
+
+    /// Decrypts the sealed records with `key`. Plaintext vaults are left as is.
+    pub fn open_sealed(&mut self, key: VaultKey) -> CommandResult<()> {
+        if let Some(sealed) = &self.sealed {
+            self.dids = sealed.open(&key)?;
+            self.vault_key = Some(key);
+        }
+        Ok(())
+    }
+
+    /// Unwraps the vault key with `password` and opens the records. Returns
+    /// `None` for a plaintext vault.
+    pub fn unlock_sealed(&mut self, password: &str) -> CommandResult<Option<VaultKey>> {
+        let Some(sealed) = &self.sealed else {
+            return Ok(None);
+        };
+        let key = VaultKey::unwrap(password, &sealed.key)?;
+        self.open_sealed(key.clone())?;
+        Ok(Some(key))
+    }
+
+    /// Turns on metadata encryption under `password`, or re-wraps the existing
+    /// vault key when it is already on. Plaintext migration backups and
+    /// snapshots are deleted once the sealed vault is saved.
+    pub fn seal_with(&mut self, password: &str) -> CommandResult<VaultKey> {
+        if self.is_locked() {
+            return Err(CommandErrors::VaultLocked);
+        }
+        let key = self.vault_key.clone().unwrap_or_else(VaultKey::generate);
+        self.sealed = Some(SealedMetadata::seal(&key, key.wrap(password)?, &self.dids)?);
+        self.vault_key = Some(key.clone());
+        self.stale_history.push(StaleHistory::Plaintext);
+        Ok(key)
+    }
+
+    /// Turns metadata encryption off; the records are written in plaintext again.
+    pub fn unseal(&mut self) -> CommandResult<()> {
+        if self.is_locked() {
+            return Err(CommandErrors::VaultLocked);
+        }
+        self.sealed = None;
+        self.vault_key = None;
+        Ok(())
+    }
+
+    pub fn did_count(&self) -> usize {
+        match &self.sealed {
+            Some(sealed) if self.is_locked() => sealed.index.len(),
+            _ => self.dids.len(),
+        }
+    }
+
+    /// Every DID as [`DidInfo`]; redacted to bare ids while locked.
+    pub fn did_infos(&self) -> Vec<DidInfo> {
+        match &self.sealed {
+            Some(sealed) if self.is_locked() => sealed
+                .index
+                .iter()
+                .map(|entry| redacted_info(&entry.id))
+                .collect(),
+            _ => self.dids.iter().map(StoredDid::to_info).collect(),
+        }
+    }
+
+    pub fn did_info(&self, did_id: &str) -> Option<DidInfo> {
+        self.did_infos().into_iter().find(|info| info.id == did_id)
+    }
+
+    /// JSON written to disk: with metadata encryption on, `dids` is re-sealed
+    /// under the vault key and left empty.
+    fn stored_value(&self) -> CommandResult<Value> {
+        let mut stored = self.clone();
+        match (&mut stored.sealed, &self.vault_key) {
+            (Some(sealed), Some(key)) => {
+                *sealed = SealedMetadata::seal(key, sealed.key.clone(), &self.dids)?;
+                stored.dids.clear();
+            }
+            // 未解锁时 `dids` 为空，原样保留密文
+            (Some(_), None) if !self.dids.is_empty() => return Err(CommandErrors::VaultLocked),
+            _ => {}
+        }
+        serde_json::to_value(&stored).map_err(|e| CommandErrors::vault_corrupted(e.to_string()))
+    }
+}
+
+/// A [`VaultBackend`] plus the vault key needed to open sealed metadata and
+/// the policy new DID passwords must meet. Every read and write goes through
+/// [`VaultHandle::transaction`] and friends, which hold the handle's lock and
+/// keep the last committed vault in memory, so callers sharing a handle
+/// cannot lose each other's updates.
+pub struct VaultHandle<B> {
+    backend: B,
+    password_policy: PasswordPolicy,
+    state: Mutex<VaultState>,
+}
+
+#[derive(Default)]
+struct VaultState {
+    vault_key: Option<VaultKey>,
+    cached: Option<VaultStore>,
+}
+
+impl<B: VaultBackend> VaultHandle<B> {
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            password_policy: PasswordPolicy::default(),
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn with_password_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.password_policy = policy;
         self
     }
+
+    /// Replaces the vault key and drops the cached vault, so the next access
+    /// reads the records sealed (`None`) or opened with the new key.
+    pub fn set_vault_key(&self, key: Option<VaultKey>) {
+        let mut state = self.lock_state();
+        state.vault_key = key;
+        state.cached = None;
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn password_policy(&self) -> &PasswordPolicy {
+        &self.password_policy
+    }
+
+    /// Like [`parse_vault`], then opens sealed records with the unlocked vault
+    /// key when there is one.
+    pub fn parse(&self, raw: Value) -> CommandResult<(VaultStore, u32)> {
+        let key = self.lock_state().vault_key.clone();
+        parse_with_key(raw, key.as_ref())
+    }
+
+    /// Runs `f` on the vault and saves it when `f` succeeds and changed it.
+    /// When `f` fails the vault stays as it was, unless `f` called
+    /// [`VaultStore::keep_on_error`]. Fails with `VaultLocked` while metadata
+    /// encryption is on and the vault is locked.
+    ///
+    /// The lock is held while `f` runs. Checking a password inside `f` costs
+    /// one Argon2 derivation (a few hundred milliseconds) during which every
+    /// other vault command waits; that keeps the attempt check, the
+    /// derivation and the recorded outcome atomic. Work that needs no vault
+    /// state, such as encrypting a new seed, belongs before the transaction.
+    pub fn transaction<T>(
+        &self,
+        f: impl FnOnce(&mut VaultStore) -> CommandResult<T>,
+    ) -> CommandResult<T> {
+        self.index_transaction(|vault| {
+            vault.ensure_unlocked()?;
+            f(vault)
+        })
+    }
+
+    /// Like [`VaultHandle::transaction`], but a locked vault is passed as is
+    /// so `f` can show its redacted index or unlock it.
+    pub fn index_transaction<T>(
+        &self,
+        f: impl FnOnce(&mut VaultStore) -> CommandResult<T>,
+    ) -> CommandResult<T> {
+        let mut state = self.lock_state();
+        let committed = match state.cached.take() {
+            Some(vault) => vault,
+            None => load_vault_index(self, state.vault_key.as_ref())?,
+        };
+
+        let mut working = committed.clone();
+        let result = f(&mut working);
+        let keep_on_error = std::mem::take(&mut working.keep_on_error);
+        let stale_history = std::mem::take(&mut working.stale_history);
+        let keep = result.is_ok() || keep_on_error;
+        if !keep {
+            state.cached = Some(committed);
+            return result;
+        }
+
+        if plain_value(&working)? != plain_value(&committed)? {
+            if let Err(err) = save_vault(self, &working) {
+                state.cached = Some(committed);
+                return Err(err);
+            }
+        }
+        if !stale_history.is_empty() {
+            if let Err(err) = purge_history(self, &stale_history) {
+                log::error!("purge stale vault history failed: {err}");
+            }
+        }
+        state.cached = Some(working);
+        result
+    }
+
+    /// Reads the vault without changing it. Fails with `VaultLocked` while
+    /// the vault is locked.
+    pub fn read<T>(&self, f: impl FnOnce(&VaultStore) -> CommandResult<T>) -> CommandResult<T> {
+        self.read_index(|vault| {
+            vault.ensure_unlocked()?;
+            f(vault)
+        })
+    }
+
+    /// Like [`VaultHandle::read`], but a locked vault is passed as is.
+    pub fn read_index<T>(
+        &self,
+        f: impl FnOnce(&VaultStore) -> CommandResult<T>,
+    ) -> CommandResult<T> {
+        self.index_transaction(|vault| f(vault))
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, VaultState> {
+        self.state.lock().expect("vault lock poisoned")
+    }
+}
+
+/// In-memory JSON of `vault` with its records in plaintext, used to tell
+/// whether a transaction changed anything.
+fn plain_value(vault: &VaultStore) -> CommandResult<Value> {
+    serde_json::to_value(vault).map_err(|e| CommandErrors::vault_corrupted(e.to_string()))
+}
+
+/// The app's `tauri_plugin_store` store. The plugin only caches the map; the
+/// file is written through [`FileBackend`] so saves stay atomic.
+pub struct TauriStoreBackend {
+    store: Arc<Store<Wry>>,
+    file: FileBackend,
+}
+
+impl VaultBackend for TauriStoreBackend {
+    fn get(&self, key: &str) -> CommandResult<Option<Value>> {
+        match self.store.reload() {
+            Ok(_) => {}
+            Err(StoreError::Io(io_err)) if io_err.kind() == ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(StoreError::Deserialize(err)) => {
+                return Err(CommandErrors::vault_corrupted(err.to_string()));
+            }
+            Err(err) => return Err(CommandErrors::store_unavailable(err.to_string())),
+        }
+        Ok(self.store.get(key))
+    }
+
+    fn set(&self, entries: Vec<(String, Value)>) -> CommandResult<()> {
+        for (key, value) in entries {
+            self.store.set(key, value);
+        }
+        let map: Map<String, Value> = self.store.entries().into_iter().collect();
+        self.file.write_map(&map)
+    }
+
+    fn remove(&self, keys: &[String]) -> CommandResult<()> {
+        for key in keys {
+            self.store.delete(key);
+        }
+        let map: Map<String, Value> = self.store.entries().into_iter().collect();
+        self.file.write_map(&map)
+    }
+
+    fn write_snapshot(&self, vault: &Value) -> CommandResult<()> {
+        self.file.write_snapshot(vault)
+    }
+
+    fn list_snapshots(&self) -> CommandResult<Vec<VaultSnapshot>> {
+        self.file.list_snapshots()
+    }
+
+    fn read_snapshot(&self, id: &str) -> CommandResult<Value> {
+        self.file.read_snapshot(id)
+    }
+
+    fn remove_snapshot(&self, id: &str) -> CommandResult<()> {
+        self.file.remove_snapshot(id)
+    }
+}
+
+pub fn open_store(app_handle: &AppHandle) -> CommandResult<VaultHandle<TauriStoreBackend>> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| CommandErrors::store_unavailable(e.to_string()))?;
+    // 落盘由 save_vault 自己完成（临时文件 + fsync + rename），关闭插件的自动保存。
+    let store = app_handle
+        .store_builder(STORE_FILE)
+        .disable_auto_save()
+        .build()
+        .map_err(|e| CommandErrors::store_unavailable(e.to_string()))?;
+    let backend = TauriStoreBackend {
+        store,
+        file: FileBackend::new(data_dir),
+    };
+    Ok(VaultHandle::new(backend).with_password_policy(password_policy(app_handle)))
+}
+
+/// The app's vault, registered as Tauri managed state so every command shares
+/// one [`VaultHandle`] and with it the lock serializing vault access. The
+/// password policy is read from the app config once, at startup.
+pub struct VaultService {
+    handle: VaultHandle<TauriStoreBackend>,
+}
+
+impl VaultService {
+    pub fn open(app_handle: &AppHandle) -> CommandResult<Self> {
+        Ok(Self {
+            handle: open_store(app_handle)?,
+        })
+    }
+
+    pub fn handle(&self) -> &VaultHandle<TauriStoreBackend> {
+        &self.handle
+    }
+}
+
+/// Migrates and deserializes a raw vault. Returns the vault and the version it
+/// was written with.
+pub fn parse_vault(mut raw: Value) -> CommandResult<(VaultStore, u32)> {
+    let from_version = migrate(&mut raw)?;
+    let vault = serde_json::from_value::<VaultStore>(raw)
+        .map_err(|e| CommandErrors::vault_corrupted(e.to_string()))?;
+    Ok((vault, from_version))
+}
+
+fn parse_with_key(raw: Value, key: Option<&VaultKey>) -> CommandResult<(VaultStore, u32)> {
+    let (mut vault, from_version) = parse_vault(raw)?;
+    if let Some(key) = key {
+        vault.open_sealed(key.clone())?;
+    }
+    Ok((vault, from_version))
+}
+
+/// Reads the vault from the backend, migrating and saving it when it was
+/// written by an older version. A locked vault is returned as is.
+fn load_vault_index<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    key: Option<&VaultKey>,
+) -> CommandResult<VaultStore> {
+    let raw = match store.backend.get(STORE_KEY) {
+        Ok(Some(raw)) => raw,
+        Ok(None) => return Ok(VaultStore::default()),
+        Err(CommandErrors::VaultCorrupted(reason)) => {
+            return restore_latest_snapshot(store, key, &reason);
+        }
+        Err(err) => return Err(err),
+    };
+    let (vault, from_version) = match parse_with_key(raw.clone(), key) {
+        Ok(parsed) => parsed,
+        Err(CommandErrors::VaultCorrupted(reason)) => {
+            return restore_latest_snapshot(store, key, &reason);
+        }
+        Err(err) => return Err(err),
+    };
+
+    if from_version != VAULT_VERSION {
+        // 迁移前的原始数据保留在独立 key 中，迁移结果与备份在同一次写入中落盘。
+        // 只改版本号的迁移不留备份，免得每次升级都多一份可能是明文的副本。
+        let backups = if rewrites_records(&raw)? {
+            vec![(backup_key(from_version), raw)]
+        } else {
+            Vec::new()
+        };
+        write_vault(store, &vault, backups)?;
+        log::info!("vault migrated from v{from_version} to v{VAULT_VERSION}");
+    }
+    Ok(vault)
 }
 
-pub type AppStore = std::sync::Arc<Store<Wry>>;
+fn restore_latest_snapshot<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    key: Option<&VaultKey>,
+    reason: &str,
+) -> CommandResult<VaultStore> {
+    log::error!("vault unreadable, falling back to snapshots: {reason}");
+    for snapshot in store.backend.list_snapshots()? {
+        match store
+            .backend
+            .read_snapshot(&snapshot.id)
+            .and_then(|raw| parse_with_key(raw, key))
+        {
+            Ok((vault, _)) => {
+                log::warn!("vault restored from snapshot {}", snapshot.id);
+                save_vault(store, &vault)?;
+                return Ok(vault);
+            }
+            Err(err) => log::warn!("snapshot {} unusable: {err}", snapshot.id),
+        }
+    }
+    Err(CommandErrors::vault_corrupted(reason))
+}
 
-pub fn open_store(app_handle: &AppHandle) -> CommandResult<AppStore> {
-    app_handle
-        .store("wallet.store")
-        .map_err(|e| CommandErrors::store_unavailable(e.to_string()))
+/// Store key holding the untouched JSON of a vault before it was migrated
+/// away from `version`.
+pub fn backup_key(version: u32) -> String {
+    format!("{STORE_KEY}.v{version}.backup")
 }
 
-pub fn load_vault(store: &AppStore) -> CommandResult<VaultStore> {
-    match store.reload() {
-        Ok(_) => {}
-        Err(StoreError::Io(io_err)) if io_err.kind() == ErrorKind::NotFound => {
-            return Ok(VaultStore::default());
+/// Deletes the migration backups and snapshots matched by any of `stale`.
+fn purge_history<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    stale: &[StaleHistory],
+) -> CommandResult<()> {
+    let is_stale = |raw: &Value| stale.iter().any(|entry| entry.matches(raw));
+
+    let mut backups = Vec::new();
+    for version in 1..VAULT_VERSION {
+        let key = backup_key(version);
+        if store.backend.get(&key)?.is_some_and(|raw| is_stale(&raw)) {
+            backups.push(key);
         }
-        Err(err) => return Err(CommandErrors::store_unavailable(err.to_string())),
+    }
+    if !backups.is_empty() {
+        store.backend.remove(&backups)?;
     }
 
-    match store.get(STORE_KEY) {
-        Some(value) => serde_json::from_value::<VaultStore>(value)
-            .map(VaultStore::ensure_version)
-            .map_err(|e| CommandErrors::vault_corrupted(e.to_string())),
-        None => Ok(VaultStore::default()),
+    for snapshot in store.backend.list_snapshots()? {
+        // 读不出的快照无法确认内容，一并删除
+        let stale = match store.backend.read_snapshot(&snapshot.id) {
+            Ok(raw) => is_stale(&raw),
+            Err(_) => true,
+        };
+        if stale {
+            store.backend.remove_snapshot(&snapshot.id)?;
+        }
     }
+    Ok(())
+}
+
+fn save_vault<B: VaultBackend>(store: &VaultHandle<B>, vault: &VaultStore) -> CommandResult<()> {
+    write_vault(store, vault, Vec::new())
 }
 
-pub fn save_vault(store: &AppStore, vault: &VaultStore) -> CommandResult<()> {
-    let value =
-        serde_json::to_value(vault).map_err(|e| CommandErrors::vault_corrupted(e.to_string()))?;
-    store.set(STORE_KEY.to_string(), value);
-    store
-        .save()
-        .map_err(|e| CommandErrors::store_unavailable(e.to_string()))
+fn write_vault<B: VaultBackend>(
+    store: &VaultHandle<B>,
+    vault: &VaultStore,
+    mut entries: Vec<(String, Value)>,
+) -> CommandResult<()> {
+    let value = vault.stored_value()?;
+    entries.push((STORE_KEY.to_string(), value.clone()));
+    store.backend.set(entries)?;
+
+    if let Err(err) = store.backend.write_snapshot(&value) {
+        log::warn!("write vault snapshot failed: {err}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::backend::MemoryBackend;
+    use serde_json::json;
+
+    fn sample_did() -> StoredDid {
+        StoredDid {
+            id: "did:bk:1:01J0000000000000000000000".to_string(),
+            nickname: "alice".to_string(),
+            seed: Some(EncryptedSeed {
+                kdf: KdfParams::recommended(),
+                kdf_salt_hex: "00".to_string(),
+                cipher_nonce_hex: "00".to_string(),
+                cipher_hex: "00".to_string(),
+            }),
+            mnemonic_language: MnemonicLanguage::English,
+            btc_network: BtcNetwork::Mainnet,
+            wallets: WalletCollection::default(),
+            sn_status: None,
+            password_attempts: PasswordAttempts::default(),
+            wipe_after_failures: None,
+            backup_status: BackupStatus::default(),
+        }
+    }
+
+    #[test]
+    fn test_sealed_vault_shows_only_index_while_locked() {
+        let mut vault = VaultStore::default();
+        vault.dids.push(sample_did());
+        vault.active_did = Some(sample_did().id);
+        vault.seal_with("vault_pw").unwrap();
+
+        let stored = vault.stored_value().unwrap();
+        assert!(stored["dids"].as_array().unwrap().is_empty());
+        assert!(!stored.to_string().contains("alice"));
+
+        let (mut locked, _) = parse_vault(stored.clone()).unwrap();
+        assert!(locked.is_locked());
+        assert_eq!(locked.did_count(), 1);
+        let info = locked.did_info(&sample_did().id).unwrap();
+        assert!(info.redacted);
+        assert!(info.nickname.is_empty());
+
+        // 锁定状态下保存只会原样写回密文，写入新记录会被拒绝
+        assert_eq!(locked.stored_value().unwrap(), stored);
+        locked.dids.push(sample_did());
+        assert!(matches!(
+            locked.stored_value(),
+            Err(CommandErrors::VaultLocked)
+        ));
+        locked.dids.clear();
+
+        assert!(matches!(
+            locked.unlock_sealed("wrong"),
+            Err(CommandErrors::InvalidPassword)
+        ));
+        assert!(locked.unlock_sealed("vault_pw").unwrap().is_some());
+        assert_eq!(locked.did_infos()[0].nickname, "alice");
+
+        locked.unseal().unwrap();
+        let plain = locked.stored_value().unwrap();
+        assert!(plain.get("sealed").is_none());
+        assert_eq!(plain["dids"][0]["nickname"], "alice");
+    }
+
+    #[test]
+    fn test_migration_keeps_backup_in_backend() {
+        let store = VaultHandle::new(MemoryBackend::default());
+        assert_eq!(store.read(|vault| Ok(vault.did_count())).unwrap(), 0);
+
+        let v1 = json!({
+            "active_did": null,
+            "dids": [{
+                "id": "did:bk:1:01J0000000000000000000000",
+                "nickname": "alice",
+                "seed": {
+                    "kdf_iter": 100000,
+                    "kdf_salt_hex": "00",
+                    "cipher_nonce_hex": "00",
+                    "cipher_hex": "00"
+                }
+            }]
+        });
+        store
+            .backend()
+            .set(vec![(STORE_KEY.to_string(), v1.clone())])
+            .unwrap();
+
+        // 缓存的是写入前读到的空 vault，重新打开才能读到刚写入的 v1 数据
+        let store = VaultHandle::new(store.backend);
+        let nickname = store
+            .read(|vault| Ok(vault.dids[0].nickname.clone()))
+            .unwrap();
+        assert_eq!(nickname, "alice");
+        assert_eq!(store.backend().get(&backup_key(1)).unwrap(), Some(v1));
+        let stored = store.backend().get(STORE_KEY).unwrap().unwrap();
+        assert_eq!(stored["version"], VAULT_VERSION);
+
+        // 只改版本号的迁移不写备份
+        let backend = MemoryBackend::default();
+        let v2 = json!({ "version": 2, "active_did": null, "dids": [] });
+        backend.set(vec![(STORE_KEY.to_string(), v2)]).unwrap();
+        let store = VaultHandle::new(backend);
+        assert_eq!(store.read(|vault| Ok(vault.did_count())).unwrap(), 0);
+        assert_eq!(store.backend().get(&backup_key(2)).unwrap(), None);
+        let stored = store.backend().get(STORE_KEY).unwrap().unwrap();
+        assert_eq!(stored["version"], VAULT_VERSION);
+    }
+
+    #[test]
+    fn test_transactions_are_serialized_and_roll_back() {
+        let store = VaultHandle::new(MemoryBackend::default());
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let store = &store;
+                scope.spawn(move || {
+                    store
+                        .transaction(|vault| {
+                            let mut did = sample_did();
+                            did.id = format!("{}{i}", did.id);
+                            vault.dids.push(did);
+                            Ok(())
+                        })
+                        .unwrap();
+                });
+            }
+        });
+        let reopened = VaultHandle::new(store.backend);
+        assert_eq!(reopened.read(|vault| Ok(vault.dids.len())).unwrap(), 8);
+
+        let err = reopened.transaction(|vault| {
+            vault.dids.clear();
+            Err::<(), _>(CommandErrors::InvalidPassword)
+        });
+        assert!(err.is_err());
+        assert_eq!(reopened.read(|vault| Ok(vault.dids.len())).unwrap(), 8);
+
+        let err = reopened.transaction(|vault| {
+            vault.dids[0].password_attempts.failed = 1;
+            vault.keep_on_error();
+            Err::<(), _>(CommandErrors::InvalidPassword)
+        });
+        assert!(err.is_err());
+        let reopened = VaultHandle::new(reopened.backend);
+        let failed = reopened
+            .read(|vault| Ok(vault.dids[0].password_attempts.failed))
+            .unwrap();
+        assert_eq!(failed, 1);
+    }
 }
@@ -0,0 +1,166 @@
+use rand::seq::index::sample;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CommandErrors, CommandResult};
+
+/// Words asked for by one backup challenge.
+pub const CHALLENGE_WORDS: usize = 3;
+
+/// Whether the user proved they wrote the mnemonic down:
+/// `Unverified` → `Challenged` → `Verified`, or back to `Unverified` after a
+/// wrong answer. Re-checking a `Verified` or `Unknown` DID keeps that status
+/// until the answer, and a wrong answer returns to it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum BackupStatus {
+    /// Stored before backup status was tracked.
+    #[default]
+    Unknown,
+    Unverified,
+    /// Waiting for the words at these 1-based positions.
+    Challenged {
+        positions: Vec<usize>,
+        /// Status before a re-check, restored when the answer is wrong.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        recheck_of: Option<Box<BackupStatus>>,
+    },
+    Verified {
+        verified_at_ms: u64,
+    },
+}
+
+impl BackupStatus {
+    /// Refuses operations that bind the DID to outside services while its
+    /// mnemonic may not be backed up. Records from before tracking pass.
+    pub fn require_verified(&self) -> CommandResult<()> {
+        match self {
+            BackupStatus::Challenged {
+                recheck_of: Some(previous),
+                ..
+            } => previous.require_verified(),
+            BackupStatus::Unverified | BackupStatus::Challenged { .. } => {
+                Err(CommandErrors::BackupNotVerified)
+            }
+            BackupStatus::Unknown | BackupStatus::Verified { .. } => Ok(()),
+        }
+    }
+
+    /// Picks [`CHALLENGE_WORDS`] distinct positions of a `word_count` word
+    /// mnemonic, replacing any pending challenge.
+    pub fn issue_challenge(&mut self, word_count: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let mut positions: Vec<usize> = sample(rng, word_count, CHALLENGE_WORDS.min(word_count))
+            .into_iter()
+            .map(|index| index + 1)
+            .collect();
+        positions.sort_unstable();
+        let recheck_of = match std::mem::take(self) {
+            BackupStatus::Challenged { recheck_of, .. } => recheck_of,
+            BackupStatus::Unverified => None,
+            previous @ (BackupStatus::Unknown | BackupStatus::Verified { .. }) => {
+                Some(Box::new(previous))
+            }
+        };
+        *self = BackupStatus::Challenged {
+            positions: positions.clone(),
+            recheck_of,
+        };
+        positions
+    }
+
+    /// Checks `answers` against the challenged `words`. A wrong answer spends
+    /// the challenge, so every retry asks for other positions.
+    pub fn answer(&mut self, words: &[&str], answers: &[String], now_ms: u64) -> CommandResult<()> {
+        let BackupStatus::Challenged { positions, .. } = self else {
+            return Err(CommandErrors::not_found("backup_challenge_not_found"));
+        };
+
+        let matches = answers.len() == positions.len()
+            && positions.iter().zip(answers).all(|(position, answer)| {
+                position
+                    .checked_sub(1)
+                    .and_then(|index| words.get(index))
+                    .is_some_and(|word| answer.trim().to_lowercase() == *word)
+            });
+        if !matches {
+            *self = match std::mem::take(self) {
+                BackupStatus::Challenged {
+                    recheck_of: Some(previous),
+                    ..
+                } => *previous,
+                _ => BackupStatus::Unverified,
+            };
+            return Err(CommandErrors::BackupMismatch);
+        }
+
+        *self = BackupStatus::Verified {
+            verified_at_ms: now_ms,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    const WORDS: [&str; 12] = [
+        "legal", "winner", "thank", "year", "wave", "sausage", "worth", "useful", "legal",
+        "winner", "thank", "yellow",
+    ];
+
+    #[test]
+    fn test_challenge_round_trip() {
+        let mut status = BackupStatus::Unverified;
+        assert!(status.require_verified().is_err());
+        assert!(status.answer(&WORDS, &[], 1).is_err());
+
+        let positions = status.issue_challenge(WORDS.len(), &mut OsRng);
+        assert_eq!(positions.len(), CHALLENGE_WORDS);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(positions.iter().all(|p| (1..=12).contains(p)));
+        assert!(status.require_verified().is_err());
+
+        let wrong = vec!["abandon".to_string(); CHALLENGE_WORDS];
+        assert!(matches!(
+            status.answer(&WORDS, &wrong, 1),
+            Err(CommandErrors::BackupMismatch)
+        ));
+        assert_eq!(status, BackupStatus::Unverified);
+
+        status.issue_challenge(WORDS.len(), &mut OsRng);
+        let BackupStatus::Challenged { positions, .. } = status.clone() else {
+            panic!("challenge expected");
+        };
+        let answers: Vec<String> = positions
+            .iter()
+            .map(|p| format!(" {} ", WORDS[p - 1].to_uppercase()))
+            .collect();
+        status.answer(&WORDS, &answers, 42).unwrap();
+        assert_eq!(status, BackupStatus::Verified { verified_at_ms: 42 });
+        assert!(status.require_verified().is_ok());
+        assert!(BackupStatus::Unknown.require_verified().is_ok());
+    }
+
+    #[test]
+    fn test_failed_recheck_keeps_verified_status() {
+        for previous in [
+            BackupStatus::Verified { verified_at_ms: 7 },
+            BackupStatus::Unknown,
+        ] {
+            let mut status = previous.clone();
+            status.issue_challenge(WORDS.len(), &mut OsRng);
+            assert!(status.require_verified().is_ok());
+            // 重复签发挑战不会丢失原状态
+            status.issue_challenge(WORDS.len(), &mut OsRng);
+
+            let wrong = vec!["abandon".to_string(); CHALLENGE_WORDS];
+            assert!(matches!(
+                status.answer(&WORDS, &wrong, 1),
+                Err(CommandErrors::BackupMismatch)
+            ));
+            assert_eq!(status, previous);
+        }
+    }
+}
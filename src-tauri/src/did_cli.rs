@@ -1,24 +1,33 @@
 use anyhow::{anyhow, Context, Result};
 use bip39::{Language, Mnemonic};
-use bitcoin::bip32::{DerivationPath, Fingerprint, Xpriv, Xpub};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Fingerprint, Xpriv, Xpub};
 use bitcoin::key::Secp256k1;
-use bitcoin::{Address, CompressedPublicKey, Network, PubkeyHash};
+use bitcoin::{Address, Network, PublicKey};
 use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use sha3::{Digest, Keccak256};
-// 加密相关依赖：PBKDF2 + AES-256-GCM
-use aes_gcm::{aead::Aead, aead::KeyInit, Aes256Gcm, Nonce};
-use pbkdf2::pbkdf2_hmac;
-use rand::{rngs::OsRng, RngCore};
-use sha2::Sha256;
-use std::fs;
 use std::path::PathBuf;
 
+// 助记词的加密与持久化与 App 共用 did 模块，保险库文件格式一致
+use buckyosapp_lib::did::backend::FileBackend;
+use buckyosapp_lib::did::domain::BtcNetwork;
+use buckyosapp_lib::did::mnemonic::parse_mnemonic;
+use buckyosapp_lib::did::password_policy::WeakPassword;
+use buckyosapp_lib::did::slip39::combine_mnemonics;
+use buckyosapp_lib::did::store::VaultHandle;
+use buckyosapp_lib::did::{
+    create_did_in, generate_mnemonic, import_did_in, reveal_mnemonic_in, split_mnemonic_shares_in,
+    SessionManager,
+};
+use buckyosapp_lib::error::CommandErrors;
+
 // =========================
 // 程序目标：
 // 1) 创建 BTC + ETH 账户：生成助记词，要求输入密码，使用密码加密保存助记词。
 // 2) 通过助记词导入 BTC + ETH 账户：同样要求密码，加密保存助记词。
 // 3) 通过密码解密并导出助记词。
+// 存储：经 FileBackend 写入与 App 相同布局的保险库（wallet.store + vault-snapshots），
+// 每次创建/导入都在保险库中新增一个 DID。
 // 封装性：BTC/ETH 派生逻辑分别在不同结构体中。中文注释说明每步的作用。
 // =========================
 
@@ -54,13 +63,13 @@ enum NetArg {
     Signet,
 }
 
-impl From<NetArg> for Network {
+impl From<NetArg> for BtcNetwork {
     fn from(value: NetArg) -> Self {
         match value {
-            NetArg::Mainnet => Network::Bitcoin,
-            NetArg::Testnet => Network::Testnet,
-            NetArg::Regtest => Network::Regtest,
-            NetArg::Signet => Network::Signet,
+            NetArg::Mainnet => BtcNetwork::Mainnet,
+            NetArg::Testnet => BtcNetwork::Testnet,
+            NetArg::Regtest => BtcNetwork::Regtest,
+            NetArg::Signet => BtcNetwork::Signet,
         }
     }
 }
@@ -71,9 +80,9 @@ impl From<NetArg> for Network {
 #[command(about = "创建/导入 BTC+ETH 钱包（助记词加密存储），并可用密码导出助记词")]
 #[command(author, version)]
 struct Cli {
-    /// 钱包文件路径（用于保存加密后的助记词等元数据）
-    #[arg(long, default_value = "wallet.json")]
-    wallet: PathBuf,
+    /// 保险库数据目录，布局与 App 数据目录相同，可直接指向 App 的数据目录
+    #[arg(long, default_value = "wallet-data")]
+    data_dir: PathBuf,
 
     #[command(subcommand)]
     command: Command,
@@ -87,6 +96,8 @@ enum Command {
     Import(ImportArgs),
     /// 使用密码解密并导出助记词
     ExportMnemonic(ExportArgs),
+    /// 将助记词拆分为 SLIP-39 M-of-N 分片（可用 import --share 恢复）
+    SplitShares(SplitSharesArgs),
 }
 
 #[derive(Args, Debug)]
@@ -96,6 +107,10 @@ struct CreateArgs {
     #[arg(long)]
     password: String,
 
+    /// DID 昵称，在保险库内唯一
+    #[arg(long)]
+    nickname: String,
+
     /// BTC 网络：mainnet / testnet / regtest / signet
     #[arg(long, value_enum, default_value = "mainnet")]
     network: NetArg,
@@ -120,7 +135,7 @@ struct CreateArgs {
     #[arg(long, default_value_t = 0)]
     index: u32,
 
-    /// 助记词语言（此处仅启用 english）
+    /// 助记词语言：english / chinese-simplified / chinese-traditional 等
     #[arg(long, default_value = "english")]
     language: String,
 
@@ -135,10 +150,6 @@ struct CreateArgs {
     /// BIP39 口令（可选），用于加盐生成种子
     #[arg(long, default_value = "")]
     passphrase: String,
-
-    /// 若钱包文件已存在，允许覆盖
-    #[arg(long, default_value_t = false)]
-    force: bool,
 }
 
 #[derive(Args, Debug)]
@@ -147,9 +158,21 @@ struct ImportArgs {
     #[arg(long)]
     password: String,
 
-    /// 导入的助记词（当前词表仅支持 english）
+    /// DID 昵称，在保险库内唯一
     #[arg(long)]
-    mnemonic: String,
+    nickname: String,
+
+    /// 导入的助记词（自动识别词表语言）
+    #[arg(long, required_unless_present = "share")]
+    mnemonic: Option<String>,
+
+    /// SLIP-39 分片助记词，可重复传入，与 mnemonic 二选一
+    #[arg(long, conflicts_with = "mnemonic")]
+    share: Vec<String>,
+
+    /// 由分片恢复时使用的助记词语言（分片中不含语言信息）
+    #[arg(long, default_value = "english", conflicts_with = "mnemonic")]
+    language: String,
 
     /// BTC 网络：mainnet / testnet / regtest / signet
     #[arg(long, value_enum, default_value = "mainnet")]
@@ -185,11 +208,35 @@ struct ExportArgs {
     /// 用于解密钱包的密码
     #[arg(long)]
     password: String,
+
+    /// 要导出的 DID id，缺省为当前激活的 DID
+    #[arg(long)]
+    did_id: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct SplitSharesArgs {
+    /// 用于解密钱包的密码
+    #[arg(long)]
+    password: String,
+
+    /// 要拆分的 DID id，缺省为当前激活的 DID
+    #[arg(long)]
+    did_id: Option<String>,
+
+    /// 恢复所需的最少分片数 M
+    #[arg(long)]
+    threshold: u8,
+
+    /// 生成的分片总数 N（最多 16）
+    #[arg(long)]
+    shares: u8,
 }
 
 #[derive(Serialize)]
 // 统一输出：同时包含 BTC 与 ETH 的地址与关键元信息
 struct WalletOut {
+    did_id: String,       // 保险库中的 DID id
     network: String,      // 所选网络（仅影响 BTC）
     kind: String,         // BTC 地址族标识，例如 b84
     purpose: u32,         // purpose（44/49/84/86）
@@ -206,24 +253,23 @@ struct WalletOut {
     account_xprv: Option<String>, //（可选）账户扩展私钥（敏感）
 }
 
-fn address_path(change: u32, index: u32) -> DerivationPath {
-    let path = format!("{}/{}", change, index);
-    path.parse().expect("static path")
+// 相对路径 change/index；bitcoin 0.31 只解析以 m 开头的路径，这里直接拼子节点
+fn address_path(change: u32, index: u32) -> Result<DerivationPath> {
+    Ok(DerivationPath::from(vec![
+        ChildNumber::from_normal_idx(change)?,
+        ChildNumber::from_normal_idx(index)?,
+    ]))
 }
 
 // ---------- 种子/主密钥上下文：多链共享 ----------
 struct SeedCtx {
-    network: Network,
     secp: Secp256k1<bitcoin::secp256k1::All>,
-    seed_bytes: [u8; 64],
     master_xprv: Xpriv,
-    master_xpub: Xpub,
     fpr: Fingerprint,
-    mnemonic: Mnemonic,
 }
 
 impl SeedCtx {
-    fn new(network: Network, mnemonic: Mnemonic, passphrase: &str) -> Result<Self> {
+    fn new(network: Network, mnemonic: &Mnemonic, passphrase: &str) -> Result<Self> {
         // 通过助记词 + 口令 生成 64 字节种子
         let seed_bytes = mnemonic.to_seed(passphrase);
         let secp = Secp256k1::new();
@@ -232,13 +278,9 @@ impl SeedCtx {
         let master_xpub = Xpub::from_priv(&secp, &master_xprv);
         let fpr = master_xpub.fingerprint();
         Ok(Self {
-            network,
             secp,
-            seed_bytes,
             master_xprv,
-            master_xpub,
             fpr,
-            mnemonic,
         })
     }
 }
@@ -296,19 +338,16 @@ impl BtcWallet {
         index: u32,
     ) -> Result<Address> {
         // 地址级别：change / index
-        let addr_path = address_path(change, index);
+        let addr_path = address_path(change, index)?;
         let child_prv = account_xprv.derive_priv(&ctx.secp, &addr_path)?;
         let child_pub = Xpub::from_priv(&ctx.secp, &child_prv);
         let secp_pk = child_pub.public_key;
-        let cpk = CompressedPublicKey(secp_pk);
+        let pubkey = PublicKey::new(secp_pk);
         let addr = match self.kind {
-            AddrKind::B44 => {
-                // 传统 p2pkh
-                let pkh: PubkeyHash = PubkeyHash::from(&cpk);
-                Address::p2pkh(pkh, self.network)
-            }
-            AddrKind::B49 => Address::p2shwpkh(&cpk, self.network),
-            AddrKind::B84 => Address::p2wpkh(&cpk, self.network),
+            // 传统 p2pkh
+            AddrKind::B44 => Address::p2pkh(&pubkey, self.network),
+            AddrKind::B49 => Address::p2shwpkh(&pubkey, self.network)?,
+            AddrKind::B84 => Address::p2wpkh(&pubkey, self.network)?,
             AddrKind::B86 => {
                 // Taproot p2tr
                 let (xonly, _parity) = secp_pk.x_only_public_key();
@@ -378,7 +417,7 @@ impl EthWallet {
         // BIP44 路径：m / 44' / 60' / account' / change / index
         let acc_path = Self::account_path(account);
         let acc_xprv = ctx.master_xprv.derive_priv(&ctx.secp, &acc_path)?;
-        let addr_xprv = acc_xprv.derive_priv(&ctx.secp, &address_path(change, index))?;
+        let addr_xprv = acc_xprv.derive_priv(&ctx.secp, &address_path(change, index)?)?;
         let xpub = Xpub::from_priv(&ctx.secp, &addr_xprv);
         let secp_pk = xpub.public_key;
         // ETH 地址：对未压缩公钥（去掉 0x04 前缀）做 Keccak-256，取后 20 字节
@@ -390,14 +429,20 @@ impl EthWallet {
     }
 }
 
-// 将 CLI 语言字符串解析为 bip39::Language（此处仅支持 English）
+// 将 CLI 语言字符串解析为 bip39::Language，支持的语言与 App 的 MnemonicLanguage 相同
 fn parse_language(s: &str) -> Result<Language> {
-    match s.to_ascii_lowercase().as_str() {
+    match s.to_ascii_lowercase().replace('_', "-").as_str() {
         "english" | "en" => Ok(Language::English),
-        other => Err(anyhow!(format!(
-            "Unsupported language: {} (only 'english' is enabled)",
-            other
-        ))),
+        "chinese-simplified" | "zh-cn" | "zh-hans" => Ok(Language::SimplifiedChinese),
+        "chinese-traditional" | "zh-tw" | "zh-hant" => Ok(Language::TraditionalChinese),
+        "czech" | "cs" => Ok(Language::Czech),
+        "french" | "fr" => Ok(Language::French),
+        "italian" | "it" => Ok(Language::Italian),
+        "japanese" | "ja" => Ok(Language::Japanese),
+        "korean" | "ko" => Ok(Language::Korean),
+        "portuguese" | "pt" => Ok(Language::Portuguese),
+        "spanish" | "es" => Ok(Language::Spanish),
+        other => Err(anyhow!(format!("Unsupported language: {}", other))),
     }
 }
 
@@ -414,100 +459,29 @@ impl BtcWallet {
     }
 }
 
-// ======== 简单的加密封装（PBKDF2 + AES-256-GCM） ========
-#[derive(Serialize, Deserialize)]
-struct WalletFile {
-    version: u32,
-    network: String,
-    // KDF 参数
-    kdf_iter: u32,
-    kdf_salt_hex: String,
-    // 对称加密：AES-256-GCM
-    cipher_nonce_hex: String,
-    cipher_hex: String,
-}
-
-fn kdf(password: &str, salt: &[u8], iter: u32) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iter, &mut key);
-    key
-}
-
-fn encrypt_mnemonic(password: &str, mnemonic: &Mnemonic) -> Result<WalletFile> {
-    // 生成随机盐和随机 nonce
-    let mut salt = [0u8; 16];
-    OsRng.fill_bytes(&mut salt);
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-
-    let iter = 100_000u32; // PBKDF2 迭代次数
-    let key = kdf(password, &salt, iter);
-    let cipher = Aes256Gcm::new_from_slice(&key).expect("aes key");
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
-    let plaintext = mnemonic.to_string();
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext.as_bytes())
-        .map_err(|e| anyhow!("encrypt failed: {e}"))?;
-
-    let wf = WalletFile {
-        version: 1,
-        network: String::new(), // 调用方填充
-        kdf_iter: iter,
-        kdf_salt_hex: hex::encode(salt),
-        cipher_nonce_hex: hex::encode(nonce_bytes),
-        cipher_hex: hex::encode(ciphertext),
-    };
-    Ok(wf)
+// ======== 命令实现 ========
+fn open_vault(data_dir: &PathBuf) -> VaultHandle<FileBackend> {
+    VaultHandle::new(FileBackend::new(data_dir))
 }
 
-fn decrypt_mnemonic(password: &str, wf: &WalletFile) -> Result<String> {
-    let salt = hex::decode(&wf.kdf_salt_hex).context("bad salt hex")?;
-    let nonce_bytes = hex::decode(&wf.cipher_nonce_hex).context("bad nonce hex")?;
-    let ciphertext = hex::decode(&wf.cipher_hex).context("bad cipher hex")?;
-    let key = kdf(password, &salt, wf.kdf_iter);
-    let cipher = Aes256Gcm::new_from_slice(&key).expect("aes key");
-    let nonce = Nonce::from_slice(&nonce_bytes);
-    let plain = cipher
-        .decrypt(nonce, ciphertext.as_ref())
-        .map_err(|_| anyhow!("password incorrect or data corrupted"))?;
-    Ok(String::from_utf8(plain).context("utf8 error")?)
+fn mnemonic_words(mnemonic: &Mnemonic) -> Vec<String> {
+    mnemonic.words().map(String::from).collect()
 }
 
-// ======== 命令实现 ========
-fn cmd_create(wallet_path: &PathBuf, args: CreateArgs) -> Result<()> {
-    if wallet_path.exists() && !args.force {
-        return Err(anyhow!(
-            "钱包文件已存在：{}（可加 --force 覆盖）",
-            wallet_path.display()
-        ));
-    }
-
-    let network: Network = args.network.into();
+fn cmd_create(data_dir: &PathBuf, args: CreateArgs) -> Result<()> {
+    // 先按 App 的密码策略校验，弱密码不生成助记词
+    let store = open_vault(data_dir);
+    store.password_policy().check(&args.password)?;
+    let btc_network: BtcNetwork = args.network.into();
+    let network: Network = btc_network.into();
     let language = parse_language(&args.language)?;
 
-    // 1) 生成助记词
-    let wcount: usize = if let Some(words) = args.words {
-        match words {
-            12 | 15 | 18 | 21 | 24 => words,
-            _ => return Err(anyhow!("words 必须为 12/15/18/21/24")),
-        }
-    } else if let Some(bits) = args.strength {
-        match bits {
-            128 => 12,
-            160 => 15,
-            192 => 18,
-            224 => 21,
-            256 => 24,
-            _ => return Err(anyhow!("strength 必须为 128/160/192/224/256")),
-        }
-    } else {
-        12
-    };
-    let mnemonic = Mnemonic::generate_in(language, wcount)?;
+    // 1) 生成助记词（单词数/熵强度的校验与 App 相同）
+    let words = generate_mnemonic(Some(language.into()), args.words, args.strength)?;
+    let mnemonic = Mnemonic::parse_in(language, words.join(" "))?;
 
     // 2) 构建种子上下文并派生地址
-    let ctx = SeedCtx::new(network, mnemonic.clone(), &args.passphrase)?;
+    let ctx = SeedCtx::new(network, &mnemonic, &args.passphrase)?;
     let coin_type = BtcWallet::default_coin_type(network, args.coin_type);
     let btc = BtcWallet::new(network, args.kind, coin_type);
     let purpose = btc.purpose();
@@ -516,17 +490,22 @@ fn cmd_create(wallet_path: &PathBuf, args: CreateArgs) -> Result<()> {
     let eth = EthWallet::new();
     let eth_address = eth.derive_address(&ctx, args.account, args.change, args.index)?;
 
-    // 3) 使用密码加密助记词并保存钱包文件
-    let mut wf = encrypt_mnemonic(&args.password, &mnemonic)?;
-    wf.network = network.to_string();
-    let json = serde_json::to_string_pretty(&wf)?;
-    fs::write(wallet_path, json)
-        .with_context(|| format!("写入钱包文件失败：{}", wallet_path.display()))?;
+    // 3) 使用密码加密助记词并写入保险库
+    let did = create_did_in(
+        &store,
+        args.nickname,
+        args.password,
+        mnemonic_words(&mnemonic),
+        Some(args.passphrase),
+        Some(language.into()),
+        Some(btc_network),
+    )?;
 
     // 4) 打印结果（不泄露密码与密文）
     let out = WalletOut {
+        did_id: did.id,
         network: network.to_string(),
-        kind: format!("b{}", purpose),
+        kind: btc.kind_tag(),
         purpose,
         path_account: acc_path.to_string(),
         path_addr: format!("{}/{}", args.change, args.index),
@@ -542,20 +521,18 @@ fn cmd_create(wallet_path: &PathBuf, args: CreateArgs) -> Result<()> {
     Ok(())
 }
 
-fn cmd_import(wallet_path: &PathBuf, args: ImportArgs) -> Result<()> {
-    if wallet_path.exists() {
-        // 为安全起见，此命令不覆盖现有文件（可用 create --force）
-        return Err(anyhow!(
-            "钱包文件已存在：{}（请删除或使用 create --force 覆盖）",
-            wallet_path.display()
-        ));
-    }
-    let network: Network = args.network.into();
-    // 仅 English 词表
-    let mnemonic = Mnemonic::parse_in(Language::English, &args.mnemonic)
-        .context("导入助记词解析失败（当前仅启用 English 词表）")?;
+fn cmd_import(data_dir: &PathBuf, args: ImportArgs) -> Result<()> {
+    let store = open_vault(data_dir);
+    store.password_policy().check(&args.password)?;
+    let btc_network: BtcNetwork = args.network.into();
+    let network: Network = btc_network.into();
+    // 传入分片时先用 SLIP-39 恢复熵，再按 --language 还原助记词
+    let mnemonic = match &args.mnemonic {
+        Some(phrase) => parse_mnemonic(phrase).context("导入助记词解析失败")?,
+        None => mnemonic_from_shares(&args.share, b"", parse_language(&args.language)?)?,
+    };
 
-    let ctx = SeedCtx::new(network, mnemonic.clone(), &args.passphrase)?;
+    let ctx = SeedCtx::new(network, &mnemonic, &args.passphrase)?;
     let coin_type = BtcWallet::default_coin_type(network, args.coin_type);
     let btc = BtcWallet::new(network, args.kind, coin_type);
     let purpose = btc.purpose();
@@ -564,15 +541,20 @@ fn cmd_import(wallet_path: &PathBuf, args: ImportArgs) -> Result<()> {
     let eth = EthWallet::new();
     let eth_address = eth.derive_address(&ctx, args.account, args.change, args.index)?;
 
-    let mut wf = encrypt_mnemonic(&args.password, &mnemonic)?;
-    wf.network = network.to_string();
-    let json = serde_json::to_string_pretty(&wf)?;
-    fs::write(wallet_path, json)
-        .with_context(|| format!("写入钱包文件失败：{}", wallet_path.display()))?;
+    let did = import_did_in(
+        &store,
+        args.nickname,
+        args.password,
+        mnemonic_words(&mnemonic),
+        Some(args.passphrase),
+        Some(btc_network),
+        None,
+    )?;
 
     let out = WalletOut {
+        did_id: did.id,
         network: network.to_string(),
-        kind: format!("b{}", purpose),
+        kind: btc.kind_tag(),
         purpose,
         path_account: acc_path.to_string(),
         path_addr: format!("{}/{}", args.change, args.index),
@@ -588,27 +570,187 @@ fn cmd_import(wallet_path: &PathBuf, args: ImportArgs) -> Result<()> {
     Ok(())
 }
 
-fn cmd_export_mnemonic(wallet_path: &PathBuf, args: ExportArgs) -> Result<()> {
-    let data = fs::read_to_string(wallet_path)
-        .with_context(|| format!("读取钱包文件失败：{}", wallet_path.display()))?;
-    let wf: WalletFile = serde_json::from_str(&data).context("钱包文件格式错误")?;
-    let mnemonic_str = decrypt_mnemonic(&args.password, &wf)?;
-    println!("{}", mnemonic_str);
+// App 拆分的分片不带 SLIP-39 口令，`passphrase` 仅供测试使用官方向量
+fn mnemonic_from_shares(
+    shares: &[String],
+    passphrase: &[u8],
+    language: Language,
+) -> Result<Mnemonic> {
+    let entropy = combine_mnemonics(shares, passphrase).context("分片恢复失败")?;
+    Ok(Mnemonic::from_entropy_in(language, &entropy)?)
+}
+
+fn cmd_export_mnemonic(data_dir: &PathBuf, args: ExportArgs) -> Result<()> {
+    let words = reveal_mnemonic_in(
+        &open_vault(data_dir),
+        &SessionManager::default(),
+        Some(args.password),
+        args.did_id,
+    )?;
+    println!("{}", words.join(" "));
+    Ok(())
+}
+
+fn cmd_split_shares(data_dir: &PathBuf, args: SplitSharesArgs) -> Result<()> {
+    let shares = split_mnemonic_shares_in(
+        &open_vault(data_dir),
+        &SessionManager::default(),
+        Some(args.password),
+        args.did_id,
+        args.threshold,
+        args.shares,
+    )?;
+    for share in shares {
+        println!("{}", share.join(" "));
+    }
     Ok(())
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+// 密码不满足策略时使用单独的退出码，便于脚本区分
+const EXIT_WEAK_PASSWORD: i32 = 2;
+
+fn exit_code(err: &anyhow::Error) -> i32 {
+    let weak = err.downcast_ref::<WeakPassword>().is_some()
+        || matches!(
+            err.downcast_ref::<CommandErrors>(),
+            Some(CommandErrors::WeakPassword(_))
+        );
+    if weak {
+        EXIT_WEAK_PASSWORD
+    } else {
+        1
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
     match cli.command {
-        Command::Create(args) => cmd_create(&cli.wallet, args),
-        Command::Import(args) => cmd_import(&cli.wallet, args),
-        Command::ExportMnemonic(args) => cmd_export_mnemonic(&cli.wallet, args),
+        Command::Create(args) => cmd_create(&cli.data_dir, args),
+        Command::Import(args) => cmd_import(&cli.data_dir, args),
+        Command::ExportMnemonic(args) => cmd_export_mnemonic(&cli.data_dir, args),
+        Command::SplitShares(args) => cmd_split_shares(&cli.data_dir, args),
     }
 }
 
 fn main() {
-    if let Err(e) = run() {
+    if let Err(e) = run(Cli::parse()) {
         eprintln!("Error: {e}");
-        std::process::exit(1);
+        std::process::exit(exit_code(&e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use buckyosapp_lib::did::domain::MnemonicLanguage;
+
+    fn shares(phrases: &[&str]) -> Vec<String> {
+        phrases.iter().map(|phrase| phrase.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_language_accepts_every_wordlist() {
+        for language in Language::ALL {
+            let name = serde_json::to_value(MnemonicLanguage::from(*language)).unwrap();
+            assert_eq!(parse_language(name.as_str().unwrap()).unwrap(), *language);
+        }
+        assert_eq!(
+            parse_language("chinese-traditional").unwrap(),
+            Language::TraditionalChinese
+        );
+        assert_eq!(
+            parse_language("zh-CN").unwrap(),
+            Language::SimplifiedChinese
+        );
+        assert!(parse_language("klingon").is_err());
+    }
+
+    #[test]
+    fn test_import_from_official_slip39_vectors() {
+        // 与 did/slip39.rs 相同的 SLIP-0039 官方向量，口令为 "TREZOR"
+        let two_of_three = shares(&[
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ]);
+        let mnemonic = mnemonic_from_shares(&two_of_three, b"TREZOR", Language::English).unwrap();
+        assert_eq!(
+            hex::encode(mnemonic.to_entropy()),
+            "b43ceb7e57a0ea8766221624d01b0864"
+        );
+        assert!(mnemonic_from_shares(&two_of_three[..1], b"TREZOR", Language::English).is_err());
+
+        let bits_256 = shares(&["theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck"]);
+        let mnemonic = mnemonic_from_shares(&bits_256, b"TREZOR", Language::English).unwrap();
+        assert_eq!(mnemonic.word_count(), 24);
+        assert_eq!(
+            hex::encode(mnemonic.to_entropy()),
+            "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92"
+        );
+    }
+
+    #[test]
+    fn test_split_shares_restore_the_stored_mnemonic() {
+        const PHRASE: &str =
+            "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let dir = std::env::temp_dir().join(format!("did-cli-shares-{}", ulid::Ulid::new()));
+        let store = open_vault(&dir);
+        let did = import_did_in(
+            &store,
+            "cli_user".to_string(),
+            "correct-horse-battery".to_string(),
+            PHRASE.split(' ').map(String::from).collect(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let split = split_mnemonic_shares_in(
+            &store,
+            &SessionManager::default(),
+            Some("correct-horse-battery".to_string()),
+            Some(did.id),
+            2,
+            3,
+        )
+        .unwrap();
+        let picked: Vec<String> = [2, 0].iter().map(|&i| split[i].join(" ")).collect();
+        let restored = mnemonic_from_shares(&picked, b"", Language::English).unwrap();
+        assert_eq!(restored.to_string(), PHRASE);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_weak_password_exits_with_its_own_code() {
+        const PHRASE: &str =
+            "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let dir = std::env::temp_dir().join(format!("did-cli-policy-{}", ulid::Ulid::new()));
+        let import = |password: &str| {
+            let dir = dir.to_str().unwrap();
+            let cli = Cli::try_parse_from([
+                "did_cli",
+                "--data-dir",
+                dir,
+                "import",
+                "--password",
+                password,
+                "--nickname",
+                "cli_user",
+                "--mnemonic",
+                PHRASE,
+            ])
+            .unwrap();
+            run(cli)
+        };
+
+        let err = import("123456").unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_WEAK_PASSWORD);
+        assert!(!dir.exists());
+
+        import("correct-horse-battery").unwrap();
+        // 其他错误（如昵称重复）仍为 1
+        assert_eq!(exit_code(&import("correct-horse-battery").unwrap_err()), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
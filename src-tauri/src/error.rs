@@ -2,22 +2,40 @@ use serde::Serialize;
 use tauri::ipc::InvokeError;
 use thiserror::Error;
 
+use crate::did::password_policy::WeakPassword;
+use crate::did::slip39::Slip39Error;
+
 #[derive(Debug, Copy, Clone, Serialize)]
 #[repr(u32)]
 pub enum CommandErrorCode {
     NotFound = 1001,
     InvalidPassword = 1002,
+    SessionLocked = 1003,
+    VaultLocked = 1004,
+    TooManyAttempts = 1005,
+    WeakPassword = 1006,
     NicknameExists = 1010,
     MnemonicRequired = 1011,
     IdentityExists = 1012,
     CountMustBePositive = 1013,
     SignMessageRequired = 1014,
     InvalidMnemonic = 1015,
+    InvalidBackup = 1016,
+    InvalidShare = 1017,
+    BackupNotVerified = 1018,
+    BackupMismatch = 1019,
+    WatchOnly = 1020,
+    InvalidPublicKey = 1021,
+    InvalidPsbt = 1022,
+    InvalidAddress = 1023,
+    InvalidSessionTimeout = 1024,
     StoreUnavailable = 1100,
     VaultCorrupted = 1101,
+    VaultVersionUnsupported = 1102,
     CryptoFailure = 1200,
     KeyDerivationFailure = 1201,
     JwtFailure = 1300,
+    ElectrumFailure = 1400,
     Internal = 1999,
 }
 
@@ -27,6 +45,14 @@ pub enum CommandErrors {
     NotFound(String),
     #[error("Invalid password")]
     InvalidPassword,
+    #[error("Session locked")]
+    SessionLocked,
+    #[error("Vault locked")]
+    VaultLocked,
+    #[error("Too many failed password attempts, retry after {retry_after_secs}s")]
+    TooManyAttempts { retry_after_secs: u64 },
+    #[error("{0}")]
+    WeakPassword(WeakPassword),
     #[error("Nickname already exists")]
     NicknameExists,
     #[error("Mnemonic required")]
@@ -39,16 +65,38 @@ pub enum CommandErrors {
     SignMessageRequired,
     #[error("Invalid mnemonic: {0}")]
     InvalidMnemonic(String),
+    #[error("Invalid backup: {0}")]
+    InvalidBackup(String),
+    #[error("Invalid share: {0}")]
+    InvalidShare(String),
+    #[error("Mnemonic backup not verified")]
+    BackupNotVerified,
+    #[error("Mnemonic backup words do not match")]
+    BackupMismatch,
+    #[error("Watch-only DID has no seed")]
+    WatchOnly,
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("Invalid PSBT: {0}")]
+    InvalidPsbt(String),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Session timeout must be positive")]
+    InvalidSessionTimeout,
     #[error("Store unavailable: {0}")]
     StoreUnavailable(String),
     #[error("Vault data corrupted: {0}")]
     VaultCorrupted(String),
+    #[error("Vault version {0} is newer than this app supports")]
+    VaultVersionUnsupported(u32),
     #[error("Crypto failure: {0}")]
     CryptoFailure(String),
     #[error("Key derivation failure: {0}")]
     KeyDerivationFailure(String),
     #[error("JWT failure: {0}")]
     JwtFailure(String),
+    #[error("Electrum server failure: {0}")]
+    ElectrumFailure(String),
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -90,6 +138,10 @@ impl CommandErrors {
         Self::JwtFailure(message.into())
     }
 
+    pub fn electrum_failed(message: impl Into<String>) -> Self {
+        Self::ElectrumFailure(message.into())
+    }
+
     pub fn internal(message: impl Into<String>) -> Self {
         Self::Internal(message.into())
     }
@@ -97,21 +149,56 @@ impl CommandErrors {
         Self::InvalidMnemonic(message.into())
     }
 
+    pub fn invalid_backup(message: impl Into<String>) -> Self {
+        Self::InvalidBackup(message.into())
+    }
+
+    pub fn invalid_share(message: impl Into<String>) -> Self {
+        Self::InvalidShare(message.into())
+    }
+
+    pub fn invalid_public_key(message: impl Into<String>) -> Self {
+        Self::InvalidPublicKey(message.into())
+    }
+
+    pub fn invalid_psbt(message: impl Into<String>) -> Self {
+        Self::InvalidPsbt(message.into())
+    }
+
+    pub fn invalid_address(message: impl Into<String>) -> Self {
+        Self::InvalidAddress(message.into())
+    }
+
     pub fn code(&self) -> CommandErrorCode {
         match self {
             CommandErrors::NotFound(_) => CommandErrorCode::NotFound,
             CommandErrors::InvalidPassword => CommandErrorCode::InvalidPassword,
+            CommandErrors::SessionLocked => CommandErrorCode::SessionLocked,
+            CommandErrors::VaultLocked => CommandErrorCode::VaultLocked,
+            CommandErrors::TooManyAttempts { .. } => CommandErrorCode::TooManyAttempts,
+            CommandErrors::WeakPassword(_) => CommandErrorCode::WeakPassword,
             CommandErrors::NicknameExists => CommandErrorCode::NicknameExists,
             CommandErrors::MnemonicRequired => CommandErrorCode::MnemonicRequired,
             CommandErrors::IdentityExists => CommandErrorCode::IdentityExists,
             CommandErrors::CountMustBePositive => CommandErrorCode::CountMustBePositive,
             CommandErrors::SignMessageRequired => CommandErrorCode::SignMessageRequired,
             CommandErrors::InvalidMnemonic(_) => CommandErrorCode::InvalidMnemonic,
+            CommandErrors::InvalidBackup(_) => CommandErrorCode::InvalidBackup,
+            CommandErrors::InvalidShare(_) => CommandErrorCode::InvalidShare,
+            CommandErrors::BackupNotVerified => CommandErrorCode::BackupNotVerified,
+            CommandErrors::BackupMismatch => CommandErrorCode::BackupMismatch,
+            CommandErrors::WatchOnly => CommandErrorCode::WatchOnly,
+            CommandErrors::InvalidPublicKey(_) => CommandErrorCode::InvalidPublicKey,
+            CommandErrors::InvalidPsbt(_) => CommandErrorCode::InvalidPsbt,
+            CommandErrors::InvalidAddress(_) => CommandErrorCode::InvalidAddress,
+            CommandErrors::InvalidSessionTimeout => CommandErrorCode::InvalidSessionTimeout,
             CommandErrors::StoreUnavailable(_) => CommandErrorCode::StoreUnavailable,
             CommandErrors::VaultCorrupted(_) => CommandErrorCode::VaultCorrupted,
+            CommandErrors::VaultVersionUnsupported(_) => CommandErrorCode::VaultVersionUnsupported,
             CommandErrors::CryptoFailure(_) => CommandErrorCode::CryptoFailure,
             CommandErrors::KeyDerivationFailure(_) => CommandErrorCode::KeyDerivationFailure,
             CommandErrors::JwtFailure(_) => CommandErrorCode::JwtFailure,
+            CommandErrors::ElectrumFailure(_) => CommandErrorCode::ElectrumFailure,
             CommandErrors::Internal(_) => CommandErrorCode::Internal,
         }
     }
@@ -124,14 +211,33 @@ impl CommandErrors {
             | CommandErrors::CryptoFailure(msg)
             | CommandErrors::KeyDerivationFailure(msg)
             | CommandErrors::JwtFailure(msg)
+            | CommandErrors::ElectrumFailure(msg)
             | CommandErrors::Internal(msg) => msg.clone(),
             CommandErrors::InvalidPassword => "invalid_password".to_string(),
+            CommandErrors::SessionLocked => "session_locked".to_string(),
+            CommandErrors::VaultLocked => "vault_locked".to_string(),
+            CommandErrors::TooManyAttempts { retry_after_secs } => {
+                format!("too_many_attempts: {retry_after_secs}")
+            }
+            CommandErrors::WeakPassword(weak) => format!("weak_password: {}", weak.rule_names()),
             CommandErrors::NicknameExists => "nickname_already_exists".to_string(),
             CommandErrors::MnemonicRequired => "mnemonic_required".to_string(),
             CommandErrors::IdentityExists => "identity_already_exists".to_string(),
             CommandErrors::CountMustBePositive => "count_must_be_positive".to_string(),
             CommandErrors::SignMessageRequired => "sign_message_required".to_string(),
             CommandErrors::InvalidMnemonic(_) => "invalid_mnemonic".to_string(),
+            CommandErrors::InvalidBackup(_) => "invalid_backup".to_string(),
+            CommandErrors::InvalidShare(_) => "invalid_share".to_string(),
+            CommandErrors::BackupNotVerified => "backup_not_verified".to_string(),
+            CommandErrors::BackupMismatch => "backup_mismatch".to_string(),
+            CommandErrors::WatchOnly => "watch_only".to_string(),
+            CommandErrors::InvalidPublicKey(_) => "invalid_public_key".to_string(),
+            CommandErrors::InvalidPsbt(_) => "invalid_psbt".to_string(),
+            CommandErrors::InvalidAddress(_) => "invalid_address".to_string(),
+            CommandErrors::InvalidSessionTimeout => "invalid_session_timeout".to_string(),
+            CommandErrors::VaultVersionUnsupported(version) => {
+                format!("vault_version_unsupported: {version}")
+            }
         }
     }
 }
@@ -142,6 +248,18 @@ impl From<bip39::Error> for CommandErrors {
     }
 }
 
+impl From<WeakPassword> for CommandErrors {
+    fn from(value: WeakPassword) -> Self {
+        CommandErrors::WeakPassword(value)
+    }
+}
+
+impl From<Slip39Error> for CommandErrors {
+    fn from(value: Slip39Error) -> Self {
+        CommandErrors::invalid_share(value.to_string())
+    }
+}
+
 impl From<jsonwebtoken::errors::Error> for CommandErrors {
     fn from(value: jsonwebtoken::errors::Error) -> Self {
         CommandErrors::jwt_failed(value.to_string())
@@ -1,3 +1,5 @@
+use tauri::Manager;
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -34,8 +36,8 @@ fn logging_plugin<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
 
 mod applist;
 mod config;
-mod did;
-mod error;
+pub mod did;
+pub mod error;
 mod network;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -48,20 +50,51 @@ pub fn run() {
         .plugin(logging_plugin())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_http::init())
+        .manage(did::SessionManager::default())
+        .setup(|app| {
+            app.manage(did::VaultService::open(app.handle())?);
+            did::spawn_session_watcher(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             did::generate_mnemonic,
             did::validate_mnemonic_words,
+            did::complete_mnemonic_word,
+            did::suggest_mnemonic_repairs,
             did::derive_bucky_public_key,
             did::create_did,
             did::import_did,
+            did::import_did_from_shares,
+            did::import_watch_only_did,
+            did::split_mnemonic_shares,
+            did::issue_backup_challenge,
+            did::verify_backup_challenge,
             did::wallet_exists,
             did::list_dids,
+            did::list_vault_snapshots,
+            did::recover_vault,
+            did::export_vault_backup,
+            did::import_vault_backup,
+            did::vault_status,
+            did::enable_vault_encryption,
+            did::disable_vault_encryption,
+            did::unlock_vault,
+            did::lock_vault,
             did::active_did,
             did::set_active_did,
+            did::set_wipe_after_failures,
             did::delete_wallet,
             did::reveal_mnemonic,
+            did::change_password,
+            did::unlock_session,
+            did::lock_session,
+            did::session_status,
             did::extend_wallets,
+            did::export_account_xpubs,
+            did::sign_psbt,
+            did::sign_btc_message,
+            did::verify_btc_message,
             did::current_wallet_nickname,
             did::generate_zone_boot_config_jwt,
             did::list_sn_statuses,
@@ -72,6 +105,7 @@ pub fn run() {
             network::local_ipv4_list,
             network::scan_device_batch,
             config::get_sn_api_host,
+            config::get_password_policy,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,17 +1,32 @@
 export enum CommandErrorCodes {
     NotFound = 1001,
     InvalidPassword = 1002,
+    SessionLocked = 1003,
+    VaultLocked = 1004,
+    TooManyAttempts = 1005,
+    WeakPassword = 1006,
     NicknameExists = 1010,
     MnemonicRequired = 1011,
     IdentityExists = 1012,
     CountMustBePositive = 1013,
     SignMessageRequired = 1014,
     InvalidMnemonic = 1015,
+    InvalidBackup = 1016,
+    InvalidShare = 1017,
+    BackupNotVerified = 1018,
+    BackupMismatch = 1019,
+    WatchOnly = 1020,
+    InvalidPublicKey = 1021,
+    InvalidPsbt = 1022,
+    InvalidAddress = 1023,
+    InvalidSessionTimeout = 1024,
     StoreUnavailable = 1100,
     VaultCorrupted = 1101,
+    VaultVersionUnsupported = 1102,
     CryptoFailure = 1200,
     KeyDerivationFailure = 1201,
     JwtFailure = 1300,
+    ElectrumFailure = 1400,
     Internal = 1999,
 }
 
@@ -1,5 +1,48 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { DidInfo, WalletExtensionRequest } from "./types";
+import type {
+    AccountXpub,
+    BtcNetwork,
+    DidInfo,
+    InvalidMnemonicWord,
+    MnemonicLanguage,
+    MnemonicRepair,
+    PasswordPolicy,
+    SignedPsbt,
+    WalletExtensionRequest,
+    WatchOnlyXpub,
+} from "./types";
+
+export async function generateMnemonic(
+    language?: MnemonicLanguage,
+    wordCount?: 12 | 15 | 18 | 21 | 24,
+    strength?: 128 | 160 | 192 | 224 | 256
+): Promise<string[]> {
+    return invoke("generate_mnemonic", { language, wordCount, strength });
+}
+
+export async function validateMnemonicWords(words: string[]): Promise<InvalidMnemonicWord[]> {
+    return invoke("validate_mnemonic_words", { words });
+}
+
+export async function completeMnemonicWord(
+    prefix: string,
+    language?: MnemonicLanguage
+): Promise<string[]> {
+    return invoke("complete_mnemonic_word", { prefix, language });
+}
+
+export async function suggestMnemonicRepairs(
+    words: string[],
+    language?: MnemonicLanguage,
+    expectedDid?: string,
+    passphrase?: string
+): Promise<MnemonicRepair[]> {
+    return invoke("suggest_mnemonic_repairs", { words, language, expectedDid, passphrase });
+}
+
+export async function getPasswordPolicy(): Promise<PasswordPolicy> {
+    return invoke("get_password_policy");
+}
 
 export async function listDids(): Promise<DidInfo[]> {
     return invoke("list_dids");
@@ -13,14 +56,68 @@ export async function setActiveDid(didId: string): Promise<DidInfo> {
     return invoke("set_active_did", { didId });
 }
 
+export async function setWipeAfterFailures(
+    password: string,
+    didId: string,
+    maxFailures?: number
+): Promise<DidInfo> {
+    return invoke("set_wipe_after_failures", { password, didId, maxFailures });
+}
+
 export async function deleteDid(password: string, didId: string): Promise<void> {
     await invoke("delete_wallet", { password, didId });
 }
 
+export async function deleteWatchOnlyDid(didId: string): Promise<void> {
+    await invoke("delete_wallet", { didId });
+}
+
 export async function revealMnemonic(password: string, didId: string): Promise<string[]> {
     return invoke("reveal_mnemonic", { password, didId });
 }
 
+export async function issueBackupChallenge(password?: string, didId?: string): Promise<number[]> {
+    return invoke("issue_backup_challenge", { password, didId });
+}
+
+export async function verifyBackupChallenge(
+    answers: string[],
+    password?: string,
+    didId?: string
+): Promise<DidInfo> {
+    return invoke("verify_backup_challenge", { password, didId, answers });
+}
+
+export async function changePassword(
+    oldPassword: string,
+    newPassword: string,
+    didId?: string
+): Promise<string[]> {
+    return invoke("change_password", { oldPassword, newPassword, didId });
+}
+
+export interface DidSessionInfo {
+    did_id: string;
+    idle_timeout_secs: number;
+    expires_in_secs: number;
+}
+
+export async function unlockSession(
+    password: string,
+    didId?: string,
+    idleTimeoutSecs?: number
+): Promise<DidSessionInfo> {
+    return invoke("unlock_session", { password, didId, idleTimeoutSecs });
+}
+
+export async function lockSession(didId?: string): Promise<void> {
+    await invoke("lock_session", { didId });
+}
+
+export async function sessionStatus(): Promise<DidSessionInfo[]> {
+    return invoke("session_status");
+}
+
 export async function extendWallets(
     password: string,
     didId: string,
@@ -29,6 +126,45 @@ export async function extendWallets(
     return invoke("extend_wallets", { password, didId, request });
 }
 
+export async function exportAccountXpubs(
+    password?: string,
+    didId?: string,
+    account?: number
+): Promise<AccountXpub[]> {
+    return invoke("export_account_xpubs", { password, didId, account });
+}
+
+export async function signPsbt(
+    psbt: string,
+    password?: string,
+    didId?: string
+): Promise<SignedPsbt> {
+    return invoke("sign_psbt", { password, didId, psbt });
+}
+
+export async function signBtcMessage(
+    address: string,
+    message: string,
+    password?: string,
+    didId?: string
+): Promise<string> {
+    return invoke("sign_btc_message", { password, didId, address, message });
+}
+
+export async function verifyBtcMessage(
+    address: string,
+    message: string,
+    signature: string,
+    btcNetwork?: BtcNetwork
+): Promise<boolean> {
+    return invoke("verify_btc_message", {
+        address,
+        message,
+        signature,
+        btcNetwork,
+    });
+}
+
 export type JsonSignPayload = Record<string, unknown>;
 
 export async function signJsonWithActiveDid(
@@ -41,7 +177,120 @@ export async function signJsonWithActiveDid(
 export async function importDid(
     nickname: string,
     password: string,
-    mnemonicWords: string[]
+    mnemonicWords: string[],
+    passphrase?: string,
+    btcNetwork?: BtcNetwork,
+    electrumServer?: string
+): Promise<DidInfo> {
+    return invoke("import_did", {
+        nickname,
+        password,
+        mnemonicWords,
+        passphrase,
+        btcNetwork,
+        electrumServer,
+    });
+}
+
+export async function importWatchOnlyDid(
+    nickname: string,
+    publicKey: Record<string, unknown>,
+    btcXpubs?: WatchOnlyXpub[],
+    ethAddresses?: string[],
+    btcNetwork?: BtcNetwork
 ): Promise<DidInfo> {
-    return invoke("import_did", { nickname, password, mnemonicWords });
+    return invoke("import_watch_only_did", {
+        nickname,
+        publicKey,
+        btcXpubs,
+        ethAddresses,
+        btcNetwork,
+    });
+}
+
+export async function splitMnemonicShares(
+    password: string,
+    didId: string,
+    threshold: number,
+    shareCount: number
+): Promise<string[][]> {
+    return invoke("split_mnemonic_shares", { password, didId, threshold, shareCount });
+}
+
+export async function importDidFromShares(
+    nickname: string,
+    password: string,
+    shares: string[][],
+    passphrase?: string,
+    language?: MnemonicLanguage,
+    btcNetwork?: BtcNetwork,
+    electrumServer?: string
+): Promise<DidInfo> {
+    return invoke("import_did_from_shares", {
+        nickname,
+        password,
+        shares,
+        passphrase,
+        language,
+        btcNetwork,
+        electrumServer,
+    });
+}
+
+export interface VaultSnapshotInfo {
+    id: string;
+    created_at_ms: number;
+    did_count: number | null;
+}
+
+export async function listVaultSnapshots(): Promise<VaultSnapshotInfo[]> {
+    return invoke("list_vault_snapshots");
+}
+
+export async function recoverVault(
+    snapshotId: string,
+    password?: string,
+    didId?: string
+): Promise<DidInfo[]> {
+    return invoke("recover_vault", { snapshotId, password, didId });
+}
+
+export async function exportVaultBackup(
+    backupPassword: string,
+    didIds?: string[],
+    password?: string
+): Promise<string> {
+    return invoke("export_vault_backup", { backupPassword, didIds, password });
+}
+
+export async function importVaultBackup(
+    backup: string,
+    backupPassword: string
+): Promise<DidInfo[]> {
+    return invoke("import_vault_backup", { backup, backupPassword });
+}
+
+export interface VaultStatus {
+    sealed: boolean;
+    locked: boolean;
+}
+
+export async function vaultStatus(): Promise<VaultStatus> {
+    return invoke("vault_status");
+}
+
+export async function enableVaultEncryption(vaultPassword: string): Promise<void> {
+    await invoke("enable_vault_encryption", { vaultPassword });
+}
+
+export async function disableVaultEncryption(vaultPassword: string): Promise<void> {
+    await invoke("disable_vault_encryption", { vaultPassword });
+}
+
+export async function unlockVault(vaultPassword: string): Promise<DidInfo[]> {
+    return invoke("unlock_vault", { vaultPassword });
+}
+
+export async function lockVault(): Promise<void> {
+    await invoke("lock_vault");
 }
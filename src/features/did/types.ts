@@ -1,6 +1,24 @@
 export type BtcAddressType = "legacy" | "nested_segwit" | "native_segwit" | "taproot";
 
+export type BtcNetwork = "mainnet" | "testnet" | "signet" | "regtest";
+
+export type AddressChain = "receive" | "change";
+
+export type MnemonicLanguage =
+    | "english"
+    | "chinese_simplified"
+    | "chinese_traditional"
+    | "czech"
+    | "french"
+    | "italian"
+    | "japanese"
+    | "korean"
+    | "portuguese"
+    | "spanish";
+
 export interface ChainAddress {
+    account: number;
+    chain: AddressChain;
     index: number;
     address: string;
 }
@@ -19,16 +37,102 @@ export interface SnStatusInfo {
     username?: string | null;
 }
 
+export type BackupStatus =
+    | { state: "unknown" }
+    | { state: "unverified" }
+    | { state: "challenged"; positions: number[]; recheck_of?: BackupStatus }
+    | { state: "verified"; verified_at_ms: number };
+
 export interface DidInfo {
     id: string;
     nickname: string;
     btc_addresses: BtcAddress[];
     eth_addresses: ChainAddress[];
     bucky_wallets: BuckyWallet[];
+    mnemonic_language: MnemonicLanguage;
+    btc_network: BtcNetwork;
     sn_status?: SnStatusInfo | null;
+    failed_password_attempts: number;
+    wipe_after_failures?: number | null;
+    backup_status: BackupStatus;
+    watch_only: boolean;
+    redacted: boolean;
+}
+
+export interface AccountXpub {
+    address_type: BtcAddressType;
+    account: number;
+    path: string;
+    master_fingerprint: string;
+    xpub: string;
+    descriptor: string;
+    change_descriptor: string;
+}
+
+export interface PsbtInputSummary {
+    previous_output: string;
+    address: string | null;
+    amount_sats: number | null;
+    signed: boolean;
+}
+
+export interface PsbtOutputSummary {
+    address: string | null;
+    amount_sats: number;
+    is_mine: boolean;
+}
+
+export interface SignedPsbt {
+    psbt: string;
+    inputs: PsbtInputSummary[];
+    outputs: PsbtOutputSummary[];
+    fee_sats: number | null;
+    fee_verified: boolean;
+}
+
+export interface WatchOnlyXpub {
+    address_type: BtcAddressType;
+    account?: number;
+    xpub: string;
+    count?: number;
 }
 
 export type WalletExtensionRequest =
-    | { kind: "btc"; address_type: BtcAddressType; count?: number }
-    | { kind: "eth"; count?: number }
+    | {
+          kind: "btc";
+          address_type: BtcAddressType;
+          account?: number;
+          chain?: AddressChain;
+          count?: number;
+      }
+    | { kind: "eth"; account?: number; chain?: AddressChain; count?: number }
     | { kind: "bucky"; count?: number };
+
+export interface PasswordPolicy {
+    min_length: number;
+    min_entropy_bits: number;
+    reject_common: boolean;
+}
+
+export type MnemonicRepairChange =
+    | { kind: "unchanged" }
+    | {
+          kind: "substitution";
+          position: number;
+          original: string;
+          replacement: string;
+          distance?: number;
+      }
+    | { kind: "swap"; position: number };
+
+export interface MnemonicRepair {
+    words: string[];
+    change: MnemonicRepairChange;
+    matches_expected_did?: boolean;
+}
+
+export interface InvalidMnemonicWord {
+    position: number;
+    word: string;
+    suggestions: string[];
+}
@@ -3,7 +3,7 @@ import { useNavigate } from "react-router-dom";
 import { invoke } from "@tauri-apps/api/core";
 import { buckyos } from "buckyos";
 import { useI18n } from "../../i18n";
-import { importDid } from "./api";
+import { importDid, issueBackupChallenge, verifyBackupChallenge } from "./api";
 import { fetchSnStatus, registerSnAccount, setCachedSnStatus } from "../sn/snStatusManager";
 import type { DidInfo } from "./types";
 import { parseCommandError } from "../../utils/commandError";
@@ -119,10 +119,17 @@ export function useDidFlow() {
                 password,
                 mnemonicWords: mnemonic,
             });
+            // 确认页已按顺序输入全部助记词，用其中的单词回答 Rust 端的备份挑战
+            const positions = await issueBackupChallenge(password, createdDid.id);
+            const verifiedDid = await verifyBackupChallenge(
+                positions.map((position) => confirmedMnemonic[position - 1] ?? ""),
+                password,
+                createdDid.id
+            );
             await setCachedSnStatus(createdDid.id, record);
 
             setDidInfo({
-                ...createdDid,
+                ...verifiedDid,
                 nickname: record.username || normalizedName,
                 sn_status: {
                     username: record.username || normalizedName,
@@ -1,8 +1,8 @@
 import React from "react";
-import { invoke } from "@tauri-apps/api/core";
 import MobileHeader from "../../components/ui/MobileHeader";
 import GradientButton from "../../components/ui/GradientButton";
 import { useI18n } from "../../i18n";
+import { validateMnemonicWords } from "../../features/did/api";
 
 interface ImportDidProps {
     loading: boolean;
@@ -40,13 +40,13 @@ const ImportDid: React.FC<ImportDidProps> = ({ loading, error, onImport, onBack
 
         const seq = ++validationSeq.current;
         try {
-            const invalidWord = await invoke<string | null>("validate_mnemonic_words", {
-                words: completedWords,
-            });
+            const invalidWords = await validateMnemonicWords(completedWords);
             if (seq !== validationSeq.current) return;
-            if (invalidWord) {
+            if (invalidWords.length > 0) {
+                const { word, suggestions } = invalidWords[0];
+                const hint = suggestions.length > 0 ? ` (${suggestions.slice(0, 3).join(", ")}?)` : "";
                 setMnemonicWordError(
-                    t("import.error.invalid_mnemonic_word", { word: invalidWord })
+                    t("import.error.invalid_mnemonic_word", { word }) + hint
                 );
             } else {
                 setMnemonicWordError("");